@@ -0,0 +1,127 @@
+//! A scope for placing values in stack frames that keeps count of them
+//!
+//! Each [`Arena::alloc`] places its value in a new stack frame and calls a
+//! continuation with a [`StackBox`] to it, so values allocated earlier stay
+//! alive while later ones are in use. The arena counts the values and bytes
+//! that are live, which makes the stack cost of a computation visible.
+
+use core::{cell::Cell, fmt, mem};
+
+use crate::StackBox;
+
+/// A scope for placing values in stack frames
+///
+/// # Example
+/// ```
+/// use nolloc::Arena;
+///
+/// let peak = Arena::scope(|arena| {
+///     arena.alloc([0u8; 64], |mut scratch| {
+///         arena.alloc(7u64, |count| {
+///             scratch[0] = *count as u8;
+///             assert_eq!(arena.len(), 2);
+///         });
+///         assert_eq!(arena.bytes(), 64);
+///     });
+///     arena.peak_bytes()
+/// });
+///
+/// assert_eq!(peak, 72);
+/// ```
+pub struct Arena {
+    len: Cell<usize>,
+    bytes: Cell<usize>,
+    peak_bytes: Cell<usize>,
+}
+
+impl Arena {
+    /// Create an arena, call a continuation on it, and return its result
+    pub fn scope<F, R>(then: F) -> R
+    where
+        F: FnOnce(&Arena) -> R,
+    {
+        then(&Arena {
+            len: Cell::new(0),
+            bytes: Cell::new(0),
+            peak_bytes: Cell::new(0),
+        })
+    }
+    /// Place a value in this stack frame, call a continuation with a handle to
+    /// it, and return the continuation's result
+    ///
+    /// The value is dropped when the continuation returns.
+    pub fn alloc<T, F, R>(&self, value: T, then: F) -> R
+    where
+        F: FnOnce(StackBox<T>) -> R,
+    {
+        let size = mem::size_of::<T>();
+        self.len.set(self.len.get() + 1);
+        self.bytes.set(self.bytes.get() + size);
+        self.peak_bytes
+            .set(self.peak_bytes.get().max(self.bytes.get()));
+        let result = StackBox::emplace(value, then);
+        self.len.set(self.len.get() - 1);
+        self.bytes.set(self.bytes.get() - size);
+        result
+    }
+    /// Place the value returned by a function in this stack frame, call a
+    /// continuation with a handle to it, and return the continuation's result
+    pub fn alloc_with<T, G, F, R>(&self, make: G, then: F) -> R
+    where
+        G: FnOnce() -> T,
+        F: FnOnce(StackBox<T>) -> R,
+    {
+        self.alloc(make(), then)
+    }
+    /// Get the number of live values placed by the arena
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+    /// Check if the arena has no live values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Get the total size of the live values placed by the arena
+    ///
+    /// This does not count the frames themselves, which take some stack too.
+    pub fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+    /// Get the greatest total size of live values the arena has had at once
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes.get()
+    }
+}
+
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("len", &self.len())
+            .field("bytes", &self.bytes())
+            .field("peak_bytes", &self.peak_bytes())
+            .finish()
+    }
+}
+
+#[test]
+fn arena_counts_live_values() {
+    fn nest(arena: &Arena, depth: u32) -> u32 {
+        if depth == 0 {
+            return arena.len() as u32;
+        }
+        arena.alloc(depth, |value| *value + nest(arena, depth - 1))
+    }
+    Arena::scope(|arena| {
+        assert_eq!(nest(arena, 4), 4 + 3 + 2 + 1 + 4);
+        assert!(arena.is_empty());
+        assert_eq!(arena.bytes(), 0);
+        assert_eq!(arena.peak_bytes(), 16);
+        arena.alloc_with(
+            || [1u16; 3],
+            |mut array| {
+                array[1] = 5;
+                assert_eq!(*array, [1, 5, 1]);
+            },
+        );
+    });
+}
@@ -0,0 +1,116 @@
+//! Parsing command-line arguments into a [`Map`] of options and a [`List`] of
+//! positional arguments
+//!
+//! Options start with `--` and are stored by their key without it. An option
+//! may take a value as `--key=value` or `--key value`. Tokens after a lone
+//! `--` are all positional, as are tokens starting with a single `-`, so
+//! negative numbers can be passed.
+
+use core::iter::Peekable;
+
+use crate::{List, Map};
+
+/// Parse tokens into a map of options and a list of positional arguments, and
+/// call a continuation function on them
+///
+/// An option in `flags` takes no value unless it is given as `--key=value`.
+/// Any other option takes the token after it as its value, unless that token
+/// is also an option or there is none. Options without a value map to `""`.
+/// If an option is given more than once, its last value shadows the others.
+///
+/// Positional arguments are collected in order, so iterating the list yields
+/// them from first to last.
+///
+/// # Example
+/// ```
+/// use nolloc::args::parse_args;
+///
+/// let line = "led set --channel 2 --verbose --rate=50 -1";
+/// parse_args(line.split(' '), &["verbose"], |options, positional| {
+///     assert_eq!(options.get("channel"), Some(&"2"));
+///     assert_eq!(options.get("verbose"), Some(&""));
+///     assert_eq!(options.get("rate"), Some(&"50"));
+///     assert!(positional.iter().eq(&["led", "set", "-1"]));
+/// });
+/// ```
+pub fn parse_args<'t, I, F, R>(tokens: I, flags: &[&str], then: F) -> R
+where
+    I: IntoIterator<Item = &'t str>,
+    F: FnOnce(&Map<&'t str, &'t str>, &List<&'t str>) -> R,
+{
+    parse_tokens(
+        &Map::new(),
+        &List::new(),
+        tokens.into_iter().peekable(),
+        flags,
+        false,
+        then,
+    )
+}
+
+fn parse_tokens<'t, I, F, R>(
+    options: &Map<&'t str, &'t str>,
+    positional: &List<&'t str>,
+    mut tokens: Peekable<I>,
+    flags: &[&str],
+    options_ended: bool,
+    then: F,
+) -> R
+where
+    I: Iterator<Item = &'t str>,
+    F: FnOnce(&Map<&'t str, &'t str>, &List<&'t str>) -> R,
+{
+    let token = match tokens.next() {
+        Some(token) => token,
+        // The positional arguments were pushed last first
+        None => {
+            return List::collect(positional.iter().copied(), |in_order| {
+                then(options, in_order)
+            })
+        }
+    };
+    match token.strip_prefix("--") {
+        Some("") if !options_ended => parse_tokens(options, positional, tokens, flags, true, then),
+        Some(option) if !options_ended => {
+            let (key, value) = match option.split_once('=') {
+                Some(pair) => pair,
+                None if flags.contains(&option) => (option, ""),
+                None => match tokens.peek() {
+                    Some(next) if !next.starts_with("--") => (option, tokens.next().unwrap()),
+                    _ => (option, ""),
+                },
+            };
+            options.insert(key, value, |options| {
+                parse_tokens(options, positional, tokens, flags, options_ended, then)
+            })
+        }
+        _ => positional.push(token, |positional| {
+            parse_tokens(options, positional, tokens, flags, options_ended, then)
+        }),
+    }
+}
+
+#[test]
+fn args_parsing() {
+    let tokens = ["--a", "--b", "x", "--a=1", "p", "--", "--b", "-", "--c"];
+    parse_args(tokens.iter().copied(), &["c"], |options, positional| {
+        // The second `a` shadows the first
+        assert_eq!(options.len(), 3);
+        assert_eq!(options.get("a"), Some(&"1"));
+        assert_eq!(options.get("b"), Some(&"x"));
+        assert_eq!(options.get("c"), None);
+        assert!(positional.iter().eq(&["p", "--b", "-", "--c"]));
+    });
+    parse_args(
+        ["--flag", "file", "--key"].iter().copied(),
+        &["flag"],
+        |options, positional| {
+            assert_eq!(options.get("flag"), Some(&""));
+            assert_eq!(options.get("key"), Some(&""));
+            assert!(positional.iter().eq(&["file"]));
+        },
+    );
+    parse_args(None, &[], |options, positional| {
+        assert!(options.is_empty() && positional.is_empty())
+    });
+}
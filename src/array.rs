@@ -0,0 +1,112 @@
+//! Collecting iterators into arrays
+
+use core::fmt;
+
+/// The error returned when an iterator does not have exactly as many items as
+/// an array
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollectArrayError {
+    /// The iterator ran out after this many items
+    TooFew(usize),
+    /// The iterator had items left after filling the array
+    TooMany,
+}
+
+impl fmt::Display for CollectArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectArrayError::TooFew(count) => {
+                write!(f, "iterator ran out after {} items", count)
+            }
+            CollectArrayError::TooMany => f.write_str("iterator has more items than the array"),
+        }
+    }
+}
+
+/// Collect an iterator of exactly `N` items into an array
+///
+/// If the iterator has more or fewer items, the items taken from it are
+/// dropped. No more than `N + 1` items are taken.
+///
+/// # Example
+/// ```
+/// use nolloc::array::{collect_array, CollectArrayError};
+///
+/// let rgb: [u8; 3] = collect_array("255,128,0".split(',').map(|c| c.parse().unwrap())).unwrap();
+/// assert_eq!(rgb, [255, 128, 0]);
+///
+/// assert_eq!(collect_array::<_, 3>(1..3), Err(CollectArrayError::TooFew(2)));
+/// assert_eq!(collect_array::<_, 3>(1..5), Err(CollectArrayError::TooMany));
+/// ```
+pub fn collect_array<T, const N: usize>(
+    iter: impl IntoIterator<Item = T>,
+) -> Result<[T; N], CollectArrayError> {
+    let mut iter = iter.into_iter().fuse();
+    let slots: [Option<T>; N] = core::array::from_fn(|_| iter.next());
+    let count = slots.iter().take_while(|slot| slot.is_some()).count();
+    if count < N {
+        Err(CollectArrayError::TooFew(count))
+    } else if iter.next().is_some() {
+        Err(CollectArrayError::TooMany)
+    } else {
+        Ok(slots.map(|slot| slot.expect("all slots are filled")))
+    }
+}
+
+/// Collect an iterator of at most `N` items into an array, filling the rest
+/// of it with clones of a filler
+///
+/// If the iterator has more items, the items taken from it are dropped.
+///
+/// # Example
+/// ```
+/// use nolloc::array::collect_array_padded;
+///
+/// let name: [u8; 8] = collect_array_padded(b"temp".iter().copied(), b' ').unwrap();
+/// assert_eq!(&name, b"temp    ");
+/// ```
+pub fn collect_array_padded<T, const N: usize>(
+    iter: impl IntoIterator<Item = T>,
+    filler: T,
+) -> Result<[T; N], CollectArrayError>
+where
+    T: Clone,
+{
+    let mut iter = iter.into_iter().fuse();
+    let array = core::array::from_fn(|_| iter.next().unwrap_or_else(|| filler.clone()));
+    if iter.next().is_some() {
+        Err(CollectArrayError::TooMany)
+    } else {
+        Ok(array)
+    }
+}
+
+#[test]
+fn collect_array_lengths() {
+    assert_eq!(collect_array::<i32, 0>(None), Ok([]));
+    assert_eq!(
+        collect_array::<_, 0>(Some(1)),
+        Err(CollectArrayError::TooMany)
+    );
+    assert_eq!(
+        collect_array::<_, 2>(Some(1)),
+        Err(CollectArrayError::TooFew(1))
+    );
+    assert_eq!(collect_array(Some(1)), Ok([1]));
+    assert_eq!(collect_array_padded(1..3, 0), Ok([1, 2, 0, 0]));
+    assert_eq!(collect_array_padded(1..3, 0), Ok([1, 2]));
+    assert_eq!(
+        collect_array_padded::<_, 1>(1..3, 0),
+        Err(CollectArrayError::TooMany)
+    );
+    // The iterator is not resumed after it runs out
+    let mut calls = 0;
+    let flaky = core::iter::from_fn(|| {
+        calls += 1;
+        Some(calls).filter(|&n| n != 2)
+    });
+    assert_eq!(
+        collect_array::<_, 3>(flaky),
+        Err(CollectArrayError::TooFew(1))
+    );
+}
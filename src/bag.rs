@@ -0,0 +1,189 @@
+//! A growable multiset where all items exist on the stack
+//!
+//! A [`Bag`] is a [`Set`] that is read through the counts of its duplicates.
+//! Adding an item that is already in the bag shadows it with an entry that
+//! counts one more occurrence.
+
+use core::{borrow::Borrow, fmt};
+
+use crate::{set, Set};
+
+/// A growable multiset where all items exist on the stack
+///
+/// As with [`Set`], the bag with an added item cannot be accessed from
+/// the same scope. Instead, a continuation function is called on the new bag,
+/// and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Bag;
+///
+/// let words = "the cat and the dog and the bird".split(' ');
+///
+/// Bag::collect(words, |counts| {
+///     assert_eq!(counts.count("the"), 3);
+///     assert_eq!(counts.count("fish"), 0);
+///     assert_eq!(counts.most_common_n::<2>(), [Some((&"the", 3)), Some((&"and", 2))]);
+/// });
+/// ```
+pub struct Bag<'a, T> {
+    set: Set<'a, T>,
+}
+
+impl<'a, T> Bag<'a, T>
+where
+    T: PartialOrd,
+{
+    /// Create a new bag
+    pub fn new() -> Self {
+        Bag::default()
+    }
+    /// Check if the bag is empty
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+    /// Get the total number of occurrences of all items in the bag
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+    /// Get the number of distinct items in the bag
+    ///
+    /// This is an **O(1)** operation.
+    pub fn distinct_len(&self) -> usize {
+        self.set.distinct_len()
+    }
+    /// Get the underlying [`Set`] of the bag
+    pub fn as_set(&self) -> &Set<'a, T> {
+        &self.set
+    }
+    /// Get the number of occurrences of an item in the bag
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn count<Q>(&self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.set.count(item)
+    }
+    /// Check if the bag contains an item
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.set.contains(item)
+    }
+    /// Add an occurrence of an item to the bag, call a continuation on the
+    /// new bag, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn add<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Bag<T>) -> R,
+    {
+        self.set.insert(item, |set| then(&Bag { set: *set }))
+    }
+    /// Remove all occurrences of an item from the bag, call a continuation on
+    /// the new bag, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn remove_all<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Bag<T>) -> R,
+    {
+        self.set.remove(item, |set| then(&Bag { set: *set }))
+    }
+    /// Get an iterator over the distinct items of the bag and their counts,
+    /// in ascending order
+    pub fn iter_counts(&self) -> set::IterCounts<'a, T> {
+        self.set.iter_counts()
+    }
+    /// Get the `N` items with the most occurrences and their counts, most
+    /// common first
+    ///
+    /// Items with equal counts are ordered ascending. If the bag has fewer than
+    /// `N` distinct items, the remaining entries are [`None`].
+    /// This is an **O(nN)** operation for n distinct items.
+    pub fn most_common_n<const N: usize>(&self) -> [Option<(&'a T, usize)>; N] {
+        let mut top = [None; N];
+        for (item, count) in self.iter_counts() {
+            // Items come in ascending order, so earlier items win ties
+            let pos = top.iter().position(|entry| match entry {
+                Some((_, top_count)) => count > *top_count,
+                None => true,
+            });
+            if let Some(pos) = pos {
+                top[pos..].rotate_right(1);
+                top[pos] = Some((item, count));
+            }
+        }
+        top
+    }
+    /// Collect an iterator into a bag and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Bag<T>) -> R,
+    {
+        Bag::default().extend(iter, then)
+    }
+    /// Add the items of an iterator to the bag and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Bag<T>) -> R,
+    {
+        self.set.extend(iter, |set| then(&Bag { set: *set }))
+    }
+}
+
+impl<'a, T> Default for Bag<'a, T> {
+    fn default() -> Self {
+        Bag {
+            set: Set::default(),
+        }
+    }
+}
+
+impl<'a, T> Clone for Bag<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Bag<'a, T> {}
+
+impl<'a, T> fmt::Debug for Bag<'a, T>
+where
+    T: PartialOrd + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter_counts()).finish()
+    }
+}
+
+#[test]
+fn bag_counts() {
+    Bag::collect([3, 1, 3, 2, 3, 1], |bag| {
+        assert_eq!(bag.len(), 6);
+        assert_eq!(bag.distinct_len(), 3);
+        assert!(bag.iter_counts().eq([(&1, 2), (&2, 1), (&3, 3)]));
+        assert_eq!(bag.most_common_n::<0>(), []);
+        assert_eq!(
+            bag.most_common_n::<4>(),
+            [Some((&3, 3)), Some((&1, 2)), Some((&2, 1)), None]
+        );
+        bag.remove_all(3, |bag| {
+            assert_eq!(bag.count(&3), 0);
+            assert_eq!(bag.len(), 3);
+            bag.add(2, |bag| {
+                assert_eq!(bag.most_common_n::<1>(), [Some((&1, 2))]);
+            });
+        });
+    });
+}
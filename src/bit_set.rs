@@ -0,0 +1,243 @@
+//! A set of small integers stored inline as an array of bits
+
+use core::{
+    fmt,
+    iter::{FromIterator, FusedIterator},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+};
+
+/// A set of the integers below `64 * WORDS`, stored inline as an array of
+/// `WORDS` 64-bit words
+///
+/// # Example
+/// ```
+/// use nolloc::BitSet;
+///
+/// let mut dirty = BitSet::<2>::new();
+/// dirty.set(3);
+/// dirty.set(70);
+///
+/// let flushed: BitSet<2> = [3, 4].iter().copied().collect();
+///
+/// assert!(dirty.test(70));
+/// assert_eq!(dirty.count(), 2);
+/// assert!((dirty & !flushed).iter().eq([70]));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitSet<const WORDS: usize> {
+    words: [u64; WORDS],
+}
+
+impl<const WORDS: usize> BitSet<WORDS> {
+    /// Create a new empty set
+    pub fn new() -> Self {
+        BitSet { words: [0; WORDS] }
+    }
+    /// Get the number of integers the set can hold
+    pub fn capacity(&self) -> usize {
+        64 * WORDS
+    }
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+    /// Get the number of integers in the set
+    ///
+    /// This is an **O(WORDS)** operation.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+    /// Get the word index and bit mask of an integer
+    fn locate(&self, index: usize) -> (usize, u64) {
+        assert!(
+            index < self.capacity(),
+            "bit {} is out of bounds of a set of capacity {}",
+            index,
+            self.capacity()
+        );
+        (index / 64, 1 << (index % 64))
+    }
+    /// Add an integer to the set
+    ///
+    /// Returns whether the integer was not already in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer is not less than the set's capacity.
+    pub fn set(&mut self, index: usize) -> bool {
+        let (word, mask) = self.locate(index);
+        let was_clear = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        was_clear
+    }
+    /// Remove an integer from the set
+    ///
+    /// Returns whether the integer was in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer is not less than the set's capacity.
+    pub fn clear(&mut self, index: usize) -> bool {
+        let (word, mask) = self.locate(index);
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        was_set
+    }
+    /// Check if an integer is in the set
+    ///
+    /// Integers not less than the set's capacity are never in it.
+    pub fn test(&self, index: usize) -> bool {
+        index < self.capacity() && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+    /// Remove all integers from the set
+    pub fn clear_all(&mut self) {
+        self.words = [0; WORDS];
+    }
+    /// Get the underlying words of the set
+    ///
+    /// Integer `i` is bit `i % 64` of word `i / 64`.
+    pub fn words(&self) -> &[u64; WORDS] {
+        &self.words
+    }
+    /// Get an iterator over the integers in the set in ascending order
+    pub fn iter(&self) -> Iter<'_, WORDS> {
+        Iter {
+            words: &self.words,
+            index: 0,
+            word: self.words.first().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// An iterator over the integers in a [`BitSet`]
+///
+/// This is returned by [`BitSet::iter`].
+#[derive(Clone)]
+pub struct Iter<'a, const WORDS: usize> {
+    words: &'a [u64; WORDS],
+    /// The index of the current word
+    index: usize,
+    /// The bits of the current word not yet yielded
+    word: u64,
+}
+
+impl<'a, const WORDS: usize> Iterator for Iter<'a, WORDS> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(64 * self.index + bit)
+    }
+}
+
+impl<'a, const WORDS: usize> FusedIterator for Iter<'a, WORDS> {}
+
+impl<'a, const WORDS: usize> IntoIterator for &'a BitSet<WORDS> {
+    type Item = usize;
+    type IntoIter = Iter<'a, WORDS>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<const WORDS: usize> Extend<usize> for BitSet<WORDS> {
+    /// Add the integers of an iterator to the set
+    ///
+    /// # Panics
+    ///
+    /// Panics if any integer is not less than the set's capacity.
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for index in iter {
+            self.set(index);
+        }
+    }
+}
+
+impl<const WORDS: usize> FromIterator<usize> for BitSet<WORDS> {
+    /// Collect the integers of an iterator into a set
+    ///
+    /// # Panics
+    ///
+    /// Panics if any integer is not less than the set's capacity.
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const WORDS: usize> Default for BitSet<WORDS> {
+    fn default() -> Self {
+        BitSet::new()
+    }
+}
+
+macro_rules! bit_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident, $tok:tt) => {
+        impl<const WORDS: usize> $op_assign for BitSet<WORDS> {
+            fn $method_assign(&mut self, other: Self) {
+                for (a, b) in self.words.iter_mut().zip(other.words) {
+                    *a = *a $tok b;
+                }
+            }
+        }
+
+        impl<const WORDS: usize> $op for BitSet<WORDS> {
+            type Output = Self;
+            fn $method(mut self, other: Self) -> Self {
+                self.$method_assign(other);
+                self
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl<const WORDS: usize> Not for BitSet<WORDS> {
+    type Output = Self;
+    fn not(mut self) -> Self {
+        for word in &mut self.words {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+impl<const WORDS: usize> fmt::Debug for BitSet<WORDS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn bit_set_ops() {
+    let mut a = BitSet::<3>::new();
+    assert!(a.set(0));
+    assert!(a.set(63));
+    assert!(a.set(64));
+    assert!(a.set(191));
+    assert!(!a.set(64));
+    assert!(!a.test(192));
+    assert!(a.iter().eq([0, 63, 64, 191]));
+    let b: BitSet<3> = [63, 100, 191].iter().copied().collect();
+    assert!((a & b).iter().eq([63, 191]));
+    assert!((a | b).iter().eq([0, 63, 64, 100, 191]));
+    assert!((a ^ b).iter().eq([0, 64, 100]));
+    assert_eq!((!a).count(), 192 - 4);
+    assert!(a.clear(63));
+    assert!(!a.clear(63));
+    assert_eq!(a.count(), 3);
+    a.clear_all();
+    assert!(a.is_empty());
+    assert_eq!(BitSet::<0>::new().iter().next(), None);
+}
@@ -0,0 +1,191 @@
+//! A probabilistic set stored inline as an array of bits
+//!
+//! A [`Bloom`] filter never reports that an inserted item is missing, but it may
+//! report that a missing item is present. It is useful for cheaply ruling out
+//! items before a more expensive lookup.
+
+use core::{
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+};
+
+/// A probabilistic set stored inline as `BYTES` bytes of bits, setting `K` bits
+/// for each item
+///
+/// Items are hashed once with a [`BuildHasher`], which is [`FnvHasher`] by default,
+/// and the `K` bits are derived from that hash.
+///
+/// # Example
+/// ```
+/// use nolloc::Bloom;
+///
+/// let mut seen = Bloom::<64, 3>::new();
+///
+/// assert!(seen.insert(&17u32));
+/// assert!(!seen.insert(&17u32));
+/// assert!(seen.maybe_contains(&17u32));
+/// assert!(!seen.maybe_contains(&9u32));
+/// ```
+#[derive(Clone, Copy)]
+pub struct Bloom<const BYTES: usize, const K: usize, S = BuildHasherDefault<FnvHasher>> {
+    bytes: [u8; BYTES],
+    hasher: S,
+}
+
+impl<const BYTES: usize, const K: usize, S> Bloom<BYTES, K, S>
+where
+    S: Default,
+{
+    /// Create a new empty filter
+    pub fn new() -> Self {
+        Bloom::with_hasher(S::default())
+    }
+}
+
+impl<const BYTES: usize, const K: usize, S> Bloom<BYTES, K, S> {
+    /// Create a new empty filter that hashes items with the given [`BuildHasher`]
+    pub fn with_hasher(hasher: S) -> Self {
+        Bloom {
+            bytes: [0; BYTES],
+            hasher,
+        }
+    }
+    /// Check if no items have been inserted into the filter
+    pub fn is_empty(&self) -> bool {
+        self.bytes.iter().all(|&byte| byte == 0)
+    }
+    /// Get the number of bits that are set in the filter
+    pub fn count_ones(&self) -> usize {
+        self.bytes
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+    /// Remove all items from the filter
+    pub fn clear(&mut self) {
+        self.bytes = [0; BYTES];
+    }
+}
+
+impl<const BYTES: usize, const K: usize, S> Bloom<BYTES, K, S>
+where
+    S: BuildHasher,
+{
+    /// Get the byte indices and bit masks of the bits for an item
+    fn bits<T>(&self, item: &T) -> impl Iterator<Item = (usize, u8)>
+    where
+        T: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(item);
+        // Derive the bits from two halves of the hash, as in
+        // "Less Hashing, Same Performance" by Kirsch and Mitzenmacher
+        let (a, b) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let bit_count = 8 * BYTES as u64;
+        (0..K as u64).map(move |i| {
+            let bit = a.wrapping_add(i.wrapping_mul(b)) % bit_count;
+            ((bit / 8) as usize, 1 << (bit % 8))
+        })
+    }
+    /// Insert an item into the filter
+    ///
+    /// Returns whether the item was definitely not in the filter before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `BYTES` is 0.
+    pub fn insert<T>(&mut self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        assert!(BYTES > 0, "a bloom filter must have at least one byte");
+        let mut new = false;
+        for (byte, mask) in self.bits(item) {
+            new |= self.bytes[byte] & mask == 0;
+            self.bytes[byte] |= mask;
+        }
+        new
+    }
+    /// Check if an item may be in the filter
+    ///
+    /// If this returns `false`, the item was never inserted. If this returns
+    /// `true`, the item was probably inserted.
+    pub fn maybe_contains<T>(&self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        BYTES > 0
+            && self
+                .bits(item)
+                .all(|(byte, mask)| self.bytes[byte] & mask != 0)
+    }
+}
+
+impl<const BYTES: usize, const K: usize, S> Default for Bloom<BYTES, K, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Bloom::new()
+    }
+}
+
+impl<const BYTES: usize, const K: usize, S> fmt::Debug for Bloom<BYTES, K, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bloom")
+            .field("bits", &(8 * BYTES))
+            .field("hashes", &K)
+            .field("ones", &self.count_ones())
+            .finish()
+    }
+}
+
+/// A [`Hasher`] that implements the 64-bit FNV-1a algorithm
+///
+/// It is fast for small inputs, but it is not resistant to collisions
+/// chosen by an attacker.
+#[derive(Clone, Copy)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl fmt::Debug for FnvHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnvHasher").field(&self.0).finish()
+    }
+}
+
+#[test]
+fn bloom_no_false_negatives() {
+    let mut bloom = Bloom::<128, 4>::new();
+    assert!(bloom.is_empty());
+    for i in (0..200u32).step_by(2) {
+        bloom.insert(&i);
+        assert!(!bloom.insert(&i));
+    }
+    assert!((0..200u32).step_by(2).all(|i| bloom.maybe_contains(&i)));
+    let false_positives = (1..200u32)
+        .step_by(2)
+        .filter(|i| bloom.maybe_contains(i))
+        .count();
+    assert!(false_positives < 20, "{} false positives", false_positives);
+    bloom.insert("x");
+    assert!(bloom.maybe_contains("x"));
+    bloom.clear();
+    assert!(!bloom.maybe_contains(&0u32));
+    assert!(!Bloom::<0, 2>::new().maybe_contains(&0u32));
+}
@@ -0,0 +1,500 @@
+//! A growable B-tree map where all items exist on the stack
+//!
+//! A [`BTreeMap`] stores up to `B` entries in each node of its search tree,
+//! so it is shallower than the binary tree of a [`Map`](crate::Map). Inserting
+//! copies the nodes on the path to the new entry, splitting any that overflow.
+
+use core::{borrow::Borrow, cmp::Ordering, fmt, iter::FusedIterator, ops::Index};
+
+use crate::tree::{self, MAX_HEIGHT};
+
+/// A growable key-value map with up to `B` entries per node where all items
+/// exist on the stack
+///
+/// As with [`Map`](crate::Map), the map with an inserted entry cannot be
+/// accessed from the same scope. Instead, a continuation function is called
+/// on the new map, and its result is returned to the calling scope.
+///
+/// Unlike [`Map`](crate::Map), inserting a key that is already in the map
+/// replaces its value in the new map, so every key appears once.
+///
+/// `B` must be at least 2. Larger nodes make the tree shallower, but each
+/// insertion copies `B` entries per level onto the stack.
+///
+/// # Example
+/// ```
+/// use nolloc::BTreeMap;
+///
+/// let scores = [("cam", 7), ("ada", 9), ("bo", 4), ("ada", 6)];
+///
+/// BTreeMap::<_, _, 8>::collect(scores, |scores| {
+///     assert_eq!(scores.len(), 3);
+///     assert_eq!(scores[&"ada"], 6);
+///     assert!(scores.keys().eq(&["ada", "bo", "cam"]));
+/// });
+/// ```
+pub struct BTreeMap<'a, K, V, const B: usize> {
+    root: Option<&'a BNode<'a, K, V, B>>,
+    len: usize,
+    depth: usize,
+}
+
+struct BNode<'a, K, V, const B: usize> {
+    /// The child before the first entry, or `None` in a leaf
+    first: Option<&'a BNode<'a, K, V, B>>,
+    /// The node's entries in ascending order, each with the child after it
+    slots: [Option<Slot<'a, K, V, B>>; B],
+    len: usize,
+}
+
+type Slot<'a, K, V, const B: usize> = (&'a (K, V), Option<&'a BNode<'a, K, V, B>>);
+
+impl<'a, K, V, const B: usize> BNode<'a, K, V, B> {
+    fn slot(&self, i: usize) -> Slot<'a, K, V, B> {
+        self.slots[i].expect("slot is in the node")
+    }
+    fn slots(&self) -> impl Iterator<Item = Slot<'a, K, V, B>> + '_ {
+        self.slots[..self.len].iter().flatten().copied()
+    }
+    /// Get the child before the `i`th entry
+    fn child(&self, i: usize) -> Option<&'a BNode<'a, K, V, B>> {
+        match i {
+            0 => self.first,
+            _ => self.slot(i - 1).1,
+        }
+    }
+    fn set_child(&mut self, i: usize, child: &'a BNode<'a, K, V, B>) {
+        match i {
+            0 => self.first = Some(child),
+            _ => {
+                if let Some(slot) = &mut self.slots[i - 1] {
+                    slot.1 = Some(child);
+                }
+            }
+        }
+    }
+    /// Find the entry for a key, or the index of the child it would be under
+    fn position<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        for (i, (entry, _)) in self.slots().enumerate() {
+            match tree::compare(key, entry.0.borrow()) {
+                Ordering::Less => return Err(i),
+                Ordering::Equal => return Ok(i),
+                Ordering::Greater => {}
+            }
+        }
+        Err(self.len)
+    }
+    /// Insert a slot before the `i`th entry, splitting the node if it is full
+    fn insert(&self, i: usize, slot: Slot<'a, K, V, B>) -> Grown<'a, K, V, B> {
+        let mut slots = self
+            .slots()
+            .take(i)
+            .chain(Some(slot))
+            .chain(self.slots().skip(i));
+        let len = self.len + 1;
+        if len <= B {
+            return Grown::One(BNode::fill(self.first, &mut slots, len));
+        }
+        let left = BNode::fill(self.first, &mut slots, len / 2);
+        let (median, first) = slots.next().expect("split node has a median");
+        let right = BNode::fill(first, &mut slots, len - len / 2 - 1);
+        Grown::Split(left, median, right)
+    }
+    fn fill<I>(first: Option<&'a BNode<'a, K, V, B>>, slots: &mut I, len: usize) -> Self
+    where
+        I: Iterator<Item = Slot<'a, K, V, B>>,
+    {
+        let mut node = BNode {
+            first,
+            slots: [None; B],
+            len,
+        };
+        for (dest, slot) in node.slots.iter_mut().zip(slots.take(len)) {
+            *dest = Some(slot);
+        }
+        node
+    }
+}
+
+/// The copy of a node an entry was inserted under, which may have been split
+enum Grown<'a, K, V, const B: usize> {
+    One(BNode<'a, K, V, B>),
+    Split(BNode<'a, K, V, B>, &'a (K, V), BNode<'a, K, V, B>),
+}
+
+/// A borrowed [`Grown`]
+enum Built<'a, K, V, const B: usize> {
+    One(&'a BNode<'a, K, V, B>),
+    Split(&'a BNode<'a, K, V, B>, &'a (K, V), &'a BNode<'a, K, V, B>),
+}
+
+type BuiltThen<'t, K, V, R, const B: usize> = dyn FnMut(Built<'_, K, V, B>) -> R + 't;
+
+fn finish<K, V, R, const B: usize>(
+    grown: Grown<'_, K, V, B>,
+    then: &mut BuiltThen<K, V, R, B>,
+) -> R {
+    match &grown {
+        Grown::One(node) => then(Built::One(node)),
+        Grown::Split(left, median, right) => then(Built::Split(left, median, right)),
+    }
+}
+
+/// Insert an entry under a node and call a continuation on the node's copy
+fn insert_into<'b, K, V, R, const B: usize>(
+    node: &'b BNode<'b, K, V, B>,
+    entry: &'b (K, V),
+    then: &mut BuiltThen<K, V, R, B>,
+) -> R
+where
+    K: PartialOrd,
+{
+    match node.position(&entry.0) {
+        Ok(i) => {
+            let mut copy = *node;
+            copy.slots[i] = Some((entry, node.slot(i).1));
+            finish(Grown::One(copy), then)
+        }
+        Err(i) => match node.child(i) {
+            None => finish(node.insert(i, (entry, None)), then),
+            Some(child) => insert_into(child, entry, &mut |built| {
+                let mut copy = *node;
+                match built {
+                    Built::One(child) => {
+                        copy.set_child(i, child);
+                        finish(Grown::One(copy), then)
+                    }
+                    Built::Split(left, median, right) => {
+                        copy.set_child(i, left);
+                        finish(copy.insert(i, (median, Some(right))), then)
+                    }
+                }
+            }),
+        },
+    }
+}
+
+impl<'a, K, V, const B: usize> BTreeMap<'a, K, V, B> {
+    /// Create a new map
+    pub fn new() -> Self {
+        BTreeMap::default()
+    }
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of entries in the map
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the number of levels of nodes in the map's search tree
+    ///
+    /// This is an **O(1)** operation.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+    /// Get the entry with the minimum key in the map
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn min(&self) -> Option<(&'a K, &'a V)> {
+        let mut node = self.root?;
+        while let Some(first) = node.first {
+            node = first;
+        }
+        let (entry, _) = node.slot(0);
+        Some((&entry.0, &entry.1))
+    }
+    /// Get the entry with the maximum key in the map
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn max(&self) -> Option<(&'a K, &'a V)> {
+        let mut node = self.root?;
+        loop {
+            match node.slot(node.len - 1) {
+                (_, Some(last)) => node = last,
+                (entry, None) => return Some((&entry.0, &entry.1)),
+            }
+        }
+    }
+    /// Get an iterator over the entries of the map in ascending key order
+    pub fn iter(&self) -> Iter<'a, K, V, B> {
+        let mut iter = Iter {
+            stack: [None; MAX_HEIGHT],
+            height: 0,
+            len: self.len,
+        };
+        iter.descend(self.root);
+        iter
+    }
+    /// Get an iterator over the keys of the map in ascending order
+    pub fn keys(&self) -> impl Iterator<Item = &'a K> {
+        self.iter().map(|(key, _)| key)
+    }
+    /// Get an iterator over the values of the map in ascending key order
+    pub fn values(&self) -> impl Iterator<Item = &'a V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V, const B: usize> BTreeMap<'a, K, V, B>
+where
+    K: PartialOrd,
+{
+    fn get_entry<Q>(&self, key: &Q) -> Option<&'a (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let mut node = self.root;
+        while let Some(current) = node {
+            match current.position(key) {
+                Ok(i) => return Some(current.slot(i).0),
+                Err(i) => node = current.child(i),
+            }
+        }
+        None
+    }
+    /// Check if the map contains a key
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get_entry(key).is_some()
+    }
+    /// Get the value corresponding to the key
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(&self.get_entry(key)?.1)
+    }
+    /// Get the map's own key equal to the key and its value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (key, value) = self.get_entry(key)?;
+        Some((key, value))
+    }
+    /// Insert a key-value pair into the map and call a continuation on the
+    /// new map
+    ///
+    /// If the key is already in the map, its value is replaced in the new map.
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is less than 2.
+    pub fn insert<F, R>(&self, key: K, value: V, then: F) -> R
+    where
+        F: FnOnce(&BTreeMap<K, V, B>) -> R,
+    {
+        assert!(
+            B >= 2,
+            "a b-tree map must have at least two entries per node"
+        );
+        let len = self.len + usize::from(!self.contains_key(&key));
+        let entry = (key, value);
+        let mut then = Some(then);
+        let mut build = |built: Built<'_, K, V, B>| {
+            let then = then.take().expect("continuation called more than once");
+            match built {
+                Built::One(root) => then(&BTreeMap {
+                    root: Some(root),
+                    len,
+                    depth: self.depth.max(1),
+                }),
+                Built::Split(left, median, right) => {
+                    let mut root = BNode {
+                        first: Some(left),
+                        slots: [None; B],
+                        len: 1,
+                    };
+                    root.slots[0] = Some((median, Some(right)));
+                    then(&BTreeMap {
+                        root: Some(&root),
+                        len,
+                        depth: self.depth + 1,
+                    })
+                }
+            }
+        };
+        match self.root {
+            Some(root) => insert_into(root, &entry, &mut build),
+            None => {
+                let mut leaf = BNode {
+                    first: None,
+                    slots: [None; B],
+                    len: 1,
+                };
+                leaf.slots[0] = Some((&entry, None));
+                build(Built::One(&leaf))
+            }
+        }
+    }
+    /// Collect an iterator into a map and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&BTreeMap<K, V, B>) -> R,
+    {
+        BTreeMap::default().extend(iter, then)
+    }
+    /// Extend the map with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&BTreeMap<K, V, B>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some((key, value)) = iter.next() {
+            self.insert(key, value, |map| map.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the entries of a [`BTreeMap`] in ascending key order
+///
+/// This is returned by [`BTreeMap::iter`].
+pub struct Iter<'a, K, V, const B: usize> {
+    /// The nodes being iterated and the index of the next entry in each
+    stack: [Option<(&'a BNode<'a, K, V, B>, usize)>; MAX_HEIGHT],
+    height: usize,
+    len: usize,
+}
+
+impl<'a, K, V, const B: usize> Iter<'a, K, V, B> {
+    /// Push a node and its leftmost descendants
+    fn descend(&mut self, mut node: Option<&'a BNode<'a, K, V, B>>) {
+        while let Some(current) = node {
+            self.stack[self.height] = Some((current, 0));
+            self.height += 1;
+            node = current.first;
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let top = self.height.checked_sub(1)?;
+            let (node, i) = self.stack[top]?;
+            if i < node.len {
+                let (entry, child) = node.slot(i);
+                self.stack[top] = Some((node, i + 1));
+                self.descend(child);
+                self.len -= 1;
+                return Some((&entry.0, &entry.1));
+            }
+            self.height = top;
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, const B: usize> ExactSizeIterator for Iter<'a, K, V, B> {}
+
+impl<'a, K, V, const B: usize> FusedIterator for Iter<'a, K, V, B> {}
+
+impl<'a, K, V, const B: usize> Clone for Iter<'a, K, V, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V, const B: usize> Copy for Iter<'a, K, V, B> {}
+
+impl<'a, K, V, const B: usize> IntoIterator for &BTreeMap<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, B>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const B: usize> Default for BTreeMap<'a, K, V, B> {
+    fn default() -> Self {
+        BTreeMap {
+            root: None,
+            len: 0,
+            depth: 0,
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize> Clone for BTreeMap<'a, K, V, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V, const B: usize> Copy for BTreeMap<'a, K, V, B> {}
+
+impl<'a, K, V, const B: usize> Clone for BNode<'a, K, V, B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V, const B: usize> Copy for BNode<'a, K, V, B> {}
+
+impl<'a, K, V, const B: usize> fmt::Debug for BTreeMap<'a, K, V, B>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, Q, const B: usize> Index<&Q> for BTreeMap<'a, K, V, B>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    #[track_caller]
+    fn index(&self, index: &Q) -> &Self::Output {
+        self.get(index).expect("no entry found for key")
+    }
+}
+
+#[test]
+fn btree_map_inserts() {
+    let pairs = (0..300).map(|i| ((i * 37) % 200, i));
+    BTreeMap::<_, _, 4>::collect(pairs, |map| {
+        assert_eq!(map.len(), 200);
+        assert!(map.keys().copied().eq(0..200));
+        assert!(map.depth() <= 7);
+        assert_eq!(map.min(), Some((&0, &200)));
+        assert_eq!(map.max(), Some((&199, &227)));
+        assert!((0..200).all(|k| map[&k] % 200 == (k * 173) % 200));
+        assert!(!map.contains_key(&200));
+        map.insert(0, 0, |map| {
+            assert_eq!(map.len(), 200);
+            assert_eq!(map.get(&0), Some(&0));
+        });
+    });
+}
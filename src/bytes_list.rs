@@ -0,0 +1,252 @@
+//! A growable byte buffer made of byte slice segments that exist on the stack
+
+use core::{fmt, slice};
+
+use crate::{list, List};
+
+/// A growable byte buffer made of byte slice segments that exist on the stack
+///
+/// Segments are appended with [`BytesList::push`]. As with [`List`], the
+/// buffer with an appended segment cannot be accessed from the same scope.
+/// Instead, a continuation function is called on the new buffer, and its
+/// result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::BytesList;
+///
+/// let payload = b"ping";
+/// let header = [0x01, payload.len() as u8];
+/// BytesList::collect([&header[..], payload, b"\n"], |frame| {
+///     assert_eq!(frame.total_len(), 7);
+///     assert_eq!(*frame, b"\x01\x04ping\n"[..]);
+///
+///     let mut buffer = [0; 16];
+///     let len = frame.copy_to_slice(&mut buffer);
+///     assert_eq!(buffer[..len], b"\x01\x04ping\n"[..]);
+/// });
+/// ```
+#[derive(Default)]
+pub struct BytesList<'a> {
+    /// The segments, last first
+    segments: List<'a, &'a [u8]>,
+    len: usize,
+}
+
+impl<'a> BytesList<'a> {
+    /// Create a new empty buffer
+    pub fn new() -> Self {
+        BytesList::default()
+    }
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the total number of bytes in the buffer
+    ///
+    /// This is an **O(1)** operation.
+    pub fn total_len(&self) -> usize {
+        self.len
+    }
+    /// Get the number of segments in the buffer
+    ///
+    /// This is an **O(1)** operation.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+    /// Append a segment to the buffer and call a continuation function
+    pub fn push<F, R>(&self, bytes: &'a [u8], then: F) -> R
+    where
+        F: FnOnce(&BytesList) -> R,
+    {
+        self.segments.push(bytes, |segments| {
+            then(&BytesList {
+                segments: *segments,
+                len: self.len + bytes.len(),
+            })
+        })
+    }
+    /// Get an iterator over the segments of the buffer, last first
+    pub fn segments_rev(&self) -> list::Iter<'a, &'a [u8]> {
+        self.segments.iter()
+    }
+    /// Call a continuation function on an iterator over the segments of the
+    /// buffer in order
+    ///
+    /// The segments are reversed on the stack, so this is an **O(m)**
+    /// operation for m segments that uses stack space for each of them.
+    pub fn segments<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(Segments) -> R,
+    {
+        self.segments.reverse(|segments| {
+            then(Segments {
+                segments: segments.iter(),
+            })
+        })
+    }
+    /// Call a continuation function on an iterator over the bytes of the buffer
+    ///
+    /// The segments are reversed on the stack, so this is an **O(n + m)**
+    /// operation for n bytes in m segments.
+    pub fn bytes<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(Bytes) -> R,
+    {
+        self.segments(|segments| {
+            then(Bytes {
+                segments,
+                bytes: [].iter(),
+            })
+        })
+    }
+    /// Copy the bytes of the buffer into the start of a slice and return the
+    /// number of bytes copied
+    ///
+    /// This is an **O(n)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slice is shorter than the buffer.
+    pub fn copy_to_slice(&self, dest: &mut [u8]) -> usize {
+        assert!(
+            self.len <= dest.len(),
+            "buffer of length {} does not fit in a slice of length {}",
+            self.len,
+            dest.len()
+        );
+        // Fill the slice from the end of the buffer, last segment first
+        let mut end = self.len;
+        for bytes in self.segments.iter() {
+            let start = end - bytes.len();
+            dest[start..end].copy_from_slice(bytes);
+            end = start;
+        }
+        self.len
+    }
+    /// Collect an iterator of segments into a buffer and call a continuation
+    /// function on it
+    pub fn collect<'s, I, F, R>(iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: IntoIterator<Item = &'s [u8]>,
+        F: FnOnce(&BytesList) -> R,
+    {
+        BytesList::default().extend(iter, then)
+    }
+    /// Append the segments of an iterator to the buffer and call a continuation
+    /// function on it
+    pub fn extend<'s, I, F, R>(&self, iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: IntoIterator<Item = &'s [u8]>,
+        F: FnOnce(&BytesList) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(bytes) = iter.next() {
+            self.push(bytes, |buffer| buffer.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the segments of a [`BytesList`] in order
+///
+/// This is passed to the continuation of [`BytesList::segments`].
+pub struct Segments<'a> {
+    segments: list::Iter<'a, &'a &'a [u8]>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<Self::Item> {
+        self.segments.next().map(|bytes| **bytes)
+    }
+}
+
+/// An iterator over the bytes of a [`BytesList`]
+///
+/// This is passed to the continuation of [`BytesList::bytes`].
+pub struct Bytes<'a> {
+    segments: Segments<'a>,
+    bytes: slice::Iter<'a, u8>,
+}
+
+impl<'a> Iterator for Bytes<'a> {
+    type Item = u8;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(&byte) = self.bytes.next() {
+                return Some(byte);
+            }
+            self.bytes = self.segments.next()?.iter();
+        }
+    }
+}
+
+impl<'a> Clone for BytesList<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for BytesList<'a> {}
+
+impl<'a> PartialEq<[u8]> for BytesList<'a> {
+    fn eq(&self, other: &[u8]) -> bool {
+        if self.len != other.len() {
+            return false;
+        }
+        // Match the segments against the end of the other slice, last first
+        let mut end = other.len();
+        self.segments.iter().all(|bytes| {
+            let start = end - bytes.len();
+            let matches = other[start..end] == **bytes;
+            end = start;
+            matches
+        })
+    }
+}
+
+impl<'a> PartialEq<&[u8]> for BytesList<'a> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> PartialEq<BytesList<'a>> for [u8] {
+    fn eq(&self, other: &BytesList<'a>) -> bool {
+        *other == *self
+    }
+}
+
+impl<'a> PartialEq<BytesList<'a>> for &[u8] {
+    fn eq(&self, other: &BytesList<'a>) -> bool {
+        *other == **self
+    }
+}
+
+impl<'a> fmt::Debug for BytesList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bytes(|bytes| f.debug_list().entries(bytes).finish())
+    }
+}
+
+#[test]
+fn bytes_list_eq() {
+    BytesList::collect([&b"ab"[..], b"", b"cd", b"e"], |bytes| {
+        assert_eq!(bytes.total_len(), 5);
+        assert_eq!(bytes.segment_count(), 4);
+        assert_eq!(*bytes, b"abcde"[..]);
+        assert_ne!(*bytes, b"abcdx"[..]);
+        assert_ne!(*bytes, b"abcd"[..]);
+        assert!(bytes.bytes(|bytes| bytes.eq(b"abcde".iter().copied())));
+        assert!(bytes.segments(|segments| segments.eq([&b"ab"[..], b"", b"cd", b"e"])));
+        assert_eq!(&b"abcde"[..], *bytes);
+        let mut dest = [0; 6];
+        assert_eq!(bytes.copy_to_slice(&mut dest), 5);
+        assert_eq!(dest, *b"abcde\0");
+    });
+    assert_eq!(BytesList::new(), &b""[..]);
+}
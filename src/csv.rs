@@ -0,0 +1,216 @@
+//! Parsing CSV records into [`List`]s of fields
+//!
+//! Fields may be quoted with `"`, in which case they can contain delimiters,
+//! newlines and quotes escaped as `""`. Fields are borrowed from the input,
+//! unless they contain escaped quotes, in which case they are unescaped into a
+//! buffer of [`MAX_ESCAPED_FIELD_LEN`] bytes.
+
+use core::{fmt, iter::FusedIterator, str};
+
+use crate::List;
+
+/// The maximum length in bytes of an unescaped field that contains escaped quotes
+pub const MAX_ESCAPED_FIELD_LEN: usize = 128;
+
+/// The error returned when parsing a CSV record fails
+///
+/// Each variant has the byte offset of the field in the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// A quoted field has no closing quote
+    UnterminatedQuote(usize),
+    /// A quoted field's closing quote is not followed by a delimiter
+    InvalidQuote(usize),
+    /// A field with escaped quotes is longer than [`MAX_ESCAPED_FIELD_LEN`] when unescaped
+    FieldTooLong(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnterminatedQuote(i) => write!(f, "unterminated quote at offset {}", i),
+            Error::InvalidQuote(i) => write!(f, "text after closing quote at offset {}", i),
+            Error::FieldTooLong(i) => write!(f, "escaped field too long at offset {}", i),
+        }
+    }
+}
+
+/// Parse a CSV record into a list of its fields, call a continuation function
+/// on it, and return its result
+///
+/// The list's head is the first field, so iterating it yields the fields in
+/// order. Each field is placed in its own stack frame.
+///
+/// # Example
+/// ```
+/// use nolloc::csv;
+///
+/// csv::parse_line(r#"12:00,"Smith, J",21.5,"says ""hi""""#, ',', |fields| {
+///     assert_eq!(fields.len(), 4);
+///     assert!(fields.iter().eq(&["12:00", "Smith, J", "21.5", r#"says "hi""#]));
+/// })
+/// .unwrap();
+/// ```
+pub fn parse_line<F, R>(line: &str, delimiter: char, then: F) -> Result<R, Error>
+where
+    F: FnOnce(&List<&str>) -> R,
+{
+    let mut then = Some(then);
+    let mut result = None;
+    fields(line, 0, delimiter, &mut |fields| {
+        let then = then.take().expect("continuation called more than once");
+        result = Some(then(fields));
+        Ok(())
+    })?;
+    Ok(result.expect("continuation was called"))
+}
+
+type FieldsThen<'t> = dyn FnMut(&List<&str>) -> Result<(), Error> + 't;
+
+/// Parse the fields of a record from a position, then push them onto a list
+/// from the last one back
+fn fields(line: &str, pos: usize, delimiter: char, then: &mut FieldsThen) -> Result<(), Error> {
+    let rest = &line[pos..];
+    let (field, escaped, end) = match rest.strip_prefix('"') {
+        Some(quoted) => {
+            let mut chars = quoted.char_indices();
+            let mut escaped = false;
+            let close = loop {
+                match chars.next() {
+                    Some((i, '"')) if quoted[i + 1..].starts_with('"') => {
+                        chars.next();
+                        escaped = true;
+                    }
+                    Some((i, '"')) => break i,
+                    Some(_) => {}
+                    None => return Err(Error::UnterminatedQuote(pos)),
+                }
+            };
+            let end = pos + close + 2;
+            if !line[end..].is_empty() && !line[end..].starts_with(delimiter) {
+                return Err(Error::InvalidQuote(pos));
+            }
+            (&quoted[..close], escaped, end)
+        }
+        None => {
+            let len = rest.find(delimiter).unwrap_or(rest.len());
+            (&rest[..len], false, pos + len)
+        }
+    };
+    let next = Some(end + delimiter.len_utf8()).filter(|_| end < line.len());
+    if escaped {
+        let mut buffer = [0; MAX_ESCAPED_FIELD_LEN];
+        let field = unescape(field, &mut buffer).ok_or(Error::FieldTooLong(pos))?;
+        push_field(line, field, next, delimiter, then)
+    } else {
+        push_field(line, field, next, delimiter, then)
+    }
+}
+
+/// Parse the fields after a field, then push it onto their list
+fn push_field(
+    line: &str,
+    field: &str,
+    next: Option<usize>,
+    delimiter: char,
+    then: &mut FieldsThen,
+) -> Result<(), Error> {
+    match next {
+        Some(next) => fields(line, next, delimiter, &mut |rest| {
+            rest.push(field, |fields| then(fields))
+        }),
+        None => List::new().push(field, |fields| then(fields)),
+    }
+}
+
+/// Replace each `""` in a quoted field with `"`, or return `None` if the
+/// result does not fit
+fn unescape<'b>(field: &str, buffer: &'b mut [u8]) -> Option<&'b str> {
+    let mut len = 0;
+    for (i, part) in field.split("\"\"").enumerate() {
+        let quote = usize::from(i > 0);
+        let out = buffer.get_mut(len..len + quote + part.len())?;
+        out[..quote].fill(b'"');
+        out[quote..].copy_from_slice(part.as_bytes());
+        len += out.len();
+    }
+    Some(str::from_utf8(&buffer[..len]).expect("unescaped field is valid UTF-8"))
+}
+
+/// Get an iterator over the records of CSV input
+///
+/// Records are separated by `\n` or `\r\n`, except inside quoted fields. A
+/// trailing newline does not start another record.
+///
+/// # Example
+/// ```
+/// use nolloc::csv;
+///
+/// let log = "time,note\r\n1,\"two\nlines\"\n2,ok\n";
+/// let mut records = csv::records(log);
+/// assert_eq!(records.next(), Some("time,note"));
+/// assert_eq!(records.next(), Some("1,\"two\nlines\""));
+/// assert_eq!(records.next(), Some("2,ok"));
+/// assert_eq!(records.next(), None);
+/// ```
+pub fn records(input: &str) -> Records<'_> {
+    Records { rest: input }
+}
+
+/// An iterator over the records of CSV input
+///
+/// This is returned by [`records`].
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let mut quoted = false;
+        let end = self.rest.bytes().position(|b| {
+            quoted ^= b == b'"';
+            b == b'\n' && !quoted
+        });
+        let (record, rest) = match end {
+            Some(end) => (&self.rest[..end], &self.rest[end + 1..]),
+            None => (self.rest, ""),
+        };
+        self.rest = rest;
+        Some(record.strip_suffix('\r').unwrap_or(record))
+    }
+}
+
+impl<'a> FusedIterator for Records<'a> {}
+
+#[test]
+fn csv_fields() {
+    let input = "a,,\"\"\n\"x\"\"\",\"y\r\nz\"\r\n;;\n\"open";
+    let mut records = records(input);
+    let mut next = || parse_line(records.next().unwrap(), ',', |fields| fields.len());
+    assert_eq!(next(), Ok(3));
+    assert_eq!(next(), Ok(2));
+    assert_eq!(next(), Ok(1));
+    assert_eq!(next(), Err(Error::UnterminatedQuote(0)));
+    parse_line("\"x\"\"\",\"y\r\nz\"", ',', |fields| {
+        assert!(fields.iter().eq(&["x\"", "y\r\nz"]));
+    })
+    .unwrap();
+    parse_line(";b;", ';', |fields| {
+        assert!(fields.iter().eq(&["", "b", ""]))
+    })
+    .unwrap();
+    assert_eq!(
+        parse_line("a,\"b\"c", ',', |_| ()),
+        Err(Error::InvalidQuote(2))
+    );
+    let mut long = crate::FixedString::<512>::new();
+    long.push('"').unwrap();
+    (0..129).for_each(|_| long.push_str("\"\"").unwrap());
+    long.push('"').unwrap();
+    assert_eq!(parse_line(&long, ',', |_| ()), Err(Error::FieldTooLong(0)));
+}
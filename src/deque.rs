@@ -0,0 +1,207 @@
+//! A growable double-ended queue where all items exist on the stack
+//!
+//! A [`Deque`] is made of two [`List`]s: one holding the items pushed to the
+//! front, with the frontmost item at its head, and one holding the items pushed
+//! to the back, with the backmost item at its head.
+
+use core::fmt;
+
+use crate::{list, List};
+
+/// A growable double-ended queue where all items exist on the stack
+///
+/// As with [`List`], the deque with a pushed item cannot be accessed from
+/// the same scope. Instead, a continuation function is called on the new deque,
+/// and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Deque;
+///
+/// Deque::new().push_back(2, |deque| {
+///     deque.push_front(1, |deque| {
+///         deque.push_back(3, |deque| {
+///             assert_eq!(deque.front(), Some(&1));
+///             assert_eq!(deque.back(), Some(&3));
+///             deque.to_list(|list| assert!(list.iter().eq(&[&1, &2, &3])));
+///         })
+///     })
+/// });
+/// ```
+pub struct Deque<'a, T> {
+    front: List<'a, T>,
+    back: List<'a, T>,
+    /// The frontmost and backmost items
+    ends: Option<(&'a T, &'a T)>,
+}
+
+impl<'a, T> Deque<'a, T> {
+    /// Create a new deque
+    pub fn new() -> Self {
+        Deque::default()
+    }
+    /// Check if the deque is empty
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_none()
+    }
+    /// Get the deque's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+    /// Get the frontmost item in the deque
+    ///
+    /// This is an **O(1)** operation.
+    pub fn front(&self) -> Option<&'a T> {
+        Some(self.ends?.0)
+    }
+    /// Get the backmost item in the deque
+    ///
+    /// This is an **O(1)** operation.
+    pub fn back(&self) -> Option<&'a T> {
+        Some(self.ends?.1)
+    }
+    /// Push an item onto the front of the deque and call a continuation function
+    pub fn push_front<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Deque<T>) -> R,
+    {
+        self.front.push(item, |front| {
+            let item = front.head().expect("item was just pushed");
+            then(&Deque {
+                front: *front,
+                back: self.back,
+                ends: Some((item, self.ends.map_or(item, |(_, back)| back))),
+            })
+        })
+    }
+    /// Push an item onto the back of the deque and call a continuation function
+    pub fn push_back<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Deque<T>) -> R,
+    {
+        self.back.push(item, |back| {
+            let item = back.head().expect("item was just pushed");
+            then(&Deque {
+                front: self.front,
+                back: *back,
+                ends: Some((self.ends.map_or(item, |(front, _)| front), item)),
+            })
+        })
+    }
+    /// Get an iterator over the items pushed to the front, from front to back
+    pub fn iter_front(&self) -> list::Iter<'a, T> {
+        self.front.iter()
+    }
+    /// Get an iterator over the items pushed to the back, from back to front
+    pub fn iter_back(&self) -> list::Iter<'a, T> {
+        self.back.iter()
+    }
+    /// Collect the items of the deque into a [`List`] in front-to-back order
+    /// and call a continuation function on it
+    ///
+    /// The items pushed to the front are reversed on the stack, so this is an
+    /// **O(n)** operation that uses stack space for each of them.
+    pub fn to_list<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&List<&T>) -> R,
+    {
+        // A list's head is the last item collected, so the front must come last
+        self.front
+            .reverse(|front| List::collect(self.back.iter().chain(front.iter().copied()), then))
+    }
+    /// Collect an iterator into a deque, pushing each item to the back,
+    /// and call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Deque;
+    ///
+    /// Deque::collect(1..=3, |deque| {
+    ///     assert_eq!(deque.len(), 3);
+    ///     assert_eq!(deque.front(), Some(&1));
+    ///     assert_eq!(deque.back(), Some(&3));
+    /// });
+    /// ```
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Deque<T>) -> R,
+    {
+        Deque::default().extend_back(iter, then)
+    }
+    /// Push the items of an iterator onto the back of the deque and call a
+    /// continuation function on it
+    pub fn extend_back<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Deque<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.push_back(item, |deque| deque.extend_back(iter, then))
+        } else {
+            then(self)
+        }
+    }
+    /// Push the items of an iterator onto the front of the deque and call a
+    /// continuation function on it
+    ///
+    /// The last item of the iterator ends up at the front.
+    pub fn extend_front<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Deque<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.push_front(item, |deque| deque.extend_front(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+impl<'a, T> Default for Deque<'a, T> {
+    fn default() -> Self {
+        Deque {
+            front: List::default(),
+            back: List::default(),
+            ends: None,
+        }
+    }
+}
+
+impl<'a, T> Clone for Deque<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Deque<'a, T> {}
+
+impl<'a, T> fmt::Debug for Deque<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deque")
+            .field("front", &self.front)
+            .field("back", &self.back)
+            .finish()
+    }
+}
+
+#[test]
+fn deque_ends() {
+    Deque::new().extend_front([3, 2, 1], |deque| {
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&3));
+        deque.extend_back([4, 5], |deque| {
+            assert_eq!(deque.len(), 5);
+            assert_eq!(deque.back(), Some(&5));
+            deque.to_list(|list| assert!(list.iter().copied().eq(&[1, 2, 3, 4, 5])));
+        });
+    });
+}
@@ -0,0 +1,219 @@
+//! Hex and Base64 encoding into stack buffers
+//!
+//! Each function writes its output into a buffer of `N` bytes and passes it
+//! to a continuation. Base64 uses the standard alphabet with `=` padding.
+
+use core::{fmt, str};
+
+use crate::fmt::Overflow;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The error returned when decoding fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The decoded bytes do not fit in the buffer
+    Overflow,
+    /// The input has a byte that is not a digit at this index
+    InvalidByte(usize),
+    /// The input has a length that no encoding can have
+    InvalidLength,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Overflow => f.write_str("decoded bytes do not fit in their buffer"),
+            DecodeError::InvalidByte(index) => write!(f, "invalid byte at index {}", index),
+            DecodeError::InvalidLength => f.write_str("invalid input length"),
+        }
+    }
+}
+
+impl From<Overflow> for DecodeError {
+    fn from(_: Overflow) -> Self {
+        DecodeError::Overflow
+    }
+}
+
+/// Encode bytes as lowercase hex into a buffer of `N` bytes, call a
+/// continuation on the [`str`](prim@str), and return its result
+///
+/// If the encoding does not fit, the continuation is not called.
+///
+/// # Example
+/// ```
+/// use nolloc::encoding::encode_hex;
+///
+/// let mac = [0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6];
+/// encode_hex::<12, _, _>(&mac, |hex| assert_eq!(hex, "001b638445e6")).unwrap();
+/// ```
+pub fn encode_hex<const N: usize, F, R>(bytes: &[u8], then: F) -> Result<R, Overflow>
+where
+    F: FnOnce(&str) -> R,
+{
+    let mut buffer = [0; N];
+    let out = buffer.get_mut(..bytes.len() * 2).ok_or(Overflow)?;
+    for (digits, byte) in out.chunks_exact_mut(2).zip(bytes) {
+        digits[0] = HEX_DIGITS[usize::from(byte >> 4)];
+        digits[1] = HEX_DIGITS[usize::from(byte & 0xf)];
+    }
+    Ok(then(str::from_utf8(out).expect("hex is ASCII")))
+}
+
+/// Decode hex into a buffer of `N` bytes, call a continuation on the bytes,
+/// and return its result
+///
+/// Both uppercase and lowercase digits are accepted.
+///
+/// # Example
+/// ```
+/// use nolloc::encoding::{decode_hex, DecodeError};
+///
+/// let sum = decode_hex::<4, _, _>("CAFEf00d", |bytes| bytes.iter().map(|&b| u32::from(b)).sum());
+/// assert_eq!(sum, Ok(0xca + 0xfe + 0xf0 + 0x0d));
+/// assert_eq!(decode_hex::<4, _, _>("c0g1", |_| ()), Err(DecodeError::InvalidByte(2)));
+/// ```
+pub fn decode_hex<const N: usize, F, R>(hex: &str, then: F) -> Result<R, DecodeError>
+where
+    F: FnOnce(&[u8]) -> R,
+{
+    let hex = hex.as_bytes();
+    if hex.len() % 2 == 1 {
+        return Err(DecodeError::InvalidLength);
+    }
+    let mut buffer = [0; N];
+    let out = buffer.get_mut(..hex.len() / 2).ok_or(Overflow)?;
+    for (i, byte) in out.iter_mut().enumerate() {
+        let digit = |j: usize| match hex[j] {
+            b'0'..=b'9' => Ok(hex[j] - b'0'),
+            b'a'..=b'f' => Ok(hex[j] - b'a' + 10),
+            b'A'..=b'F' => Ok(hex[j] - b'A' + 10),
+            _ => Err(DecodeError::InvalidByte(j)),
+        };
+        *byte = digit(2 * i)? << 4 | digit(2 * i + 1)?;
+    }
+    Ok(then(out))
+}
+
+/// Encode bytes as padded Base64 into a buffer of `N` bytes, call a
+/// continuation on the [`str`](prim@str), and return its result
+///
+/// If the encoding does not fit, the continuation is not called.
+///
+/// # Example
+/// ```
+/// use nolloc::encoding::encode_base64;
+///
+/// encode_base64::<16, _, _>(b"user:pass", |auth| assert_eq!(auth, "dXNlcjpwYXNz")).unwrap();
+/// ```
+pub fn encode_base64<const N: usize, F, R>(bytes: &[u8], then: F) -> Result<R, Overflow>
+where
+    F: FnOnce(&str) -> R,
+{
+    let mut buffer = [0; N];
+    let out = buffer
+        .get_mut(..bytes.chunks(3).len() * 4)
+        .ok_or(Overflow)?;
+    for (digits, chunk) in out.chunks_exact_mut(4).zip(bytes.chunks(3)) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for (i, digit) in digits.iter_mut().enumerate() {
+            *digit = if i <= chunk.len() {
+                BASE64_DIGITS[(group >> (18 - 6 * i) & 0x3f) as usize]
+            } else {
+                b'='
+            };
+        }
+    }
+    Ok(then(str::from_utf8(out).expect("Base64 is ASCII")))
+}
+
+/// Decode padded or unpadded Base64 into a buffer of `N` bytes, call a
+/// continuation on the bytes, and return its result
+///
+/// # Example
+/// ```
+/// use nolloc::encoding::decode_base64;
+///
+/// decode_base64::<16, _, _>("aGVsbG8=", |bytes| assert_eq!(bytes, b"hello")).unwrap();
+/// decode_base64::<16, _, _>("aGVsbG8", |bytes| assert_eq!(bytes, b"hello")).unwrap();
+/// ```
+pub fn decode_base64<const N: usize, F, R>(base64: &str, then: F) -> Result<R, DecodeError>
+where
+    F: FnOnce(&[u8]) -> R,
+{
+    let base64 = base64.as_bytes();
+    let unpadded = match base64 {
+        // Padding completes a final group of 2 or 3 digits
+        [rest @ .., b'=', b'='] if rest.len() % 4 == 2 => rest,
+        [rest @ .., b'='] if rest.len() % 4 == 3 => rest,
+        _ => base64,
+    };
+    if unpadded.len() % 4 == 1 {
+        return Err(DecodeError::InvalidLength);
+    }
+    let mut buffer = [0; N];
+    let out = buffer.get_mut(..unpadded.len() * 3 / 4).ok_or(Overflow)?;
+    for (c, (bytes, digits)) in out.chunks_mut(3).zip(unpadded.chunks(4)).enumerate() {
+        let mut group = 0u32;
+        for (i, &digit) in digits.iter().enumerate() {
+            let value = match digit {
+                b'A'..=b'Z' => digit - b'A',
+                b'a'..=b'z' => digit - b'a' + 26,
+                b'0'..=b'9' => digit - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(DecodeError::InvalidByte(4 * c + i)),
+            };
+            group |= u32::from(value) << (18 - 6 * i);
+        }
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (group >> (16 - 8 * i)) as u8;
+        }
+    }
+    Ok(then(out))
+}
+
+#[test]
+fn encoding_round_trips() {
+    let vectors = [
+        ("", "", ""),
+        ("f", "66", "Zg=="),
+        ("fo", "666f", "Zm8="),
+        ("foo", "666f6f", "Zm9v"),
+        ("foob", "666f6f62", "Zm9vYg=="),
+        ("fooba", "666f6f6261", "Zm9vYmE="),
+        ("foobar", "666f6f626172", "Zm9vYmFy"),
+    ];
+    for &(bytes, hex, base64) in &vectors {
+        encode_hex::<12, _, _>(bytes.as_bytes(), |s| assert_eq!(s, hex)).unwrap();
+        encode_base64::<8, _, _>(bytes.as_bytes(), |s| assert_eq!(s, base64)).unwrap();
+        decode_hex::<6, _, _>(hex, |b| assert_eq!(b, bytes.as_bytes())).unwrap();
+        decode_base64::<6, _, _>(base64, |b| assert_eq!(b, bytes.as_bytes())).unwrap();
+    }
+    assert_eq!(encode_hex::<3, _, _>(b"ab", |_| ()), Err(Overflow));
+    assert_eq!(
+        decode_hex::<4, _, _>("abc", |_| ()),
+        Err(DecodeError::InvalidLength)
+    );
+    assert_eq!(
+        decode_base64::<3, _, _>("Zm9vYg==", |_| ()),
+        Err(DecodeError::Overflow)
+    );
+    assert_eq!(
+        decode_base64::<8, _, _>("Zm9v!g", |_| ()),
+        Err(DecodeError::InvalidByte(4))
+    );
+    assert_eq!(
+        decode_base64::<8, _, _>("Zm9vY", |_| ()),
+        Err(DecodeError::InvalidLength)
+    );
+    assert_eq!(
+        decode_base64::<8, _, _>("Zg=", |_| ()),
+        Err(DecodeError::InvalidByte(2))
+    );
+}
@@ -0,0 +1,217 @@
+//! A mutable string with a fixed capacity that is stored inline
+
+use core::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str,
+};
+
+/// A mutable string with a fixed capacity of `N` bytes that is stored inline
+///
+/// Unlike the collections in this crate, a `FixedString` owns its contents,
+/// so it can be built inside a continuation and returned out of it by value.
+///
+/// Because this crate has no unsafe code, getting the [`str`](prim@str) of a `FixedString`
+/// validates its UTF-8, which is an **O(n)** operation.
+///
+/// # Example
+/// ```
+/// use core::fmt::Write;
+/// use nolloc::{FixedString, List};
+///
+/// let summary: FixedString<32> = List::collect([3, 1, 2], |list| {
+///     let mut s = FixedString::new();
+///     write!(s, "{} items, max {}", list.len(), list.iter().max().unwrap()).unwrap();
+///     s
+/// });
+///
+/// assert_eq!(&*summary, "3 items, max 3");
+/// ```
+#[derive(Clone, Copy)]
+pub struct FixedString<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FixedString<N> {
+    /// Create a new empty string
+    pub fn new() -> Self {
+        FixedString {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+    /// Check if the string is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the length of the string in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the capacity of the string in bytes
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the number of bytes that can still be pushed
+    pub fn remaining_capacity(&self) -> usize {
+        N - self.len
+    }
+    /// Get the string as a [`str`](prim@str)
+    ///
+    /// This is an **O(n)** operation.
+    pub fn as_str(&self) -> &str {
+        str::from_utf8(&self.bytes[..self.len]).expect("string is valid UTF-8")
+    }
+    /// Append a [`char`] to the end of the string
+    ///
+    /// If the [`char`] does not fit, the string is unchanged and the [`char`]
+    /// is returned as an error.
+    pub fn push(&mut self, c: char) -> Result<(), char> {
+        let mut buffer = [0; 4];
+        self.push_str(c.encode_utf8(&mut buffer)).map_err(|_| c)
+    }
+    /// Append a [`str`](prim@str) to the end of the string
+    ///
+    /// If the [`str`](prim@str) does not fit, the string is unchanged and the [`str`](prim@str)
+    /// is returned as an error.
+    pub fn push_str<'s>(&mut self, s: &'s str) -> Result<(), &'s str> {
+        if s.len() > self.remaining_capacity() {
+            return Err(s);
+        }
+        self.bytes[self.len..][..s.len()].copy_from_slice(s.as_bytes());
+        self.len += s.len();
+        Ok(())
+    }
+    /// Remove the last [`char`] from the string and return it
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.len -= c.len_utf8();
+        Some(c)
+    }
+    /// Shorten the string to a length in bytes
+    ///
+    /// Does nothing if the string is already that short.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is not on a [`char`] boundary.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            assert!(
+                self.as_str().is_char_boundary(len),
+                "length {} is not on a char boundary",
+                len
+            );
+            self.len = len;
+        }
+    }
+    /// Remove all contents from the string
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for FixedString<N> {
+    fn default() -> Self {
+        FixedString::new()
+    }
+}
+
+impl<const N: usize> Deref for FixedString<N> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for FixedString<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'s, const N: usize> TryFrom<&'s str> for FixedString<N> {
+    type Error = &'s str;
+    fn try_from(s: &'s str) -> Result<Self, Self::Error> {
+        let mut string = FixedString::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+}
+
+impl<const N: usize> fmt::Write for FixedString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<FixedString<M>> for FixedString<N> {
+    fn eq(&self, other: &FixedString<M>) -> bool {
+        self.bytes[..self.len] == other.bytes[..other.len]
+    }
+}
+
+impl<const N: usize> Eq for FixedString<N> {}
+
+impl<const N: usize> PartialEq<str> for FixedString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes[..self.len] == *other.as_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for FixedString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl<const N: usize, const M: usize> PartialOrd<FixedString<M>> for FixedString<N> {
+    fn partial_cmp(&self, other: &FixedString<M>) -> Option<Ordering> {
+        Some(self.bytes[..self.len].cmp(&other.bytes[..other.len]))
+    }
+}
+
+impl<const N: usize> Ord for FixedString<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bytes[..self.len].cmp(&other.bytes[..other.len])
+    }
+}
+
+impl<const N: usize> Hash for FixedString<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl<const N: usize> fmt::Debug for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[test]
+fn fixed_string_capacity() {
+    let mut s = FixedString::<6>::new();
+    assert_eq!(s.push_str("hé"), Ok(()));
+    assert_eq!(s.push_str("llo!"), Err("llo!"));
+    assert_eq!(s.push_str("ll"), Ok(()));
+    assert_eq!(s.push('ö'), Err('ö'));
+    assert_eq!(s.push('o'), Ok(()));
+    assert_eq!(s, "héllo");
+    assert_eq!(s.remaining_capacity(), 0);
+    assert_eq!(s.pop(), Some('o'));
+    s.truncate(3);
+    assert_eq!(s, "hé");
+    assert!(fmt::Write::write_str(&mut s, "wxyz").is_err());
+    assert_eq!(s, FixedString::<3>::try_from("hé").unwrap());
+}
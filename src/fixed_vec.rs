@@ -0,0 +1,287 @@
+//! A mutable vector with a fixed capacity that is stored inline
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+/// A mutable vector with a fixed capacity of `N` items that is stored inline
+///
+/// Unlike the collections in this crate, a `FixedVec` owns its items in
+/// contiguous storage, so it can be mutated, passed to slice-based APIs, and
+/// returned out of a continuation by value.
+///
+/// Because this crate has no unsafe code, unused slots hold the [`Default`]
+/// value of `T`, and creating or shrinking a `FixedVec` requires it.
+///
+/// # Example
+/// ```
+/// use nolloc::{FixedVec, List};
+///
+/// let mut sorted: FixedVec<i32, 8> = List::collect([5, 3, 8, 1], |list| {
+///     let mut vec = FixedVec::new();
+///     vec.try_extend(list.iter().copied()).unwrap();
+///     vec
+/// });
+///
+/// sorted.sort();
+/// assert_eq!(*sorted, [1, 3, 5, 8]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct FixedVec<T, const N: usize> {
+    slots: [T; N],
+    len: usize,
+}
+
+impl<T, const N: usize> FixedVec<T, N> {
+    /// Check if the vector is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the vector is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the vector's length
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the vector's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the items of the vector as a slice
+    pub fn as_slice(&self) -> &[T] {
+        &self.slots[..self.len]
+    }
+    /// Get the items of the vector as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.slots[..self.len]
+    }
+    /// Push an item onto the end of the vector
+    ///
+    /// If the vector is full, the item is returned as an error.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.slots[self.len] = item;
+        self.len += 1;
+        Ok(())
+    }
+    /// Insert an item at an index, shifting all items after it to the right
+    ///
+    /// If the vector is full, the item is returned as an error.
+    /// This is an **O(n)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the vector's length.
+    pub fn insert(&mut self, index: usize, item: T) -> Result<(), T> {
+        assert!(
+            index <= self.len,
+            "insertion index {} is out of bounds of a vector of length {}",
+            index,
+            self.len
+        );
+        self.push(item)?;
+        self.slots[index..self.len].rotate_right(1);
+        Ok(())
+    }
+    /// Push the items of an iterator onto the end of the vector
+    ///
+    /// If the vector fills up, the first item that did not fit is returned
+    /// as an error, and the rest of the iterator is not consumed.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        iter.into_iter().try_for_each(|item| self.push(item))
+    }
+}
+
+impl<T, const N: usize> FixedVec<T, N>
+where
+    T: Default,
+{
+    /// Create a new empty vector
+    pub fn new() -> Self {
+        FixedVec {
+            slots: core::array::from_fn(|_| T::default()),
+            len: 0,
+        }
+    }
+    /// Remove the last item from the vector and return it
+    pub fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        Some(mem::take(&mut self.slots[self.len]))
+    }
+    /// Remove the item at an index, shifting all items after it to the left,
+    /// and return it
+    ///
+    /// This is an **O(n)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(
+            index < self.len,
+            "removal index {} is out of bounds of a vector of length {}",
+            index,
+            self.len
+        );
+        self.slots[index..self.len].rotate_left(1);
+        self.pop().expect("vector is not empty")
+    }
+    /// Shorten the vector to a length, dropping the removed items
+    ///
+    /// Does nothing if the vector is already that short.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+    /// Remove all items from the vector
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+}
+
+impl<T, const N: usize> Default for FixedVec<T, N>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        FixedVec::new()
+    }
+}
+
+impl<T, const N: usize> Deref for FixedVec<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for FixedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for FixedVec<T, N> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> AsMut<[T]> for FixedVec<T, N> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a FixedVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut FixedVec<T, N> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<FixedVec<U, M>> for FixedVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &FixedVec<U, M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T, const N: usize> Eq for FixedVec<T, N> where T: Eq {}
+
+impl<T, U, const N: usize> PartialEq<[U]> for FixedVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<[U; M]> for FixedVec<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &[U; M]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<T, const N: usize> PartialOrd for FixedVec<T, N>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T, const N: usize> Ord for FixedVec<T, N>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T, const N: usize> Hash for FixedVec<T, N>
+where
+    T: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for FixedVec<T, N>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn fixed_vec_edits() {
+    let mut vec = FixedVec::<u8, 5>::new();
+    assert_eq!(vec.try_extend([1, 2, 4]), Ok(()));
+    assert_eq!(vec.insert(2, 3), Ok(()));
+    assert_eq!(vec.insert(0, 0), Ok(()));
+    assert_eq!(vec, [0, 1, 2, 3, 4]);
+    assert_eq!(vec.push(5), Err(5));
+    assert_eq!(vec.insert(5, 5), Err(5));
+    assert_eq!(vec.remove(1), 1);
+    assert_eq!(vec.pop(), Some(4));
+    vec.truncate(1);
+    assert_eq!(vec, [0]);
+    assert_eq!(vec.try_extend(1..), Err(5));
+    assert_eq!(vec, [0, 1, 2, 3, 4]);
+    vec.clear();
+    assert!(vec.is_empty());
+}
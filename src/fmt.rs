@@ -0,0 +1,429 @@
+//! Formatting into stack buffers
+//!
+//! [`format`] and [`format_collect!`](crate::format_collect) render
+//! [`format_args!`] into a buffer of `N` bytes and pass the resulting
+//! [`str`](prim@str) to a continuation, like `format!` without the allocation.
+//! [`str_from_chars`] does the same for an iterator of [`char`]s.
+//! [`StackWriter`] is a [`Write`] sink that can be kept and returned by value.
+//!
+//! [`write_int`], [`write_uint`] and [`write_float`] render numbers into any
+//! [`Write`] without going through [`format_args!`].
+
+use core::{
+    fmt::{self, Write},
+    str,
+};
+
+use crate::FixedString;
+
+/// Render formatting arguments into a buffer of `N` bytes, call a continuation
+/// on the [`str`](prim@str), and return its result
+///
+/// If the output does not fit in the buffer, or a formatting trait
+/// implementation returns an error, the continuation is not called.
+///
+/// # Example
+/// ```
+/// use nolloc::fmt;
+///
+/// let (id, temp) = (7, 21.5);
+/// let len = fmt::format::<32, _, _>(format_args!("sensor {}: {}C", id, temp), |s| {
+///     assert_eq!(s, "sensor 7: 21.5C");
+///     s.len()
+/// });
+/// assert_eq!(len, Ok(15));
+///
+/// let long = fmt::format::<4, _, _>(format_args!("{}", 123_456), |s| s.len());
+/// assert_eq!(long, Err(fmt::Overflow));
+/// ```
+pub fn format<const N: usize, F, R>(args: fmt::Arguments, then: F) -> Result<R, Overflow>
+where
+    F: FnOnce(&str) -> R,
+{
+    let mut s = FixedString::<N>::new();
+    s.write_fmt(args).map_err(|_| Overflow)?;
+    Ok(then(s.as_str()))
+}
+
+/// Encode the [`char`]s of an iterator into a buffer of `N` bytes, call a
+/// continuation on the [`str`](prim@str), and return its result
+///
+/// If the [`char`]s do not fit in the buffer, the continuation is not called.
+///
+/// # Example
+/// ```
+/// use nolloc::fmt;
+///
+/// let title = "Flow Rate (L/min)";
+/// let ident = title.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase());
+/// let matched = fmt::str_from_chars::<16, _, _, _>(ident, |ident| ident == "flowratelmin");
+/// assert_eq!(matched, Ok(true));
+/// ```
+pub fn str_from_chars<const N: usize, I, F, R>(iter: I, then: F) -> Result<R, Overflow>
+where
+    I: IntoIterator<Item = char>,
+    F: FnOnce(&str) -> R,
+{
+    let mut s = FixedString::<N>::new();
+    for c in iter {
+        s.push(c).map_err(|_| Overflow)?;
+    }
+    Ok(then(s.as_str()))
+}
+
+/// Format a string into a buffer of a fixed capacity and call a continuation
+/// on it
+///
+/// The capacity comes first, then the format string and its arguments, then
+/// `=>` and the continuation. The arguments are passed to [`format_args!`]
+/// as they are, so named arguments work as in `format!`. This expands to a
+/// call to [`fmt::format`](crate::fmt::format), so it returns a `Result` with
+/// the continuation's result.
+///
+/// # Example
+/// ```
+/// use nolloc::format_collect;
+///
+/// let name = "pump";
+/// let shouted = format_collect!(16; "{}-{}", name, 2 => |s| s.to_uppercase());
+/// assert_eq!(shouted.unwrap(), "PUMP-2");
+///
+/// let padded = format_collect!(16; "{name}:{id:>3}", id = 7 => |s| s.len());
+/// assert_eq!(padded, Ok(8));
+/// ```
+#[macro_export]
+macro_rules! format_collect {
+    // Move the format arguments into the brackets one token at a time until
+    // the `=>` before the continuation
+    (@args $n:expr; [$($args:tt)*] => $then:expr) => {
+        $crate::fmt::format::<{ $n }, _, _>(format_args!($($args)*), $then)
+    };
+    (@args $n:expr; [$($args:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::format_collect!(@args $n; [$($args)* $next] $($rest)*)
+    };
+    ($n:expr; $($rest:tt)*) => {
+        $crate::format_collect!(@args $n; [] $($rest)*)
+    };
+}
+
+/// The error returned when formatted output does not fit in its buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("formatted output does not fit in its buffer")
+    }
+}
+
+/// What a [`StackWriter`] does with output that does not fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    /// Return an error and leave the buffer as it was before the write
+    #[default]
+    Error,
+    /// Keep as much of the output as fits and drop the rest
+    Truncate,
+    /// Keep as much of the output as fits with `...` at the end, and drop the rest
+    Ellipsis,
+}
+
+/// A [`Write`] sink with a fixed capacity of `N` bytes that is stored inline
+///
+/// What happens when a write does not fit is decided by its [`OverflowPolicy`].
+/// Once a truncating writer has overflowed, later writes are ignored.
+///
+/// # Example
+/// ```
+/// use core::fmt::Write;
+/// use nolloc::{fmt::{OverflowPolicy, StackWriter}, List};
+///
+/// let mut w = StackWriter::<16>::with_policy(OverflowPolicy::Ellipsis);
+/// List::collect(1..=10, |list| write!(w, "{}", list)).unwrap();
+///
+/// assert_eq!(w.as_str(), "(10, 9, 8, 7,...");
+/// assert!(w.has_overflowed());
+/// ```
+#[derive(Clone, Copy)]
+pub struct StackWriter<const N: usize> {
+    buffer: FixedString<N>,
+    policy: OverflowPolicy,
+    overflowed: bool,
+}
+
+impl<const N: usize> StackWriter<N> {
+    /// Create a new empty writer that returns an error on overflow
+    pub fn new() -> Self {
+        StackWriter::with_policy(OverflowPolicy::Error)
+    }
+    /// Create a new empty writer with an overflow policy
+    pub fn with_policy(policy: OverflowPolicy) -> Self {
+        StackWriter {
+            buffer: FixedString::new(),
+            policy,
+            overflowed: false,
+        }
+    }
+    /// Get the writer's overflow policy
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+    /// Check if any output has not fit in the writer
+    pub fn has_overflowed(&self) -> bool {
+        self.overflowed
+    }
+    /// Check if nothing has been written
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Get the length of the written output in bytes
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Get the capacity of the writer in bytes
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the written output
+    ///
+    /// This is an **O(n)** operation.
+    pub fn as_str(&self) -> &str {
+        self.buffer.as_str()
+    }
+    /// Get the underlying string
+    pub fn into_inner(self) -> FixedString<N> {
+        self.buffer
+    }
+    /// Remove all output and reset the overflow flag
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.overflowed = false;
+    }
+    /// Append as much of a [`str`](prim@str) as fits in a length, cutting it at a
+    /// [`char`] boundary
+    fn push_prefix(&mut self, s: &str, max_len: usize) {
+        let available = max_len.saturating_sub(self.buffer.len());
+        let _ = self
+            .buffer
+            .push_str(&s[..floor_char_boundary(s, available)]);
+    }
+}
+
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    (0..=index)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+impl<const N: usize> Write for StackWriter<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.overflowed && self.policy != OverflowPolicy::Error {
+            return Ok(());
+        }
+        if self.buffer.push_str(s).is_ok() {
+            return Ok(());
+        }
+        self.overflowed = true;
+        match self.policy {
+            OverflowPolicy::Error => return Err(fmt::Error),
+            OverflowPolicy::Truncate => self.push_prefix(s, N),
+            OverflowPolicy::Ellipsis => {
+                const ELLIPSIS: &str = "...";
+                let max_len = N.saturating_sub(ELLIPSIS.len());
+                if self.buffer.len() > max_len {
+                    let len = floor_char_boundary(self.buffer.as_str(), max_len);
+                    self.buffer.truncate(len);
+                }
+                self.push_prefix(s, max_len);
+                self.push_prefix(ELLIPSIS, N);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for StackWriter<N> {
+    fn default() -> Self {
+        StackWriter::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for StackWriter<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for StackWriter<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Write a signed integer in decimal
+///
+/// Smaller integer types can be passed with [`Into`].
+///
+/// # Example
+/// ```
+/// use nolloc::fmt::{write_int, StackWriter};
+///
+/// let mut w = StackWriter::<8>::new();
+/// write_int(&mut w, -4096).unwrap();
+/// assert_eq!(w.as_str(), "-4096");
+/// ```
+pub fn write_int<W>(w: &mut W, n: i64) -> fmt::Result
+where
+    W: Write + ?Sized,
+{
+    if n < 0 {
+        w.write_char('-')?;
+    }
+    write_uint(w, n.unsigned_abs())
+}
+
+/// Write an unsigned integer in decimal
+///
+/// Smaller integer types can be passed with [`Into`].
+pub fn write_uint<W>(w: &mut W, n: u64) -> fmt::Result
+where
+    W: Write + ?Sized,
+{
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    let mut n = n;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    w.write_str(str::from_utf8(&digits[start..]).expect("digits are ASCII"))
+}
+
+/// Write a floating-point number with a fixed number of digits after the
+/// decimal point
+///
+/// Numbers with a precision of up to 9 digits whose scaled value fits in a
+/// [`u64`] are written with integer arithmetic. Others are written by the
+/// slower [`Display`](fmt::Display) implementation of [`f64`]. The integer
+/// path rounds halfway cases away from zero, so the last digit may differ
+/// from `{:.*}` formatting, which rounds the exact binary value.
+///
+/// [`f32`]s can be passed with [`Into`].
+///
+/// # Example
+/// ```
+/// use nolloc::fmt::{write_float, StackWriter};
+///
+/// let mut w = StackWriter::<16>::new();
+/// write_float(&mut w, -3.14159, 3).unwrap();
+/// assert_eq!(w.as_str(), "-3.142");
+/// ```
+pub fn write_float<W>(w: &mut W, x: f64, precision: usize) -> fmt::Result
+where
+    W: Write + ?Sized,
+{
+    const MAX_SCALED: f64 = (1u64 << 63) as f64;
+    if x.is_nan() {
+        return w.write_str("NaN");
+    }
+    if x.is_sign_negative() {
+        w.write_char('-')?;
+    }
+    let magnitude = if x.is_sign_negative() { -x } else { x };
+    if magnitude.is_infinite() {
+        return w.write_str("inf");
+    }
+    let scale = match precision {
+        0..=9 => 10u64.pow(precision as u32),
+        _ => return write!(w, "{:.*}", precision, magnitude),
+    };
+    let scaled = magnitude * scale as f64 + 0.5;
+    if scaled >= MAX_SCALED {
+        return write!(w, "{:.*}", precision, magnitude);
+    }
+    let scaled = scaled as u64;
+    write_uint(w, scaled / scale)?;
+    if precision > 0 {
+        w.write_char('.')?;
+        let fraction = scaled % scale;
+        // Pad the fraction with leading zeros
+        let mut place = scale / 10;
+        while place > 1 && fraction < place {
+            w.write_char('0')?;
+            place /= 10;
+        }
+        write_uint(w, fraction)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn formatting_overflow() {
+    assert_eq!(format::<3, _, _>(format_args!("{}", 123), str::len), Ok(3));
+    assert_eq!(
+        format::<3, _, _>(format_args!("{}", 1234), str::len),
+        Err(Overflow)
+    );
+    let empty = format_collect!(0; "" => |s: &str| s.is_empty());
+    assert_eq!(empty, Ok(true));
+    let chars = "añb".chars();
+    assert_eq!(str_from_chars::<4, _, _, _>(chars.clone(), str::len), Ok(4));
+    assert_eq!(str_from_chars::<3, _, _, _>(chars, str::len), Err(Overflow));
+    let x = 5;
+    assert_eq!(format_collect!(8; "{x}" => |s: &str| s == "5"), Ok(true));
+    let named = format_collect!(8; "{a}{b}", b = x, a = 1, => |s: &str| s == "15");
+    assert_eq!(named, Ok(true));
+    let mut w = StackWriter::<5>::with_policy(OverflowPolicy::Truncate);
+    assert!(w.write_str("abé").is_ok() && !w.has_overflowed());
+    assert!(write!(w, "éz").is_ok() && w.has_overflowed());
+    assert_eq!(w.as_str(), "abé");
+    let mut w = StackWriter::<4>::new();
+    assert!(w.write_str("abc").is_ok() && w.write_str("de").is_err());
+    assert_eq!(w.as_str(), "abc");
+    let mut w = StackWriter::<6>::with_policy(OverflowPolicy::Ellipsis);
+    assert!(w.write_str("abcde").is_ok() && w.write_str("fg").is_ok());
+    assert_eq!(w.as_str(), "abc...");
+    let mut w = StackWriter::<2>::with_policy(OverflowPolicy::Ellipsis);
+    assert!(w.write_str("abc").is_ok());
+    assert_eq!(w.as_str(), "..");
+    let mut w = StackWriter::<64>::new();
+    for n in [0, -7, i64::MIN, i64::MAX] {
+        w.clear();
+        write_int(&mut w, n).unwrap();
+        assert!(format::<24, _, _>(format_args!("{}", n), |s| s == w.as_str()).unwrap());
+    }
+    let floats = [
+        0.0,
+        -0.0,
+        1.0,
+        0.004,
+        2.5e-3,
+        123.456,
+        -1e18,
+        1e30,
+        0.1 + 0.2,
+    ];
+    for (x, precision) in floats.iter().flat_map(|&x| (0..12).map(move |p| (x, p))) {
+        w.clear();
+        write_float(&mut w, x, precision).unwrap();
+        let core = format::<64, _, _>(format_args!("{:.*}", precision, x), |s| s == w.as_str());
+        assert!(core.unwrap(), "{:.*} != {}", precision, x, w);
+    }
+    for (x, s) in [
+        (f64::NAN, "NaN"),
+        (f64::INFINITY, "inf"),
+        (-f64::INFINITY, "-inf"),
+    ] {
+        w.clear();
+        write_float(&mut w, x, 2).unwrap();
+        assert_eq!(w.as_str(), s);
+    }
+}
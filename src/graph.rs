@@ -0,0 +1,514 @@
+//! A growable directed graph where all nodes and edges exist on the stack
+//!
+//! A [`Graph`] is a [`Map`] from each node to the [`List`] of its successors.
+//! Adding an edge inserts the node's extended list, shadowing the old one.
+//! Traversals keep their worklists and visited sets in [`Queue`]s and
+//! [`Set`]s that grow down the stack.
+
+use core::fmt;
+
+use crate::{list, map, List, Map, Queue, Set};
+
+/// A growable directed graph where all nodes and edges exist on the stack
+///
+/// As with [`Map`], the graph with an added node or edge cannot be accessed
+/// from the same scope. Instead, a continuation function is called on the new
+/// graph, and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Graph;
+///
+/// let deps = [("app", "net"), ("app", "log"), ("net", "log"), ("net", "hal")];
+///
+/// Graph::collect(deps, |graph| {
+///     assert_eq!(graph.node_count(), 4);
+///     assert!(graph.contains_edge(&"net", &"hal"));
+///     graph.breadth_first(&"app", |order| {
+///         assert!(order.iter().eq(&[&"app", &"log", &"net", &"hal"]));
+///     });
+///     graph.depth_first(&"net", |order| {
+///         assert!(order.iter().eq(&[&"net", &"hal", &"log"]));
+///     });
+/// });
+/// ```
+pub struct Graph<'a, N> {
+    adjacency: Map<'a, N, List<'a, N>>,
+    node_count: usize,
+    edge_count: usize,
+}
+
+type VisitThen<'t, 'a, N, R> = dyn FnMut(&Set<&'a N>, &List<&'a N>) -> R + 't;
+
+impl<'a, N> Graph<'a, N> {
+    /// Create a new graph
+    pub fn new() -> Self {
+        Graph::default()
+    }
+    /// Check if the graph has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.node_count == 0
+    }
+    /// Get the number of nodes in the graph
+    ///
+    /// This is an **O(1)** operation.
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+    /// Get the number of edges in the graph
+    ///
+    /// This is an **O(1)** operation.
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+    /// Get the underlying map from each node to its successors
+    pub fn adjacency(&self) -> &Map<'a, N, List<'a, N>> {
+        &self.adjacency
+    }
+}
+
+impl<'a, N> Graph<'a, N>
+where
+    N: PartialOrd,
+{
+    /// Check if the graph contains a node
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.adjacency.contains_key(node)
+    }
+    /// Check if the graph contains an edge
+    ///
+    /// This is an **O(logn + d)** operation for a node with d successors.
+    pub fn contains_edge(&self, from: &N, to: &N) -> bool {
+        self.successors(from).any(|node| node == to)
+    }
+    /// Get an iterator over the successors of a node
+    ///
+    /// The iterator yields nodes in the opposite order of their edges' insertion.
+    /// It is empty if the node is not in the graph.
+    pub fn successors(&self, node: &N) -> list::Iter<'a, N> {
+        self.adjacency.get(node).copied().unwrap_or_default().iter()
+    }
+    /// Get an iterator over the nodes of the graph in ascending order
+    ///
+    /// Each step is an **O(logn)** operation.
+    pub fn nodes(&self) -> Nodes<'a, N> {
+        Nodes {
+            cursor: self.adjacency.cursor(),
+        }
+    }
+    /// Get the graph's own copy of a node
+    fn node(&self, node: &N) -> Option<&'a N> {
+        Some(self.adjacency.get_key_value(node)?.0)
+    }
+    /// Add a node to the graph if it is not already in it, call a continuation
+    /// on the new (or old) graph, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn add_node<F, R>(&self, node: N, then: F) -> R
+    where
+        F: FnOnce(&Graph<N>) -> R,
+    {
+        if self.contains_node(&node) {
+            return then(self);
+        }
+        self.adjacency.insert(node, List::new(), |adjacency| {
+            then(&Graph {
+                adjacency: *adjacency,
+                node_count: self.node_count + 1,
+                edge_count: self.edge_count,
+            })
+        })
+    }
+    /// Add an edge to the graph, adding its nodes if they are not already in it,
+    /// call a continuation on the new graph, and return its result
+    ///
+    /// Adding an edge that is already in the graph adds it again, so the
+    /// traversals and [`Graph::successors`] see it twice.
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn add_edge<F, R>(&self, from: N, to: N, then: F) -> R
+    where
+        N: Clone,
+        F: FnOnce(&Graph<N>) -> R,
+    {
+        let from_count = usize::from(!self.contains_node(&from));
+        let successors = self.adjacency.get(&from).copied().unwrap_or_default();
+        successors.push(to.clone(), |successors| {
+            self.adjacency.insert(from, *successors, |adjacency| {
+                Graph {
+                    adjacency: *adjacency,
+                    node_count: self.node_count + from_count,
+                    edge_count: self.edge_count + 1,
+                }
+                .add_node(to, then)
+            })
+        })
+    }
+    /// Visit the nodes reachable from a node in breadth-first order, call a
+    /// continuation on a [`List`] of them in the order they were visited,
+    /// and return its result
+    ///
+    /// Successors are visited in the order of [`Graph::successors`]. The list
+    /// is empty if the node is not in the graph.
+    ///
+    /// This is an **O(elogn)** operation for e reachable edges that uses
+    /// stack space for each reachable node and edge.
+    pub fn breadth_first<F, R>(&self, start: &N, then: F) -> R
+    where
+        F: FnOnce(&List<&N>) -> R,
+    {
+        let start = match self.node(start) {
+            Some(start) => start,
+            None => return then(&List::new()),
+        };
+        let mut then = Some(then);
+        Set::new().insert(start, |visited| {
+            Queue::new().enqueue(start, |queue| {
+                self.visit_breadth_first(queue, visited, &List::new(), &mut |_, order| {
+                    let then = then.take().expect("traversal continues once");
+                    List::collect(order.iter().copied(), then)
+                })
+            })
+        })
+    }
+    /// Visit the nodes reachable from a node in depth-first preorder, call a
+    /// continuation on a [`List`] of them in the order they were visited,
+    /// and return its result
+    ///
+    /// Successors are visited in the order of [`Graph::successors`]. The list
+    /// is empty if the node is not in the graph.
+    ///
+    /// This is an **O(elogn)** operation for e reachable edges that uses
+    /// stack space for each reachable node and edge.
+    pub fn depth_first<F, R>(&self, start: &N, then: F) -> R
+    where
+        F: FnOnce(&List<&N>) -> R,
+    {
+        let start = match self.node(start) {
+            Some(start) => start,
+            None => return then(&List::new()),
+        };
+        let mut then = Some(then);
+        self.visit_depth_first(start, &Set::new(), &List::new(), &mut |_, order| {
+            let then = then.take().expect("traversal continues once");
+            List::collect(order.iter().copied(), then)
+        })
+    }
+    /// Visit the node at the front of the queue and enqueue its unvisited successors
+    ///
+    /// `order` holds the visited nodes, most recent first.
+    fn visit_breadth_first<R>(
+        &self,
+        queue: &Queue<&'a N>,
+        visited: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, R>,
+    ) -> R {
+        queue.dequeue(|queue, node| match node {
+            Some(&node) => order.push(node, |order| {
+                self.enqueue_successors(self.successors(node), queue, visited, order, then)
+            }),
+            None => then(visited, order),
+        })
+    }
+    fn enqueue_successors<R>(
+        &self,
+        mut successors: list::Iter<'a, N>,
+        queue: &Queue<&'a N>,
+        visited: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, R>,
+    ) -> R {
+        match successors.find(|node| !visited.contains(node)) {
+            Some(next) => visited.insert(next, |visited| {
+                queue.enqueue(next, |queue| {
+                    self.enqueue_successors(successors, queue, visited, order, then)
+                })
+            }),
+            None => self.visit_breadth_first(queue, visited, order, then),
+        }
+    }
+    /// Visit a node and then its unvisited successors
+    ///
+    /// `order` holds the visited nodes, most recent first.
+    fn visit_depth_first<R>(
+        &self,
+        node: &'a N,
+        visited: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, R>,
+    ) -> R {
+        visited.insert(node, |visited| {
+            order.push(node, |order| {
+                self.visit_successors(self.successors(node), visited, order, then)
+            })
+        })
+    }
+    fn visit_successors<R>(
+        &self,
+        mut successors: list::Iter<'a, N>,
+        visited: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, R>,
+    ) -> R {
+        match successors.find(|node| !visited.contains(node)) {
+            Some(next) => self.visit_depth_first(next, visited, order, &mut |visited, order| {
+                self.visit_successors(successors.clone(), visited, order, then)
+            }),
+            None => then(visited, order),
+        }
+    }
+    /// Order the nodes of the graph so that every edge goes from an earlier
+    /// node to a later one, call a continuation on a [`List`] of them in that
+    /// order, and return its result
+    ///
+    /// Returns a [`Cycle`] error without calling the continuation if there is
+    /// no such order.
+    ///
+    /// This is an **O(elogn)** operation for e edges that uses stack space
+    /// for each node and edge.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Graph;
+    ///
+    /// // Each driver must start before the drivers that use it
+    /// let uses = [("clock", "gpio"), ("gpio", "uart"), ("clock", "uart"), ("uart", "console")];
+    ///
+    /// Graph::collect(uses, |drivers| {
+    ///     let order = drivers.topo_sort(|order| order.iter().copied().copied().collect::<Vec<_>>());
+    ///     assert_eq!(order.unwrap(), ["clock", "gpio", "uart", "console"]);
+    ///     drivers.add_edge("console", "clock", |drivers| {
+    ///         assert!(drivers.topo_sort(|_| ()).is_err());
+    ///     });
+    /// });
+    /// ```
+    pub fn topo_sort<F, R>(&self, then: F) -> Result<R, Cycle<'a, N>>
+    where
+        F: FnOnce(&List<&N>) -> R,
+    {
+        let mut then = Some(then);
+        self.sort_from(self.nodes(), &Set::new(), &List::new(), &mut |_, order| {
+            let then = then.take().expect("sort continues once");
+            Ok(then(order))
+        })
+    }
+    /// Finish the nodes reachable from each root that is not already finished
+    ///
+    /// `order` holds the finished nodes, most recently finished first.
+    fn sort_from<R>(
+        &self,
+        mut roots: Nodes<'a, N>,
+        finished: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, Result<R, Cycle<'a, N>>>,
+    ) -> Result<R, Cycle<'a, N>> {
+        match roots.find(|node| !finished.contains(node)) {
+            Some(root) => self.finish(
+                root,
+                &Set::new(),
+                finished,
+                order,
+                &mut |finished, order| self.sort_from(roots.clone(), finished, order, then),
+            ),
+            None => then(finished, order),
+        }
+    }
+    /// Finish the successors of a node and then the node itself
+    ///
+    /// `path` holds the nodes being finished that lead to this one.
+    fn finish<R>(
+        &self,
+        node: &'a N,
+        path: &Set<&'a N>,
+        finished: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, Result<R, Cycle<'a, N>>>,
+    ) -> Result<R, Cycle<'a, N>> {
+        path.insert(node, |path| {
+            let successors = self.successors(node);
+            self.finish_successors(successors, path, finished, order, &mut |finished, order| {
+                finished.insert(node, |finished| {
+                    order.push(node, |order| then(finished, order))
+                })
+            })
+        })
+    }
+    fn finish_successors<R>(
+        &self,
+        mut successors: list::Iter<'a, N>,
+        path: &Set<&'a N>,
+        finished: &Set<&'a N>,
+        order: &List<&'a N>,
+        then: &mut VisitThen<'_, 'a, N, Result<R, Cycle<'a, N>>>,
+    ) -> Result<R, Cycle<'a, N>> {
+        match successors.find(|node| !finished.contains(node)) {
+            Some(next) if path.contains(next) => Err(Cycle { node: next }),
+            Some(next) => self.finish(next, path, finished, order, &mut |finished, order| {
+                self.finish_successors(successors.clone(), path, finished, order, then)
+            }),
+            None => then(finished, order),
+        }
+    }
+    /// Collect an iterator of edges into a graph and call a continuation function on it
+    pub fn collect<I, F, R>(edges: I, then: F) -> R
+    where
+        N: Clone,
+        I: IntoIterator<Item = (N, N)>,
+        F: FnOnce(&Graph<N>) -> R,
+    {
+        Graph::default().extend(edges, then)
+    }
+    /// Add the edges of an iterator to the graph and call a continuation function on it
+    pub fn extend<I, F, R>(&self, edges: I, then: F) -> R
+    where
+        N: Clone,
+        I: IntoIterator<Item = (N, N)>,
+        F: FnOnce(&Graph<N>) -> R,
+    {
+        let mut edges = edges.into_iter();
+        if let Some((from, to)) = edges.next() {
+            self.add_edge(from, to, |graph| graph.extend(edges, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// The error returned by [`Graph::topo_sort`] when the graph has a cycle
+pub struct Cycle<'a, N> {
+    node: &'a N,
+}
+
+impl<'a, N> Cycle<'a, N> {
+    /// Get a node on the cycle
+    pub fn node(&self) -> &'a N {
+        self.node
+    }
+}
+
+impl<'a, N> Clone for Cycle<'a, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, N> Copy for Cycle<'a, N> {}
+
+impl<'a, N> fmt::Debug for Cycle<'a, N>
+where
+    N: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cycle").field("node", self.node).finish()
+    }
+}
+
+impl<'a, N> fmt::Display for Cycle<'a, N>
+where
+    N: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph has a cycle through {:?}", self.node)
+    }
+}
+
+/// An iterator over the nodes of a [`Graph`] in ascending order
+///
+/// This is returned by [`Graph::nodes`].
+pub struct Nodes<'a, N> {
+    cursor: map::MapCursor<'a, N, List<'a, N>>,
+}
+
+impl<'a, N> Iterator for Nodes<'a, N>
+where
+    N: PartialOrd,
+{
+    type Item = &'a N;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, _) = self.cursor.peek()?;
+        self.cursor.next_key();
+        Some(node)
+    }
+}
+
+impl<'a, N> Clone for Nodes<'a, N> {
+    fn clone(&self) -> Self {
+        Nodes {
+            cursor: self.cursor,
+        }
+    }
+}
+
+impl<'a, N> Default for Graph<'a, N> {
+    fn default() -> Self {
+        Graph {
+            adjacency: Map::default(),
+            node_count: 0,
+            edge_count: 0,
+        }
+    }
+}
+
+impl<'a, N> Clone for Graph<'a, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, N> Copy for Graph<'a, N> {}
+
+impl<'a, N> fmt::Debug for Graph<'a, N>
+where
+    N: PartialOrd + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.nodes().map(|node| (node, &self.adjacency[node])))
+            .finish()
+    }
+}
+
+#[test]
+fn graph_traversals() {
+    let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (4, 1), (5, 6), (2, 4)];
+    Graph::collect(edges, |graph| {
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 7);
+        assert!(graph.nodes().copied().eq(1..=6));
+        assert!(graph.successors(&2).eq(&[4, 4]));
+        assert!(!graph.contains_edge(&4, &3));
+        graph.breadth_first(&1, |order| assert!(order.iter().copied().eq(&[1, 3, 2, 4])));
+        graph.depth_first(&1, |order| assert!(order.iter().copied().eq(&[1, 3, 4, 2])));
+        graph.depth_first(&6, |order| assert!(order.iter().copied().eq(&[6])));
+        graph.breadth_first(&7, |order| assert!(order.is_empty()));
+        graph.add_node(7, |graph| {
+            assert_eq!(graph.node_count(), 7);
+            assert_eq!(graph.successors(&7).next(), None);
+        });
+    });
+}
+
+#[test]
+fn graph_topo_sort() {
+    let edges = [(5, 2), (5, 0), (4, 0), (4, 1), (2, 3), (3, 1), (6, 6)];
+    Graph::collect(edges[..6].iter().copied(), |graph| {
+        let sorted = graph.topo_sort(|order| {
+            assert_eq!(order.len(), 6);
+            edges[..6].iter().all(|(from, to)| {
+                let position = |node| order.iter().position(|&n| n == node);
+                position(from) < position(to)
+            })
+        });
+        assert_eq!(sorted.map_err(|cycle| *cycle.node()), Ok(true));
+        graph.add_edge(1, 5, |graph| {
+            let cycle = graph.topo_sort(|_| ()).unwrap_err();
+            assert!([5, 2, 3, 1].contains(cycle.node()));
+        });
+    });
+    Graph::collect([edges[6]], |graph| {
+        assert_eq!(graph.topo_sort(|_| ()).unwrap_err().node(), &6);
+    });
+}
@@ -0,0 +1,329 @@
+//! A two-dimensional grid with a fixed size that is stored inline
+
+use core::{
+    fmt,
+    iter::FusedIterator,
+    ops::{Index, IndexMut},
+    slice,
+};
+
+/// A grid of `W` columns and `H` rows of cells that is stored inline
+///
+/// Cells are addressed by `(x, y)`, where `x` is the column and `y` is the
+/// row, and are stored row by row.
+///
+/// # Example
+/// ```
+/// use nolloc::Grid;
+///
+/// let mut tiles = Grid::<char, 4, 3>::filled('.');
+/// tiles[(1, 1)] = '#';
+/// tiles[(2, 1)] = '#';
+///
+/// assert_eq!(tiles.row(1), Some(&['.', '#', '#', '.']));
+/// assert!(tiles.column(2).unwrap().eq(&['.', '#', '.']));
+/// assert_eq!(tiles.neighbors(1, 0).filter(|(_, &tile)| tile == '#').count(), 1);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid<T, const W: usize, const H: usize> {
+    rows: [[T; W]; H],
+}
+
+/// The offsets of the cells that share an edge with a cell
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// The offsets of the cells that share an edge or corner with a cell
+const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl<T, const W: usize, const H: usize> Grid<T, W, H> {
+    /// Create a grid by calling a function with the position of each cell
+    pub fn from_fn<F>(mut f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        Grid {
+            rows: core::array::from_fn(|y| core::array::from_fn(|x| f(x, y))),
+        }
+    }
+    /// Create a grid with every cell set to a value
+    pub fn filled(value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid::from_fn(|_, _| value.clone())
+    }
+    /// Get the number of columns in the grid
+    pub fn width(&self) -> usize {
+        W
+    }
+    /// Get the number of rows in the grid
+    pub fn height(&self) -> usize {
+        H
+    }
+    /// Check if a position is inside the grid
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x < W && y < H
+    }
+    /// Get the cell at a position
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.rows.get(y)?.get(x)
+    }
+    /// Get a mutable reference to the cell at a position
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.rows.get_mut(y)?.get_mut(x)
+    }
+    /// Set every cell to a value
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for row in &mut self.rows {
+            row.fill(value.clone());
+        }
+    }
+    /// Get a row of the grid
+    pub fn row(&self, y: usize) -> Option<&[T; W]> {
+        self.rows.get(y)
+    }
+    /// Get a mutable reference to a row of the grid
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T; W]> {
+        self.rows.get_mut(y)
+    }
+    /// Get an iterator over the rows of the grid from top to bottom
+    pub fn rows(&self) -> slice::Iter<'_, [T; W]> {
+        self.rows.iter()
+    }
+    /// Get an iterator over the cells of a column from top to bottom
+    pub fn column(&self, x: usize) -> Option<Column<'_, T, W>> {
+        if x < W {
+            Some(Column {
+                rows: self.rows.iter(),
+                x,
+            })
+        } else {
+            None
+        }
+    }
+    /// Get an iterator over the positions and cells of the grid, row by row
+    pub fn iter(&self) -> Iter<'_, T, W> {
+        Iter {
+            rows: &self.rows,
+            index: 0,
+        }
+    }
+    /// Get an iterator over the positions and cells that share an edge with
+    /// a position
+    ///
+    /// Positions outside the grid are skipped.
+    pub fn neighbors(&self, x: usize, y: usize) -> Neighbors<'_, T, W, H> {
+        self.near(x, y, &ORTHOGONAL)
+    }
+    /// Get an iterator over the positions and cells that share an edge or a
+    /// corner with a position
+    ///
+    /// Positions outside the grid are skipped.
+    pub fn surrounding(&self, x: usize, y: usize) -> Neighbors<'_, T, W, H> {
+        self.near(x, y, &SURROUNDING)
+    }
+    fn near(
+        &self,
+        x: usize,
+        y: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> Neighbors<'_, T, W, H> {
+        Neighbors {
+            grid: self,
+            x,
+            y,
+            offsets: offsets.iter(),
+        }
+    }
+}
+
+/// An iterator over the cells of a column of a [`Grid`]
+///
+/// This is returned by [`Grid::column`].
+pub struct Column<'a, T, const W: usize> {
+    rows: slice::Iter<'a, [T; W]>,
+    x: usize,
+}
+
+impl<'a, T, const W: usize> Iterator for Column<'a, T, W> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(&self.rows.next()?[self.x])
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl<'a, T, const W: usize> DoubleEndedIterator for Column<'a, T, W> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(&self.rows.next_back()?[self.x])
+    }
+}
+
+impl<'a, T, const W: usize> ExactSizeIterator for Column<'a, T, W> {}
+
+impl<'a, T, const W: usize> FusedIterator for Column<'a, T, W> {}
+
+impl<'a, T, const W: usize> Clone for Column<'a, T, W> {
+    fn clone(&self) -> Self {
+        Column {
+            rows: self.rows.clone(),
+            x: self.x,
+        }
+    }
+}
+
+/// An iterator over the positions and cells of a [`Grid`], row by row
+///
+/// This is returned by [`Grid::iter`].
+pub struct Iter<'a, T, const W: usize> {
+    rows: &'a [[T; W]],
+    /// The index of the next cell, counting row by row
+    index: usize,
+}
+
+impl<'a, T, const W: usize> Iterator for Iter<'a, T, W> {
+    type Item = ((usize, usize), &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.rows.len() * W {
+            return None;
+        }
+        let (x, y) = (self.index % W, self.index / W);
+        self.index += 1;
+        Some(((x, y), &self.rows[y][x]))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.rows.len() * W - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, const W: usize> ExactSizeIterator for Iter<'a, T, W> {}
+
+impl<'a, T, const W: usize> FusedIterator for Iter<'a, T, W> {}
+
+impl<'a, T, const W: usize> Clone for Iter<'a, T, W> {
+    fn clone(&self) -> Self {
+        Iter {
+            rows: self.rows,
+            index: self.index,
+        }
+    }
+}
+
+/// An iterator over the positions and cells near a position in a [`Grid`]
+///
+/// This is returned by [`Grid::neighbors`] and [`Grid::surrounding`].
+pub struct Neighbors<'a, T, const W: usize, const H: usize> {
+    grid: &'a Grid<T, W, H>,
+    x: usize,
+    y: usize,
+    offsets: slice::Iter<'static, (isize, isize)>,
+}
+
+impl<'a, T, const W: usize, const H: usize> Iterator for Neighbors<'a, T, W, H> {
+    type Item = ((usize, usize), &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (grid, x, y) = (self.grid, self.x, self.y);
+        self.offsets.find_map(|&(dx, dy)| {
+            let x = x.checked_add_signed(dx)?;
+            let y = y.checked_add_signed(dy)?;
+            Some(((x, y), grid.get(x, y)?))
+        })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.offsets.len()))
+    }
+}
+
+impl<'a, T, const W: usize, const H: usize> FusedIterator for Neighbors<'a, T, W, H> {}
+
+impl<'a, T, const W: usize, const H: usize> Clone for Neighbors<'a, T, W, H> {
+    fn clone(&self) -> Self {
+        Neighbors {
+            offsets: self.offsets.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<'a, T, const W: usize, const H: usize> IntoIterator for &'a Grid<T, W, H> {
+    type Item = ((usize, usize), &'a T);
+    type IntoIter = Iter<'a, T, W>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const W: usize, const H: usize> Index<(usize, usize)> for Grid<T, W, H> {
+    type Output = T;
+    #[track_caller]
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.rows[y][x]
+    }
+}
+
+impl<T, const W: usize, const H: usize> IndexMut<(usize, usize)> for Grid<T, W, H> {
+    #[track_caller]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        &mut self.rows[y][x]
+    }
+}
+
+impl<T, const W: usize, const H: usize> Default for Grid<T, W, H>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Grid::from_fn(|_, _| T::default())
+    }
+}
+
+impl<T, const W: usize, const H: usize> fmt::Debug for Grid<T, W, H>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.rows()).finish()
+    }
+}
+
+#[test]
+fn grid_life_step() {
+    // A blinker flips between a horizontal and a vertical line
+    let horizontal = Grid::<bool, 5, 5>::from_fn(|x, y| y == 2 && (1..4).contains(&x));
+    let step = |grid: &Grid<bool, 5, 5>| {
+        Grid::<bool, 5, 5>::from_fn(|x, y| {
+            let live = grid.surrounding(x, y).filter(|(_, &cell)| cell).count();
+            live == 3 || live == 2 && grid[(x, y)]
+        })
+    };
+    let vertical = step(&horizontal);
+    assert!(vertical
+        .column(2)
+        .unwrap()
+        .eq(&[false, true, true, true, false]));
+    assert_eq!(step(&vertical), horizontal);
+    assert_eq!(horizontal.surrounding(0, 0).count(), 3);
+    assert_eq!(
+        horizontal.neighbors(4, 2).map(|(pos, _)| pos).last(),
+        Some((4, 3))
+    );
+    assert_eq!(horizontal.iter().filter(|(_, &cell)| cell).count(), 3);
+    assert_eq!(horizontal.iter().nth(11), Some(((1, 2), &true)));
+    assert_eq!(Grid::<u8, 0, 3>::filled(0).iter().len(), 0);
+    assert!(horizontal.get(5, 0).is_none() && horizontal.column(5).is_none());
+}
@@ -0,0 +1,377 @@
+//! A growable hash map where all items exist on the stack
+//!
+//! A [`HashMap`] keeps `B` buckets inline, each of which is a chain of entries
+//! whose keys hash to it. Inserting pushes an entry onto the front of its chain
+//! and copies the bucket array, so keys only need to implement [`Hash`] and
+//! [`Eq`], not [`PartialOrd`].
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault, Hash},
+    iter::FusedIterator,
+    ops::Index,
+};
+
+use crate::bloom::FnvHasher;
+
+/// A growable hash map with `B` buckets where all items exist on the stack
+///
+/// Keys are hashed with a [`BuildHasher`], which is [`FnvHasher`] by default.
+///
+/// As with [`Map`](crate::Map), the map with an inserted entry cannot be
+/// accessed from the same scope. Instead, a continuation function is called
+/// on the new map, and its result is returned to the calling scope.
+///
+/// Lookups are **O(1)** operations on average, as long as there are not many
+/// more entries than buckets. Each insertion copies the bucket array onto the
+/// stack, so choose `B` with the number of entries in mind.
+///
+/// # Example
+/// ```
+/// use nolloc::HashMap;
+///
+/// #[derive(PartialEq, Eq, Hash)]
+/// enum Color {
+///     Red,
+///     Green,
+///     Blue,
+/// }
+///
+/// let paint = [(Color::Red, 3), (Color::Blue, 5), (Color::Red, 4)];
+///
+/// HashMap::<_, _, 8>::collect(paint, |cans| {
+///     assert_eq!(cans.len(), 2);
+///     assert_eq!(cans[&Color::Red], 4);
+///     assert_eq!(cans.get(&Color::Green), None);
+/// });
+/// ```
+pub struct HashMap<'a, K, V, const B: usize, S = BuildHasherDefault<FnvHasher>> {
+    buckets: [Chain<'a, K, V>; B],
+    len: usize,
+    hasher: S,
+}
+
+type Chain<'a, K, V> = Option<&'a HashNode<'a, K, V>>;
+
+struct HashNode<'a, K, V> {
+    key: K,
+    value: V,
+    next: Chain<'a, K, V>,
+}
+
+impl<'a, K, V, const B: usize, S> HashMap<'a, K, V, B, S>
+where
+    S: Default,
+{
+    /// Create a new map
+    pub fn new() -> Self {
+        HashMap::with_hasher(S::default())
+    }
+}
+
+impl<'a, K, V, const B: usize, S> HashMap<'a, K, V, B, S> {
+    /// Create a new map that hashes keys with the given [`BuildHasher`]
+    pub fn with_hasher(hasher: S) -> Self {
+        HashMap {
+            buckets: [None; B],
+            len: 0,
+            hasher,
+        }
+    }
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of keys in the map
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the number of buckets in the map
+    pub fn bucket_count(&self) -> usize {
+        B
+    }
+    /// Get the map's [`BuildHasher`]
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+    /// Get an iterator over the entries of the map, in no particular order
+    ///
+    /// Entries whose keys were inserted again are skipped.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.buckets.iter(),
+            chain: None,
+            node: None,
+            len: self.len,
+        }
+    }
+    /// Get an iterator over the keys of the map, in no particular order
+    pub fn keys(&self) -> impl Iterator<Item = &K>
+    where
+        K: Eq,
+    {
+        self.iter().map(|(key, _)| key)
+    }
+    /// Get an iterator over the values of the map, in no particular order
+    pub fn values(&self) -> impl Iterator<Item = &V>
+    where
+        K: Eq,
+    {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V, const B: usize, S> HashMap<'a, K, V, B, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Get the index of the bucket for a key
+    fn bucket<Q>(&self, key: &Q) -> usize
+    where
+        Q: Hash + ?Sized,
+    {
+        (self.hasher.hash_one(key) % B as u64) as usize
+    }
+    /// Get the most recently inserted entry for a key
+    fn get_node<Q>(&self, key: &Q) -> Option<&'a HashNode<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if B == 0 {
+            return None;
+        }
+        let mut chain = self.buckets[self.bucket(key)];
+        while let Some(node) = chain {
+            if node.key.borrow() == key {
+                return Some(node);
+            }
+            chain = node.next;
+        }
+        None
+    }
+    /// Check if the map contains a key
+    ///
+    /// This is an **O(1)** operation on average.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_node(key).is_some()
+    }
+    /// Get the value corresponding to the key
+    ///
+    /// This is an **O(1)** operation on average.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        Some(&self.get_node(key)?.value)
+    }
+    /// Get the map's own key equal to the key and its value
+    ///
+    /// This is an **O(1)** operation on average.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.get_node(key)?;
+        Some((&node.key, &node.value))
+    }
+    /// Insert a key-value pair into the map and call a continuation on the
+    /// new map
+    ///
+    /// If an entry with the key already exists in the map, lookups on the new
+    /// map find the new entry instead.
+    ///
+    /// This is an **O(B)** operation, because the bucket array is copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` is 0.
+    pub fn insert<F, R>(&self, key: K, value: V, then: F) -> R
+    where
+        S: Clone,
+        F: FnOnce(&HashMap<K, V, B, S>) -> R,
+    {
+        assert!(B > 0, "a hash map must have at least one bucket");
+        let bucket = self.bucket(&key);
+        let len = self.len + usize::from(!self.contains_key(&key));
+        let node = HashNode {
+            key,
+            value,
+            next: self.buckets[bucket],
+        };
+        let mut buckets = self.buckets;
+        buckets[bucket] = Some(&node);
+        then(&HashMap {
+            buckets,
+            len,
+            hasher: self.hasher.clone(),
+        })
+    }
+    /// Collect an iterator into a map and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        S: Clone + Default,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&HashMap<K, V, B, S>) -> R,
+    {
+        HashMap::new().extend(iter, then)
+    }
+    /// Extend the map with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        S: Clone,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&HashMap<K, V, B, S>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some((key, value)) = iter.next() {
+            self.insert(key, value, |map| map.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the entries of a [`HashMap`]
+///
+/// This is returned by [`HashMap::iter`].
+pub struct Iter<'a, K, V> {
+    buckets: core::slice::Iter<'a, Chain<'a, K, V>>,
+    /// The front of the chain being iterated
+    chain: Chain<'a, K, V>,
+    node: Chain<'a, K, V>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V>
+where
+    K: Eq,
+{
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(node) = self.node {
+                self.node = node.next;
+                // An entry is shadowed by an entry for the same key nearer
+                // the front of its chain
+                let mut earlier = self.chain;
+                let shadowed = loop {
+                    match earlier {
+                        Some(other) if core::ptr::eq(other, node) => break false,
+                        Some(other) if other.key == node.key => break true,
+                        Some(other) => earlier = other.next,
+                        None => break false,
+                    }
+                };
+                if !shadowed {
+                    self.len -= 1;
+                    return Some((&node.key, &node.value));
+                }
+            }
+            self.chain = *self.buckets.next()?;
+            self.node = self.chain;
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> where K: Eq {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> where K: Eq {}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            buckets: self.buckets.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<'a, 'm, K, V, const B: usize, S> IntoIterator for &'m HashMap<'a, K, V, B, S>
+where
+    K: Eq,
+{
+    type Item = (&'m K, &'m V);
+    type IntoIter = Iter<'m, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const B: usize, S> Default for HashMap<'a, K, V, B, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        HashMap::new()
+    }
+}
+
+impl<'a, K, V, const B: usize, S> Clone for HashMap<'a, K, V, B, S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        HashMap {
+            buckets: self.buckets,
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<'a, K, V, const B: usize, S> Copy for HashMap<'a, K, V, B, S> where S: Copy {}
+
+impl<'a, K, V, const B: usize, S> fmt::Debug for HashMap<'a, K, V, B, S>
+where
+    K: Eq + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, Q, const B: usize, S> Index<&Q> for HashMap<'a, K, V, B, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+    #[track_caller]
+    fn index(&self, index: &Q) -> &Self::Output {
+        self.get(index).expect("no entry found for key")
+    }
+}
+
+#[test]
+fn hash_map_shadowing() {
+    let pairs = (0..40).map(|i| (i % 25, i));
+    HashMap::<_, _, 7>::collect(pairs, |map| {
+        assert_eq!(map.len(), 25);
+        assert_eq!(map.iter().len(), 25);
+        assert!((0..25).all(|k| map[&k] == if k < 15 { k + 25 } else { k }));
+        let mut keys = [false; 25];
+        for (&k, &v) in map {
+            assert!(!keys[k]);
+            keys[k] = true;
+            assert_eq!(map.get(&k), Some(&v));
+        }
+        assert!(!map.contains_key(&25));
+    });
+    assert_eq!(HashMap::<u8, u8, 0>::new().get(&1), None);
+}
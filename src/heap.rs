@@ -0,0 +1,238 @@
+//! A growable priority queue where all items exist on the stack
+//!
+//! A [`Heap`] is a persistent leftist heap. Pushing and popping copy the nodes
+//! along the right spines of the heaps being merged, which have O(logn) nodes.
+
+use core::fmt;
+
+/// A growable min-priority queue where all items exist on the stack
+///
+/// As with [`List`](crate::List), the heap with a pushed item cannot be accessed
+/// from the same scope. Instead, a continuation function is called on the new heap,
+/// and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Heap;
+///
+/// let deadlines = [30, 10, 20];
+///
+/// Heap::collect(deadlines, |timers| {
+///     assert_eq!(timers.peek(), Some(&10));
+///     timers.pop(|timers, next| {
+///         assert_eq!(next, Some(&10));
+///         assert_eq!(timers.peek(), Some(&20));
+///         assert_eq!(timers.len(), 2);
+///     })
+/// });
+/// ```
+pub struct Heap<'a, T> {
+    root: Option<&'a HeapNode<'a, T>>,
+}
+
+struct HeapNode<'a, T> {
+    item: &'a T,
+    left: Option<&'a Self>,
+    right: Option<&'a Self>,
+    /// The length of the right spine
+    rank: u8,
+    /// The number of items in the subtree
+    len: usize,
+}
+
+impl<'a, T> Heap<'a, T> {
+    /// Create a new heap
+    pub fn new() -> Self {
+        Heap::default()
+    }
+    /// Check if the heap is empty
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+    /// Get the heap's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.root.map_or(0, |root| root.len)
+    }
+    /// Get the least item in the heap
+    ///
+    /// This is an **O(1)** operation.
+    pub fn peek(&self) -> Option<&'a T> {
+        Some(self.root?.item)
+    }
+}
+
+impl<'a, T> Heap<'a, T>
+where
+    T: PartialOrd,
+{
+    /// Push an item onto the heap, call a continuation on the new heap,
+    /// and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn push<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Heap<T>) -> R,
+    {
+        let node = HeapNode {
+            item: &item,
+            left: None,
+            right: None,
+            rank: 1,
+            len: 1,
+        };
+        merge(self.root, Some(&node), |root| then(&Heap { root }))
+    }
+    /// Remove the least item from the heap, call a continuation on the new heap
+    /// and the removed item, and return its result
+    ///
+    /// The continuation gets [`None`] and the heap unchanged if the heap is empty.
+    /// This is an **O(logn)** operation.
+    pub fn pop<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&Heap<T>, Option<&T>) -> R,
+    {
+        match self.root {
+            Some(root) => merge(root.left, root.right, |rest| {
+                then(&Heap { root: rest }, Some(root.item))
+            }),
+            None => then(self, None),
+        }
+    }
+    /// Collect an iterator into a heap and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Heap<T>) -> R,
+    {
+        Heap::default().extend(iter, then)
+    }
+    /// Extend the heap with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Heap<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.push(item, |heap| heap.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+fn rank<T>(node: Option<&HeapNode<T>>) -> u8 {
+    node.map_or(0, |node| node.rank)
+}
+
+/// Merge two heaps and call a continuation on the new root
+fn merge<'a, T, F, R>(a: Option<&'a HeapNode<'a, T>>, b: Option<&'a HeapNode<'a, T>>, then: F) -> R
+where
+    T: PartialOrd,
+    F: FnOnce(Option<&HeapNode<T>>) -> R,
+{
+    // Merging copies at most one node per step down either right spine
+    match usize::from(rank(a)) + usize::from(rank(b)) {
+        0..=8 => merge_in::<_, _, _, 8>(a, b, then),
+        9..=16 => merge_in::<_, _, _, 16>(a, b, then),
+        17..=32 => merge_in::<_, _, _, 32>(a, b, then),
+        _ => merge_in::<_, _, _, { 2 * usize::BITS as usize }>(a, b, then),
+    }
+}
+
+// Kept out of line so that only the chosen buffer size takes up stack space
+#[inline(never)]
+fn merge_in<'a, T, F, R, const N: usize>(
+    a: Option<&'a HeapNode<'a, T>>,
+    b: Option<&'a HeapNode<'a, T>>,
+    then: F,
+) -> R
+where
+    T: PartialOrd,
+    F: FnOnce(Option<&HeapNode<T>>) -> R,
+{
+    let mut buffer: [Option<HeapNode<T>>; N] = core::array::from_fn(|_| None);
+    let mut free: &mut [Option<HeapNode<T>>] = &mut buffer;
+    then(merge_into(a, b, &mut free))
+}
+
+/// Merge two heaps, placing the copied nodes in a buffer, and get the new root
+fn merge_into<'b, T>(
+    a: Option<&'b HeapNode<'b, T>>,
+    b: Option<&'b HeapNode<'b, T>>,
+    free: &mut &'b mut [Option<HeapNode<'b, T>>],
+) -> Option<&'b HeapNode<'b, T>>
+where
+    T: PartialOrd,
+{
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) if b.item < a.item => (b, a),
+        (Some(a), Some(b)) => (a, b),
+        (a, None) => return a,
+        (None, b) => return b,
+    };
+    let merged = merge_into(a.right, Some(b), free);
+    // Keep the shorter right spine on the right
+    let (left, right) = if rank(a.left) >= rank(merged) {
+        (a.left, merged)
+    } else {
+        (merged, a.left)
+    };
+    let (slot, rest) = core::mem::take(free)
+        .split_first_mut()
+        .expect("heap merge buffer is full");
+    *free = rest;
+    Some(Option::insert(
+        slot,
+        HeapNode {
+            item: a.item,
+            left,
+            right,
+            rank: rank(right) + 1,
+            len: a.len + b.len,
+        },
+    ))
+}
+
+impl<'a, T> Default for Heap<'a, T> {
+    fn default() -> Self {
+        Heap { root: None }
+    }
+}
+
+impl<'a, T> Clone for Heap<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Heap<'a, T> {}
+
+impl<'a, T> fmt::Debug for Heap<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Heap")
+            .field("len", &self.len())
+            .field("peek", &self.peek())
+            .finish()
+    }
+}
+
+#[test]
+fn heap_order() {
+    fn drain(heap: &Heap<u32>, prev: u32, left: usize) {
+        assert_eq!(heap.len(), left);
+        heap.pop(|heap, item| match item {
+            Some(&item) => {
+                assert!(item >= prev);
+                drain(heap, item, left - 1)
+            }
+            None => assert_eq!(left, 0),
+        })
+    }
+    Heap::collect((0..200).map(|i| (i * 37) % 101), |heap| drain(heap, 0, 200));
+}
@@ -0,0 +1,200 @@
+//! A string interner where all strings exist on the stack
+//!
+//! An [`Interner`] is a [`Set`] of the first occurrence of each distinct string.
+//! A [`Symbol`] refers to that occurrence, so symbols for equal strings point to
+//! the same bytes and can be compared without looking at them.
+
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ptr,
+};
+
+use crate::{set, Set};
+
+/// A string interner where all strings exist on the stack
+///
+/// As with [`Set`], the interner with a newly interned string cannot be
+/// accessed from the same scope. Instead, a continuation function is called
+/// on the new interner, and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Interner;
+///
+/// let source = String::from("let x = x + y");
+///
+/// Interner::collect(source.split(' '), |idents| {
+///     let x = idents.get("x").unwrap();
+///     assert_eq!(idents.len(), 5);
+///     assert_eq!(x.as_str(), "x");
+///     idents.intern("y", |_, y| assert_ne!(x, y));
+/// });
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct Interner<'a> {
+    strings: Set<'a, &'a str>,
+}
+
+impl<'a> Interner<'a> {
+    /// Create a new interner
+    pub fn new() -> Self {
+        Interner::default()
+    }
+    /// Check if the interner is empty
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+    /// Get the number of distinct strings in the interner
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+    /// Get the symbol for a string if it has been interned
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, s: &str) -> Option<Symbol<'a>> {
+        self.strings.get(s).map(|&s| Symbol(s))
+    }
+    /// Check if a string has been interned
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains(&self, s: &str) -> bool {
+        self.strings.contains(s)
+    }
+    /// Get the symbol for a string, interning it if it is new, call a
+    /// continuation on the new (or old) interner and the symbol, and return
+    /// its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn intern<F, R>(&self, s: &'a str, then: F) -> R
+    where
+        F: FnOnce(&Interner, Symbol<'a>) -> R,
+    {
+        if let Some(symbol) = self.get(s) {
+            return then(self, symbol);
+        }
+        self.strings.insert(s, |strings| {
+            then(&Interner { strings: *strings }, Symbol(s))
+        })
+    }
+    /// Get an iterator over the symbols of the interner
+    ///
+    /// The iterator yields symbols in the opposite order of their interning.
+    pub fn symbols(&self) -> Symbols<'a> {
+        Symbols {
+            strings: self.strings.iter(),
+        }
+    }
+    /// Intern the strings of an iterator, call a continuation function on
+    /// the new interner, and return its result
+    pub fn collect<'k, I, F, R>(iter: I, then: F) -> R
+    where
+        'k: 'a,
+        I: IntoIterator<Item = &'k str>,
+        F: FnOnce(&Interner) -> R,
+    {
+        Interner::default().extend(iter, then)
+    }
+    /// Intern the strings of an iterator, call a continuation function on
+    /// the new interner, and return its result
+    pub fn extend<'k, I, F, R>(&self, iter: I, then: F) -> R
+    where
+        'k: 'a,
+        I: IntoIterator<Item = &'k str>,
+        F: FnOnce(&Interner) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(s) = iter.next() {
+            self.intern(s, |interner, _| interner.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// A handle to a string interned in an [`Interner`]
+///
+/// Symbols for equal strings from the same interner are equal. Comparing and
+/// hashing symbols are **O(1)** operations, and a symbol can outlive the
+/// interner that made it.
+#[derive(Clone, Copy)]
+pub struct Symbol<'a>(&'a str);
+
+impl<'a> Symbol<'a> {
+    /// Get the string the symbol was interned from
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl<'a> PartialEq for Symbol<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        // Distinct strings in an interner never share both a start and a length
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl<'a> Eq for Symbol<'a> {}
+
+impl<'a> Hash for Symbol<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_ptr().hash(state);
+        self.0.len().hash(state);
+    }
+}
+
+impl<'a> fmt::Debug for Symbol<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Symbol").field(&self.0).finish()
+    }
+}
+
+impl<'a> fmt::Display for Symbol<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// An iterator over the symbols of an [`Interner`]
+///
+/// This is returned by [`Interner::symbols`].
+pub struct Symbols<'a> {
+    strings: set::Iter<'a, &'a str>,
+}
+
+impl<'a> Iterator for Symbols<'a> {
+    type Item = Symbol<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.strings.next().map(|&s| Symbol(s))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.strings.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Symbols<'a> {}
+
+impl<'a> fmt::Debug for Interner<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.strings.iter()).finish()
+    }
+}
+
+#[test]
+fn interner_symbols() {
+    let text = "a b a ab b";
+    let words: [&str; 5] = core::array::from_fn(|i| text.split(' ').nth(i).unwrap());
+    Interner::collect(words, |interner| {
+        assert_eq!(interner.len(), 3);
+        let symbols: [Symbol; 5] = core::array::from_fn(|i| interner.get(words[i]).unwrap());
+        assert_eq!(symbols[0], symbols[2]);
+        assert_eq!(symbols[1], symbols[4]);
+        assert_ne!(symbols[0], symbols[3]);
+        // The first occurrence of each string is the one that is kept
+        assert!(ptr::eq(symbols[2].as_str(), words[0]));
+        assert!(interner.symbols().map(|s| s.as_str()).eq(["ab", "b", "a"]));
+        assert_eq!(interner.get("c"), None);
+    });
+}
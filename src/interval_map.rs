@@ -0,0 +1,363 @@
+//! A growable map with interval keys where all items exist on the stack
+//!
+//! Entries are kept in a persistent balanced search tree ordered by the start
+//! and then the end of their intervals. Each tree node also records the greatest
+//! end in its subtree, so queries can skip subtrees that end too early.
+
+use core::{cmp::Ordering, fmt, iter::FusedIterator, ops::Range};
+
+use crate::tree::{self, Augment, InOrder, Node, MAX_HEIGHT};
+
+/// A growable map with interval keys where all items exist on the stack
+///
+/// Intervals are half-open [`Range`]s, so `0..4` contains `3` but not `4`,
+/// and `0..4` does not overlap `4..8`. Empty intervals overlap nothing. Their
+/// bounds are copied into the tree's nodes, so they must be [`Copy`].
+///
+/// As with [`Map`](crate::Map), the map with an inserted interval cannot be
+/// accessed from the same scope. Instead, a continuation function is called
+/// on the new map, and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::IntervalMap;
+///
+/// let regions = [(0x0000..0x4000, "flash"), (0x2000..0x3000, "bootloader"), (0x8000..0x9000, "ram")];
+///
+/// IntervalMap::collect(regions, |memory| {
+///     assert!(memory.containing(&0x2800).map(|(_, name)| name).eq(&["flash", "bootloader"]));
+///     assert_eq!(memory.containing(&0x5000).next(), None);
+///     assert_eq!(memory.overlapping(0x3800..0x8800).count(), 2);
+/// });
+/// ```
+pub struct IntervalMap<'a, K, V> {
+    root: Option<&'a IntervalTree<'a, K, V>>,
+    len: usize,
+}
+
+struct IntervalNode<K, V> {
+    range: Range<K>,
+    value: V,
+}
+
+/// The greatest end of the intervals in a subtree
+#[derive(Clone, Copy)]
+struct MaxEnd<K>(K);
+
+impl<K, V> Augment<IntervalNode<K, V>> for MaxEnd<K>
+where
+    K: PartialOrd + Copy,
+{
+    fn new(entry: &IntervalNode<K, V>, left: Option<&Self>, right: Option<&Self>) -> Self {
+        let mut max = entry.range.end;
+        for child in [left, right].iter().flatten() {
+            if child.0 > max {
+                max = child.0;
+            }
+        }
+        MaxEnd(max)
+    }
+}
+
+type IntervalTree<'a, K, V> = Node<'a, IntervalNode<K, V>, MaxEnd<K>>;
+
+/// Order intervals by their starts and then their ends
+fn compare_ranges<K>(a: &Range<K>, b: &Range<K>) -> Ordering
+where
+    K: PartialOrd,
+{
+    tree::compare(&a.start, &b.start).then_with(|| tree::compare(&a.end, &b.end))
+}
+
+impl<'a, K, V> IntervalMap<'a, K, V> {
+    /// Create a new map
+    pub fn new() -> Self {
+        IntervalMap::default()
+    }
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of intervals in the map
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get an iterator over the intervals and values of the map, ordered by
+    /// the starts and then the ends of the intervals
+    pub fn iter(&self) -> Iter<'a, K, V> {
+        Iter {
+            nodes: InOrder::new(self.root),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, K, V> IntervalMap<'a, K, V>
+where
+    K: PartialOrd + Copy,
+{
+    /// Check if the map contains an interval
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains_range(&self, range: &Range<K>) -> bool {
+        self.get(range).is_some()
+    }
+    /// Get the value corresponding to an interval
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, range: &Range<K>) -> Option<&'a V> {
+        let node = tree::find(self.root, |node| compare_ranges(range, &node.range))?;
+        Some(&node.entry.value)
+    }
+    /// Get an iterator over the intervals that contain a point and their
+    /// values, in the same order as [`IntervalMap::iter`]
+    ///
+    /// This is an **O(klogn)** operation for k intervals found.
+    pub fn containing(&self, point: &K) -> Overlaps<'a, K, V> {
+        Overlaps::new(self.root, *point, Bound::Included(*point))
+    }
+    /// Get an iterator over the intervals that overlap a range and their
+    /// values, in the same order as [`IntervalMap::iter`]
+    ///
+    /// This is an **O(klogn)** operation for k intervals found.
+    pub fn overlapping(&self, range: Range<K>) -> Overlaps<'a, K, V> {
+        let root = if range.is_empty() { None } else { self.root };
+        Overlaps::new(root, range.start, Bound::Excluded(range.end))
+    }
+    /// Check the invariants of the map's search tree, panicking if any are violated
+    ///
+    /// This requires the `validate` feature.
+    #[cfg(any(test, feature = "validate"))]
+    pub fn debug_validate(&self) {
+        fn check_ends<K, V>(node: Option<&IntervalTree<K, V>>)
+        where
+            K: PartialOrd + Copy,
+        {
+            if let Some(node) = node {
+                let left = node.left.map(|left| &left.augment);
+                let right = node.right.map(|right| &right.augment);
+                let max = MaxEnd::new(node.entry, left, right);
+                assert!(max.0 == node.augment.0, "tree node has the wrong end");
+                check_ends(node.left);
+                check_ends(node.right);
+            }
+        }
+        tree::validate(self.root, |a, b| compare_ranges(&a.range, &b.range));
+        check_ends(self.root);
+    }
+    /// Insert an interval and its value into the map, call a continuation
+    /// on the new map, and return its result
+    ///
+    /// If the interval is already in the map, its value is replaced.
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, range: Range<K>, value: V, then: F) -> R
+    where
+        F: FnOnce(&IntervalMap<K, V>) -> R,
+    {
+        let len = self.len + usize::from(!self.contains_range(&range));
+        let node = IntervalNode { range, value };
+        let cmp =
+            |a: &IntervalNode<K, V>, b: &IntervalNode<K, V>| compare_ranges(&a.range, &b.range);
+        tree::insert(self.root, &node, &cmp, |root| {
+            then(&IntervalMap {
+                root: Some(root),
+                len,
+            })
+        })
+    }
+    /// Remove an interval from the map, call a continuation on the new map
+    /// and the removed value, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn remove<F, R>(&self, range: &Range<K>, then: F) -> R
+    where
+        F: FnOnce(&IntervalMap<K, V>, Option<&V>) -> R,
+    {
+        let removed = self.get(range);
+        let len = self.len - usize::from(removed.is_some());
+        tree::remove(
+            self.root,
+            |node| compare_ranges(range, &node.range),
+            |root| then(&IntervalMap { root, len }, removed),
+        )
+    }
+    /// Collect an iterator into a map and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (Range<K>, V)>,
+        F: FnOnce(&IntervalMap<K, V>) -> R,
+    {
+        IntervalMap::default().extend(iter, then)
+    }
+    /// Extend the map with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (Range<K>, V)>,
+        F: FnOnce(&IntervalMap<K, V>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some((range, value)) = iter.next() {
+            self.insert(range, value, |map| map.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the intervals and values of an [`IntervalMap`]
+///
+/// This is returned by [`IntervalMap::iter`].
+pub struct Iter<'a, K, V> {
+    nodes: InOrder<'a, IntervalNode<K, V>, MaxEnd<K>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a Range<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?;
+        self.len -= 1;
+        Some((&node.range, &node.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+/// The greatest start an interval found by a query may have
+#[derive(Clone, Copy)]
+enum Bound<K> {
+    Included(K),
+    Excluded(K),
+}
+
+/// An iterator over the intervals of an [`IntervalMap`] that overlap a
+/// range or contain a point, and their values
+///
+/// This is returned by [`IntervalMap::overlapping`] and [`IntervalMap::containing`].
+pub struct Overlaps<'a, K, V> {
+    /// Nodes whose entries and right subtrees have not been visited, lowest last
+    stack: [Option<&'a IntervalTree<'a, K, V>>; MAX_HEIGHT],
+    len: usize,
+    /// Found intervals end after this
+    after: K,
+    /// Found intervals start before this
+    before: Bound<K>,
+}
+
+impl<'a, K, V> Overlaps<'a, K, V>
+where
+    K: PartialOrd + Copy,
+{
+    fn new(root: Option<&'a IntervalTree<'a, K, V>>, after: K, before: Bound<K>) -> Self {
+        let mut iter = Overlaps {
+            stack: [None; MAX_HEIGHT],
+            len: 0,
+            after,
+            before,
+        };
+        iter.push_near(root);
+        iter
+    }
+    /// Push a node and its left descendants, stopping at a subtree that ends too early
+    fn push_near(&mut self, mut node: Option<&'a IntervalTree<'a, K, V>>) {
+        while let Some(curr) = node.filter(|node| node.augment.0 > self.after) {
+            self.stack[self.len] = Some(curr);
+            self.len += 1;
+            node = curr.left;
+        }
+    }
+    fn starts_in_time(&self, range: &Range<K>) -> bool {
+        match self.before {
+            Bound::Included(before) => range.start <= before,
+            Bound::Excluded(before) => range.start < before,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Overlaps<'a, K, V>
+where
+    K: PartialOrd + Copy,
+{
+    type Item = (&'a Range<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.len = self.len.checked_sub(1)?;
+            let node = self.stack[self.len].take()?;
+            if !self.starts_in_time(&node.entry.range) {
+                // Every node left starts later still
+                self.len = 0;
+                return None;
+            }
+            self.push_near(node.right);
+            let range = &node.entry.range;
+            if range.end > self.after && !range.is_empty() {
+                return Some((range, &node.entry.value));
+            }
+        }
+    }
+}
+
+impl<'a, K, V> FusedIterator for Overlaps<'a, K, V> where K: PartialOrd + Copy {}
+
+impl<'a, K, V> Default for IntervalMap<'a, K, V> {
+    fn default() -> Self {
+        IntervalMap { root: None, len: 0 }
+    }
+}
+
+impl<'a, K, V> Clone for IntervalMap<'a, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for IntervalMap<'a, K, V> {}
+
+impl<'a, K, V> fmt::Debug for IntervalMap<'a, K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn interval_map_queries() {
+    let ranges = (0..60u32).map(|i| {
+        let start = (i * 37) % 100;
+        (start..start + i % 7, i)
+    });
+    IntervalMap::collect(ranges.clone(), |map| {
+        map.debug_validate();
+        assert_eq!(map.len(), 60);
+        // Queries find the same intervals as a scan of every interval
+        for point in 0..110 {
+            let expected = map.iter().filter(|(r, _)| r.contains(&point));
+            assert!(map.containing(&point).eq(expected));
+        }
+        for start in 0..100 {
+            let end = start + 5;
+            let expected = map
+                .iter()
+                .filter(|(r, _)| r.start < end && r.end > start && !r.is_empty());
+            assert!(map.overlapping(start..end).eq(expected));
+        }
+        assert_eq!(map.overlapping(0..100).count(), 60 - 60 / 7 - 1);
+        assert_eq!(map.overlapping(50..50).next(), None);
+        map.remove(&(74..76), |map, removed| {
+            map.debug_validate();
+            assert_eq!(removed, Some(&2));
+            assert_eq!(map.len(), 59);
+            assert_eq!(map.containing(&75).next(), None);
+        });
+    });
+}
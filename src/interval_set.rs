@@ -0,0 +1,208 @@
+//! A growable set of intervals where all items exist on the stack
+//!
+//! An [`IntervalSet`] is an [`IntervalMap`] whose values are all `()`.
+
+use core::{fmt, iter::FusedIterator, ops::Range};
+
+use crate::{interval_map, IntervalMap};
+
+/// A growable set of intervals where all items exist on the stack
+///
+/// As with [`IntervalMap`], intervals are half-open [`Range`]s with [`Copy`]
+/// bounds, and the set with an inserted interval cannot be accessed from the
+/// same scope. Instead, a continuation function is called on the new set,
+/// and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::IntervalSet;
+///
+/// let busy = [9..12, 13..15, 14..17];
+///
+/// IntervalSet::collect(busy, |busy| {
+///     assert!(busy.contains_point(&10));
+///     assert!(!busy.contains_point(&12));
+///     assert!(busy.overlapping(11..14).eq(&[9..12, 13..15]));
+/// });
+/// ```
+pub struct IntervalSet<'a, K> {
+    map: IntervalMap<'a, K, ()>,
+}
+
+impl<'a, K> IntervalSet<'a, K> {
+    /// Create a new set
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    /// Get the number of intervals in the set
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    /// Get an iterator over the intervals of the set, ordered by their starts
+    /// and then their ends
+    pub fn iter(&self) -> Ranges<interval_map::Iter<'a, K, ()>> {
+        Ranges {
+            iter: self.map.iter(),
+        }
+    }
+}
+
+impl<'a, K> IntervalSet<'a, K>
+where
+    K: PartialOrd + Copy,
+{
+    /// Check if the set contains an interval
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains(&self, range: &Range<K>) -> bool {
+        self.map.contains_range(range)
+    }
+    /// Check if any interval in the set contains a point
+    ///
+    /// This stops at the first interval found.
+    pub fn contains_point(&self, point: &K) -> bool {
+        self.containing(point).next().is_some()
+    }
+    /// Get an iterator over the intervals that contain a point, in the same
+    /// order as [`IntervalSet::iter`]
+    ///
+    /// This is an **O(klogn)** operation for k intervals found.
+    pub fn containing(&self, point: &K) -> Ranges<interval_map::Overlaps<'a, K, ()>> {
+        Ranges {
+            iter: self.map.containing(point),
+        }
+    }
+    /// Get an iterator over the intervals that overlap a range, in the same
+    /// order as [`IntervalSet::iter`]
+    ///
+    /// This is an **O(klogn)** operation for k intervals found.
+    pub fn overlapping(&self, range: Range<K>) -> Ranges<interval_map::Overlaps<'a, K, ()>> {
+        Ranges {
+            iter: self.map.overlapping(range),
+        }
+    }
+    /// Insert an interval into the set, call a continuation on the new set,
+    /// and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, range: Range<K>, then: F) -> R
+    where
+        F: FnOnce(&IntervalSet<K>) -> R,
+    {
+        self.map
+            .insert(range, (), |map| then(&IntervalSet { map: *map }))
+    }
+    /// Remove an interval from the set, call a continuation on the new set
+    /// and whether the interval was in the set, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn remove<F, R>(&self, range: &Range<K>, then: F) -> R
+    where
+        F: FnOnce(&IntervalSet<K>, bool) -> R,
+    {
+        self.map.remove(range, |map, removed| {
+            then(&IntervalSet { map: *map }, removed.is_some())
+        })
+    }
+    /// Collect an iterator into a set and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = Range<K>>,
+        F: FnOnce(&IntervalSet<K>) -> R,
+    {
+        IntervalSet::default().extend(iter, then)
+    }
+    /// Extend the set with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = Range<K>>,
+        F: FnOnce(&IntervalSet<K>) -> R,
+    {
+        self.map
+            .extend(iter.into_iter().map(|range| (range, ())), |map| {
+                then(&IntervalSet { map: *map })
+            })
+    }
+}
+
+/// An iterator over intervals of an [`IntervalSet`]
+///
+/// This is returned by [`IntervalSet::iter`], [`IntervalSet::containing`]
+/// and [`IntervalSet::overlapping`].
+pub struct Ranges<I> {
+    iter: I,
+}
+
+impl<'a, K, I> Iterator for Ranges<I>
+where
+    K: 'a,
+    I: Iterator<Item = (&'a Range<K>, &'a ())>,
+{
+    type Item = &'a Range<K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()?.0)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, I> ExactSizeIterator for Ranges<I>
+where
+    K: 'a,
+    I: ExactSizeIterator<Item = (&'a Range<K>, &'a ())>,
+{
+}
+
+impl<'a, K, I> FusedIterator for Ranges<I>
+where
+    K: 'a,
+    I: FusedIterator<Item = (&'a Range<K>, &'a ())>,
+{
+}
+
+impl<'a, K> Default for IntervalSet<'a, K> {
+    fn default() -> Self {
+        IntervalSet {
+            map: IntervalMap::default(),
+        }
+    }
+}
+
+impl<'a, K> Clone for IntervalSet<'a, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K> Copy for IntervalSet<'a, K> {}
+
+impl<'a, K> fmt::Debug for IntervalSet<'a, K>
+where
+    K: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn interval_set_edits() {
+    IntervalSet::collect([4..8, 0..2, 4..8, 6..10], |set| {
+        assert_eq!(set.len(), 3);
+        assert!(set.iter().eq(&[0..2, 4..8, 6..10]));
+        assert!(set.containing(&7).eq(&[4..8, 6..10]));
+        assert!(!set.contains_point(&3));
+        set.remove(&(4..8), |set, removed| {
+            assert!(removed);
+            assert!(set.containing(&5).next().is_none());
+            set.remove(&(4..8), |_, removed| assert!(!removed));
+        });
+    });
+}
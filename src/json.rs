@@ -0,0 +1,540 @@
+//! Parsing JSON into values that exist on the stack
+//!
+//! [`parse`] builds a [`Value`] whose arrays are [`List`]s and whose objects
+//! are [`Map`]s, then calls a continuation on it. Every array element and
+//! object member is placed in its own stack frame, so the stack use grows with
+//! the size of the document, and nesting is limited to a maximum depth.
+//!
+//! String values are kept in their escaped form and unescaped lazily by
+//! [`JsonStr::chars`]. Object keys are borrowed from the input, unless they
+//! contain escapes, in which case they are unescaped into a buffer of
+//! [`MAX_ESCAPED_KEY_LEN`] bytes.
+
+use core::{
+    fmt::{self, Write},
+    str,
+};
+
+use crate::{List, Map};
+
+/// The maximum nesting depth of arrays and objects allowed by [`parse`]
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// The maximum length in bytes of an unescaped object key that contains escapes
+pub const MAX_ESCAPED_KEY_LEN: usize = 64;
+
+/// A JSON value
+///
+/// Array elements are kept last first, as they were pushed to their [`List`].
+/// If an object has duplicate keys, the last value shadows the others.
+#[derive(Debug, Clone, Copy)]
+pub enum Value<'a> {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool(bool),
+    /// A number
+    Number(f64),
+    /// A string
+    String(JsonStr<'a>),
+    /// An array, with its elements last first
+    Array(List<'a, Value<'a>>),
+    /// An object
+    Object(Map<'a, &'a str, Value<'a>>),
+}
+
+impl<'a> Value<'a> {
+    /// Check if the value is `null`
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+    /// Get the value as a [`bool`]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    /// Get the value as an [`f64`]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+    /// Get the value as a string
+    pub fn as_str(&self) -> Option<JsonStr<'a>> {
+        match self {
+            Value::String(s) => Some(*s),
+            _ => None,
+        }
+    }
+    /// Get the value as a list of elements, last first
+    pub fn as_array(&self) -> Option<List<'a, Value<'a>>> {
+        match self {
+            Value::Array(elements) => Some(*elements),
+            _ => None,
+        }
+    }
+    /// Get the value as a map of members
+    pub fn as_object(&self) -> Option<Map<'a, &'a str, Value<'a>>> {
+        match self {
+            Value::Object(members) => Some(*members),
+            _ => None,
+        }
+    }
+    /// Get the value of an object's member
+    ///
+    /// Returns `None` if the value is not an object or does not have the member.
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+}
+
+/// A JSON string, kept in its escaped form
+#[derive(Clone, Copy)]
+pub struct JsonStr<'a> {
+    /// The escaped string between its quotes, which has been validated
+    raw: &'a str,
+}
+
+impl<'a> JsonStr<'a> {
+    /// Get the string in its escaped form, without its quotes
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+    /// Get the string as a [`str`](prim@str) if it has no escapes
+    pub fn as_str(&self) -> Option<&'a str> {
+        Some(self.raw).filter(|raw| !raw.contains('\\'))
+    }
+    /// Get an iterator over the unescaped [`char`]s of the string
+    pub fn chars(&self) -> Chars<'a> {
+        Chars {
+            raw: self.raw.chars(),
+        }
+    }
+    /// Unescape the string into a buffer, or return `None` if it does not fit
+    fn unescape_into<'b>(&self, buffer: &'b mut [u8]) -> Option<&'b str> {
+        let mut len = 0;
+        for c in self.chars() {
+            let encoded = buffer.get_mut(len..len + c.len_utf8())?;
+            len += c.encode_utf8(encoded).len();
+        }
+        Some(str::from_utf8(&buffer[..len]).expect("unescaped string is valid UTF-8"))
+    }
+}
+
+/// An iterator over the unescaped [`char`]s of a [`JsonStr`]
+///
+/// This is returned by [`JsonStr::chars`].
+pub struct Chars<'a> {
+    raw: str::Chars<'a>,
+}
+
+impl<'a> Chars<'a> {
+    fn hex4(&mut self) -> u32 {
+        (0..4).fold(0, |n, _| {
+            let digit = self.raw.next().and_then(|c| c.to_digit(16));
+            n * 16 + digit.expect("escape was validated")
+        })
+    }
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.raw.next()?;
+        if c != '\\' {
+            return Some(c);
+        }
+        Some(match self.raw.next().expect("escape was validated") {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let unit = self.hex4();
+                let code = if (0xd800..0xdc00).contains(&unit) {
+                    self.raw.nth(1);
+                    0x10000 + ((unit - 0xd800) << 10) + (self.hex4() - 0xdc00)
+                } else {
+                    unit
+                };
+                char::from_u32(code).expect("escape was validated")
+            }
+            c => c,
+        })
+    }
+}
+
+impl<'a> PartialEq<str> for JsonStr<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl<'a> PartialEq<&str> for JsonStr<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> fmt::Debug for JsonStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.chars() {
+            fmt::Display::fmt(&c.escape_debug(), f)?;
+        }
+        f.write_char('"')
+    }
+}
+
+impl<'a> fmt::Display for JsonStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| f.write_char(c))
+    }
+}
+
+/// The error returned when parsing JSON fails
+///
+/// Each variant except [`Error::UnexpectedEnd`] has the byte offset of the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// The input ended before the value did
+    UnexpectedEnd,
+    /// A byte that cannot appear here
+    UnexpectedByte(usize),
+    /// A malformed escape in a string
+    InvalidEscape(usize),
+    /// A malformed number
+    InvalidNumber(usize),
+    /// Arrays and objects are nested deeper than the maximum depth
+    TooDeep(usize),
+    /// An object key with escapes is longer than [`MAX_ESCAPED_KEY_LEN`] when unescaped
+    KeyTooLong(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEnd => f.write_str("unexpected end of input"),
+            Error::UnexpectedByte(i) => write!(f, "unexpected byte at offset {}", i),
+            Error::InvalidEscape(i) => write!(f, "invalid escape at offset {}", i),
+            Error::InvalidNumber(i) => write!(f, "invalid number at offset {}", i),
+            Error::TooDeep(i) => write!(f, "nesting too deep at offset {}", i),
+            Error::KeyTooLong(i) => write!(f, "escaped key too long at offset {}", i),
+        }
+    }
+}
+
+/// Parse a JSON document, call a continuation function on its value, and
+/// return its result
+///
+/// Arrays and objects may be nested up to [`DEFAULT_MAX_DEPTH`] deep.
+///
+/// # Example
+/// ```
+/// use nolloc::json;
+///
+/// let reading = r#"{"sensor": "t\u00e9mp", "values": [20.5, 21], "ok": true}"#;
+/// json::parse(reading, |value| {
+///     assert_eq!(value.get("sensor").unwrap().as_str().unwrap(), "témp");
+///     assert_eq!(value.get("ok").and_then(|ok| ok.as_bool()), Some(true));
+///     let values = value.get("values").unwrap().as_array().unwrap();
+///     assert!(values.iter().filter_map(|v| v.as_f64()).eq([21.0, 20.5]));
+/// })
+/// .unwrap();
+///
+/// assert_eq!(json::parse("[1, 2,]", |_| ()), Err(json::Error::UnexpectedByte(6)));
+/// ```
+pub fn parse<F, R>(input: &str, then: F) -> Result<R, Error>
+where
+    F: FnOnce(&Value) -> R,
+{
+    parse_with_max_depth(input, DEFAULT_MAX_DEPTH, then)
+}
+
+/// Parse a JSON document whose arrays and objects are nested at most
+/// `max_depth` deep, call a continuation function on its value, and return its
+/// result
+pub fn parse_with_max_depth<F, R>(input: &str, max_depth: usize, then: F) -> Result<R, Error>
+where
+    F: FnOnce(&Value) -> R,
+{
+    let parser = Parser {
+        src: input,
+        max_depth,
+    };
+    let mut then = Some(then);
+    let mut result = None;
+    parser.value(0, 0, &mut |value, pos| {
+        let pos = parser.skip_whitespace(pos);
+        if pos < input.len() {
+            return Err(Error::UnexpectedByte(pos));
+        }
+        let then = then.take().expect("continuation called more than once");
+        result = Some(then(&value));
+        Ok(())
+    })?;
+    Ok(result.expect("continuation was called"))
+}
+
+type ValueThen<'t> = dyn FnMut(Value<'_>, usize) -> Result<(), Error> + 't;
+
+struct Parser<'s> {
+    src: &'s str,
+    max_depth: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn byte(&self, pos: usize) -> Option<u8> {
+        self.src.as_bytes().get(pos).copied()
+    }
+    fn skip_whitespace(&self, pos: usize) -> usize {
+        let bytes = &self.src.as_bytes()[pos..];
+        pos + bytes
+            .iter()
+            .take_while(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            .count()
+    }
+    fn skip_digits(&self, pos: usize) -> usize {
+        let bytes = self.src.as_bytes().get(pos..).unwrap_or_default();
+        pos + bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+    }
+    /// Get the position after a byte, or an error if the byte is not there
+    fn expect(&self, pos: usize, byte: u8) -> Result<usize, Error> {
+        match self.byte(pos) {
+            Some(b) if b == byte => Ok(pos + 1),
+            Some(_) => Err(Error::UnexpectedByte(pos)),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+    fn literal(&self, pos: usize, literal: &str) -> Result<usize, Error> {
+        let end = pos + literal.len();
+        match self.src.as_bytes().get(pos..end) {
+            Some(bytes) if bytes == literal.as_bytes() => Ok(end),
+            Some(_) => Err(Error::UnexpectedByte(pos)),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+    fn value(&self, pos: usize, depth: usize, then: &mut ValueThen) -> Result<(), Error> {
+        let pos = self.skip_whitespace(pos);
+        match self.byte(pos) {
+            None => Err(Error::UnexpectedEnd),
+            Some(b'n') => then(Value::Null, self.literal(pos, "null")?),
+            Some(b't') => then(Value::Bool(true), self.literal(pos, "true")?),
+            Some(b'f') => then(Value::Bool(false), self.literal(pos, "false")?),
+            Some(b'"') => {
+                let (s, pos) = self.string(pos)?;
+                then(Value::String(s), pos)
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let (n, pos) = self.number(pos)?;
+                then(Value::Number(n), pos)
+            }
+            Some(b'[' | b'{') if depth == self.max_depth => Err(Error::TooDeep(pos)),
+            Some(b'[') => self.elements(pos + 1, depth + 1, &List::new(), then),
+            Some(b'{') => self.members(pos + 1, depth + 1, &Map::new(), then),
+            Some(_) => Err(Error::UnexpectedByte(pos)),
+        }
+    }
+    /// Parse the rest of an array's elements onto a list
+    fn elements(
+        &self,
+        pos: usize,
+        depth: usize,
+        elements: &List<Value>,
+        then: &mut ValueThen,
+    ) -> Result<(), Error> {
+        let pos = self.skip_whitespace(pos);
+        if elements.is_empty() && self.byte(pos) == Some(b']') {
+            return then(Value::Array(*elements), pos + 1);
+        }
+        self.value(pos, depth, &mut |element, pos| {
+            elements.push(element, |elements| {
+                let pos = self.skip_whitespace(pos);
+                match self.byte(pos) {
+                    Some(b',') => self.elements(pos + 1, depth, elements, then),
+                    Some(b']') => then(Value::Array(*elements), pos + 1),
+                    Some(_) => Err(Error::UnexpectedByte(pos)),
+                    None => Err(Error::UnexpectedEnd),
+                }
+            })
+        })
+    }
+    /// Parse the rest of an object's members into a map
+    fn members(
+        &self,
+        pos: usize,
+        depth: usize,
+        members: &Map<&str, Value>,
+        then: &mut ValueThen,
+    ) -> Result<(), Error> {
+        let pos = self.skip_whitespace(pos);
+        if members.is_empty() && self.byte(pos) == Some(b'}') {
+            return then(Value::Object(*members), pos + 1);
+        }
+        self.expect(pos, b'"')?;
+        let (key, key_end) = self.string(pos)?;
+        let value_pos = self.expect(self.skip_whitespace(key_end), b':')?;
+        match key.as_str() {
+            Some(key) => self.member(key, value_pos, depth, members, then),
+            None => {
+                let mut buffer = [0; MAX_ESCAPED_KEY_LEN];
+                let key = key
+                    .unescape_into(&mut buffer)
+                    .ok_or(Error::KeyTooLong(pos))?;
+                self.member(key, value_pos, depth, members, then)
+            }
+        }
+    }
+    /// Parse a member's value, insert it into a map, and parse the rest
+    fn member(
+        &self,
+        key: &str,
+        pos: usize,
+        depth: usize,
+        members: &Map<&str, Value>,
+        then: &mut ValueThen,
+    ) -> Result<(), Error> {
+        self.value(pos, depth, &mut |value, pos| {
+            members.insert(key, value, |members| {
+                let pos = self.skip_whitespace(pos);
+                match self.byte(pos) {
+                    Some(b',') => self.members(pos + 1, depth, members, then),
+                    Some(b'}') => then(Value::Object(*members), pos + 1),
+                    Some(_) => Err(Error::UnexpectedByte(pos)),
+                    None => Err(Error::UnexpectedEnd),
+                }
+            })
+        })
+    }
+    /// Read 4 hex digits of a `\u` escape
+    fn hex4(&self, pos: usize) -> Option<u32> {
+        let digits = self.src.as_bytes().get(pos..pos + 4)?;
+        digits
+            .iter()
+            .try_fold(0, |n, &b| Some(n * 16 + char::from(b).to_digit(16)?))
+    }
+    /// Validate a string starting at its opening quote
+    fn string(&self, pos: usize) -> Result<(JsonStr<'s>, usize), Error> {
+        let start = pos + 1;
+        let mut i = start;
+        loop {
+            match self.byte(i) {
+                None => return Err(Error::UnexpectedEnd),
+                Some(b'"') => {
+                    let raw = &self.src[start..i];
+                    return Ok((JsonStr { raw }, i + 1));
+                }
+                Some(b'\\') => match self.byte(i + 1) {
+                    Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => i += 2,
+                    Some(b'u') => {
+                        let unit = self.hex4(i + 2).ok_or(Error::InvalidEscape(i))?;
+                        if (0xd800..0xdc00).contains(&unit) {
+                            // A high surrogate must be followed by an escaped low one
+                            let low = Some(i + 6)
+                                .filter(|&j| {
+                                    self.src.get(j..).is_some_and(|s| s.starts_with("\\u"))
+                                })
+                                .and_then(|j| self.hex4(j + 2));
+                            if !low.is_some_and(|low| (0xdc00..0xe000).contains(&low)) {
+                                return Err(Error::InvalidEscape(i));
+                            }
+                            i += 12;
+                        } else if (0xdc00..0xe000).contains(&unit) {
+                            return Err(Error::InvalidEscape(i));
+                        } else {
+                            i += 6;
+                        }
+                    }
+                    Some(_) => return Err(Error::InvalidEscape(i)),
+                    None => return Err(Error::UnexpectedEnd),
+                },
+                Some(b) if b < 0x20 => return Err(Error::UnexpectedByte(i)),
+                Some(_) => i += 1,
+            }
+        }
+    }
+    fn number(&self, pos: usize) -> Result<(f64, usize), Error> {
+        let invalid = Error::InvalidNumber(pos);
+        let mut i = pos;
+        if self.byte(i) == Some(b'-') {
+            i += 1;
+        }
+        match self.byte(i) {
+            Some(b'0') => i += 1,
+            Some(b'1'..=b'9') => i = self.skip_digits(i),
+            _ => return Err(invalid),
+        }
+        if self.byte(i) == Some(b'.') {
+            let end = self.skip_digits(i + 1);
+            if end == i + 1 {
+                return Err(invalid);
+            }
+            i = end;
+        }
+        if let Some(b'e' | b'E') = self.byte(i) {
+            i += 1;
+            if let Some(b'+' | b'-') = self.byte(i) {
+                i += 1;
+            }
+            let end = self.skip_digits(i);
+            if end == i {
+                return Err(invalid);
+            }
+            i = end;
+        }
+        let n = self.src[pos..i].parse().map_err(|_| invalid)?;
+        Ok((n, i))
+    }
+}
+
+#[test]
+fn json_documents() {
+    let doc = r#" {
+        "name": "sn\"ow \ud83d\udc27",
+        "tags": ["a", [], {}, null, -0.5e1],
+        "n\u0061me": "shadow",
+        "nested": {"deep": [[true]]}
+    } "#;
+    parse(doc, |value| {
+        assert_eq!(value.get("name").unwrap().as_str().unwrap(), "shadow");
+        let members = value.as_object().unwrap();
+        assert_eq!(members.len(), 4);
+        let tags = value.get("tags").unwrap().as_array().unwrap();
+        assert_eq!(tags.len(), 5);
+        assert_eq!(tags.head().unwrap().as_f64(), Some(-5.0));
+        assert!(tags.iter().nth(1).unwrap().is_null());
+        let deep = value.get("nested").unwrap().get("deep").unwrap();
+        let inner = deep.as_array().unwrap().head().unwrap().as_array().unwrap();
+        assert_eq!(inner.head().unwrap().as_bool(), Some(true));
+    })
+    .unwrap();
+    parse(r#""sn\"ow \ud83d\udc27""#, |value| {
+        let s = value.as_str().unwrap();
+        assert_eq!(s, "sn\"ow 🐧");
+        assert_eq!(s.as_str(), None);
+    })
+    .unwrap();
+    let error = |input| parse_with_max_depth(input, 2, |_| ()).unwrap_err();
+    assert_eq!(error("[[[]]]"), Error::TooDeep(2));
+    assert_eq!(error(r#"{"a" 1}"#), Error::UnexpectedByte(5));
+    assert_eq!(error(r#""\ud800x""#), Error::InvalidEscape(1));
+    assert_eq!(error(r#""\q""#), Error::InvalidEscape(1));
+    assert_eq!(error("01"), Error::UnexpectedByte(1));
+    assert_eq!(error("1."), Error::InvalidNumber(0));
+    assert_eq!(error("[1, 2"), Error::UnexpectedEnd);
+    assert_eq!(error("nul"), Error::UnexpectedEnd);
+    assert_eq!(error("\"a\nb\""), Error::UnexpectedByte(2));
+    let mut long_key = crate::FixedString::<80>::new();
+    long_key.push_str(r#"{"\u0041"#).unwrap();
+    (0..64).for_each(|_| long_key.push('a').unwrap());
+    long_key.push_str(r#"": 1}"#).unwrap();
+    assert_eq!(error(&long_key), Error::KeyTooLong(1));
+}
@@ -0,0 +1,234 @@
+//! Parsing `key=value` pairs into a [`Map`]
+//!
+//! [`parse_kv`] borrows keys and values straight from the input.
+//! [`parse_kv_with`] can also unescape and percent-decode them, in which case
+//! each decoded key and value is written into a buffer of `N` bytes in the
+//! stack frame of its pair.
+
+use core::{fmt, str};
+
+use crate::Map;
+
+/// How [`parse_kv_with`] decodes keys and values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct KvOptions {
+    /// A [`char`] that makes the [`char`] after it literal, so that separators
+    /// can appear in keys and values
+    pub escape: Option<char>,
+    /// Whether `%` followed by two hex digits is decoded to that byte
+    pub percent_decode: bool,
+    /// Whether `+` is decoded to a space
+    pub plus_as_space: bool,
+}
+
+impl KvOptions {
+    /// Options for decoding URL query strings, which are percent-encoded and
+    /// encode spaces as `+`
+    pub fn query() -> Self {
+        KvOptions {
+            escape: None,
+            percent_decode: true,
+            plus_as_space: true,
+        }
+    }
+    /// Check if a raw key or value needs to be decoded
+    fn needs_decoding(&self, raw: &str) -> bool {
+        self.escape.is_some_and(|escape| raw.contains(escape))
+            || self.percent_decode && raw.contains('%')
+            || self.plus_as_space && raw.contains('+')
+    }
+    /// Decode a raw key or value, into a buffer if it needs to be
+    fn decode<'s>(&self, raw: &'s str, buffer: &'s mut [u8]) -> Result<&'s str, KvError> {
+        if !self.needs_decoding(raw) {
+            return Ok(raw);
+        }
+        let mut escape = [0; 4];
+        let escape = self.escape.map(|c| c.encode_utf8(&mut escape).as_bytes());
+        let bytes = raw.as_bytes();
+        let (mut i, mut len) = (0, 0);
+        while i < bytes.len() {
+            let byte = match escape {
+                Some(escape)
+                    if bytes[i..].starts_with(escape) && i + escape.len() < bytes.len() =>
+                {
+                    i += escape.len();
+                    bytes[i]
+                }
+                _ if self.percent_decode && bytes[i] == b'%' => {
+                    let digit = |j: usize| match bytes.get(j) {
+                        Some(&byte @ b'0'..=b'9') => Ok(byte - b'0'),
+                        Some(&byte @ b'a'..=b'f') => Ok(byte - b'a' + 10),
+                        Some(&byte @ b'A'..=b'F') => Ok(byte - b'A' + 10),
+                        _ => Err(KvError::InvalidPercent),
+                    };
+                    let byte = digit(i + 1)? << 4 | digit(i + 2)?;
+                    i += 2;
+                    byte
+                }
+                _ if self.plus_as_space && bytes[i] == b'+' => b' ',
+                _ => bytes[i],
+            };
+            *buffer.get_mut(len).ok_or(KvError::Overflow)? = byte;
+            len += 1;
+            i += 1;
+        }
+        str::from_utf8(&buffer[..len]).map_err(|_| KvError::InvalidUtf8)
+    }
+}
+
+/// The error returned when [`parse_kv_with`] fails to decode a key or value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KvError {
+    /// A decoded key or value does not fit in its buffer
+    Overflow,
+    /// A `%` is not followed by two hex digits
+    InvalidPercent,
+    /// A decoded key or value is not valid UTF-8
+    InvalidUtf8,
+}
+
+impl fmt::Display for KvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KvError::Overflow => "decoded key or value does not fit in its buffer",
+            KvError::InvalidPercent => "% is not followed by two hex digits",
+            KvError::InvalidUtf8 => "decoded key or value is not valid UTF-8",
+        })
+    }
+}
+
+/// Parse pairs of keys and values into a map and call a continuation function
+/// on it
+///
+/// Pairs are separated by `pair_sep`, and each key is separated from its value
+/// by the first `kv_sep` in its pair. A pair without `kv_sep` has an empty
+/// value, and empty pairs are skipped. If a key appears more than once, its
+/// last value shadows the others.
+///
+/// # Example
+/// ```
+/// use nolloc::kv::parse_kv;
+///
+/// parse_kv("mode=fast;retries=3;verbose", ';', '=', |config| {
+///     assert_eq!(config.get("retries"), Some(&"3"));
+///     assert_eq!(config.get("verbose"), Some(&""));
+///     assert_eq!(config.len(), 3);
+/// });
+/// ```
+pub fn parse_kv<F, R>(input: &str, pair_sep: char, kv_sep: char, then: F) -> R
+where
+    F: FnOnce(&Map<&str, &str>) -> R,
+{
+    parse_kv_with::<0, _, _>(input, pair_sep, kv_sep, KvOptions::default(), then)
+        .expect("pairs are not decoded without options")
+}
+
+/// Parse pairs of keys and values into a map, decoding them with some options,
+/// and call a continuation function on it
+///
+/// Pairs are split as by [`parse_kv`], except that escaped separators do not
+/// split. Keys and values that need decoding are decoded into buffers of `N`
+/// bytes, and others are borrowed from the input.
+///
+/// # Example
+/// ```
+/// use nolloc::kv::{parse_kv_with, KvOptions};
+///
+/// let query = "q=caf%C3%A9+au+lait&page=2";
+/// let search = parse_kv_with::<32, _, _>(query, '&', '=', KvOptions::query(), |params| {
+///     params.get("q").map(|q| q.len())
+/// });
+/// assert_eq!(search, Ok(Some(13)));
+///
+/// let options = KvOptions { escape: Some('\\'), ..KvOptions::default() };
+/// parse_kv_with::<16, _, _>(r"path=C:\\temp, sep=\,", ',', '=', options, |pairs| {
+///     assert_eq!(pairs.get("path"), Some(&r"C:\temp"));
+///     assert_eq!(pairs.get(" sep"), Some(&","));
+/// })
+/// .unwrap();
+/// ```
+pub fn parse_kv_with<const N: usize, F, R>(
+    input: &str,
+    pair_sep: char,
+    kv_sep: char,
+    options: KvOptions,
+    then: F,
+) -> Result<R, KvError>
+where
+    F: FnOnce(&Map<&str, &str>) -> R,
+{
+    let mut rest = Some(input);
+    let pairs = core::iter::from_fn(|| {
+        let (pair, after) = split_once(rest?, pair_sep, options.escape);
+        rest = after;
+        Some(pair)
+    });
+    insert_pairs::<N, _, _, _>(
+        &Map::new(),
+        pairs.filter(|pair| !pair.is_empty()),
+        kv_sep,
+        options,
+        then,
+    )
+}
+
+fn insert_pairs<'i, const N: usize, I, F, R>(
+    map: &Map<&str, &str>,
+    mut pairs: I,
+    kv_sep: char,
+    options: KvOptions,
+    then: F,
+) -> Result<R, KvError>
+where
+    I: Iterator<Item = &'i str>,
+    F: FnOnce(&Map<&str, &str>) -> R,
+{
+    let pair = match pairs.next() {
+        Some(pair) => pair,
+        None => return Ok(then(map)),
+    };
+    let (raw_key, raw_value) = split_once(pair, kv_sep, options.escape);
+    let (mut key_buffer, mut value_buffer) = ([0; N], [0; N]);
+    let key = options.decode(raw_key, &mut key_buffer)?;
+    let value = options.decode(raw_value.unwrap_or(""), &mut value_buffer)?;
+    map.insert(key, value, |map| {
+        insert_pairs::<N, _, _, _>(map, pairs, kv_sep, options, then)
+    })
+}
+
+/// Split a string at the first separator that is not escaped
+fn split_once(s: &str, separator: char, escape: Option<char>) -> (&str, Option<&str>) {
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if Some(c) == escape {
+            chars.next();
+        } else if c == separator {
+            return (&s[..i], Some(&s[i + c.len_utf8()..]));
+        }
+    }
+    (s, None)
+}
+
+#[test]
+fn kv_decoding() {
+    let options = KvOptions {
+        escape: Some('\\'),
+        ..KvOptions::query()
+    };
+    let input = r"a=1&&b=x\=y&c&a=%41%2b+&d=\%41\\";
+    parse_kv_with::<4, _, _>(input, '&', '=', options, |map| {
+        // The second `a` shadows the first
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get("a"), Some(&"A+ "));
+        assert_eq!(map.get("b"), Some(&"x=y"));
+        assert_eq!(map.get("c"), Some(&""));
+        assert_eq!(map.get("d"), Some(&r"%41\"));
+    })
+    .unwrap();
+    let query = KvOptions::query();
+    let error = |input| parse_kv_with::<2, _, _>(input, '&', '=', query, |_| ()).unwrap_err();
+    assert_eq!(error("a=%4"), KvError::InvalidPercent);
+    assert_eq!(error("a=%+f"), KvError::InvalidPercent);
+    assert_eq!(error("a=%ff"), KvError::InvalidUtf8);
+    assert_eq!(error("a=+++"), KvError::Overflow);
+}
@@ -7,15 +7,83 @@
 
 This crate provides growable collections that do not use dynamic memory allocation.
 
-It is fully `no_std` compatible, and it has no dependencies.
+It is fully `no_std` compatible, and it has no required dependencies.
 
 # Collections
 
-This crate currently provides 3 collections which keep their items entirely on the stack:
+This crate currently provides 22 collections which keep their items entirely on the stack:
 
 - [`List`] - a singly-linked list
+- [`Vector`] - a sequence with O(logn) indexing and pushing to either end
+- [`Deque`] - a double-ended queue with O(1) access to both ends
+- [`Queue`] - a first-in-first-out queue with O(1) amortized enqueueing and dequeueing
+- [`Heap`] - a min-priority queue with O(1) peeking and O(logn) pushing and popping
 - [`Map`] - an append-only key-value map with O(logn) lookup and insertion
-- [`Set`] - an append-only set with O(logn) lookup and insertion
+- [`HashMap`] - a key-value map with O(1) average lookup for keys that hash but are not ordered
+- [`BTreeMap`] - a key-value map with unique keys and several entries per tree node
+- [`OrderedMap`] - a key-value map with unique keys that iterates in insertion order
+- [`Set`] - a set with O(logn) lookup, insertion and removal
+- [`SortedList`] - a sequence kept in ascending order, with O(logn) search by value or index
+- [`Bag`] - a multiset that counts occurrences of each item
+- [`Trie`] - a map with byte string keys and longest-prefix matching
+- [`Graph`] - a directed graph with breadth-first and depth-first traversals
+- [`IntervalMap`] and [`IntervalSet`] - maps and sets of intervals with point and overlap queries
+- [`UnionFind`] - disjoint sets with O(log²n) lookup of each item's set
+- [`Interner`] - a string interner that hands out symbols comparable in O(1)
+- [`StrList`] - a string built from [`str`] fragments
+- [`BytesList`] - a byte buffer built from byte slice segments
+- [`Rope`] - a string of [`str`] chunks that can be concatenated in O(1) and sliced in O(depth)
+- [`Path`] - a path of [`str`] segments that can be joined, normalized and displayed
+
+# Fixed-Capacity Types
+
+Values built inside a continuation cannot outlive it if they borrow from the stack.
+This crate also provides owned types with a capacity fixed at compile time,
+which can be returned out of a continuation by value:
+
+- [`BitSet`] - a set of small integers stored as bits
+- [`Bloom`] - a probabilistic set for ruling out items before an expensive lookup
+- [`FixedString`] - a mutable string stored inline
+- [`FixedVec`] - a mutable vector stored inline
+- [`Grid`] - a two-dimensional grid with row, column and neighbor iteration
+- [`RangeMin`] and [`RangeSum`] - sequences that find the minimum or sum of any range in O(logn)
+- [`RingBuffer`] - a circular buffer that can overwrite its oldest items
+- [`Slab`] - a pool of items with keys that stay valid until their item is removed
+- [`SmallMap`] and [`SmallSet`] - a mutable map and set of up to N items that can be returned by value
+- [`SparseSet`] - a set of small integers with O(1) insertion, removal and clearing
+
+Iterators of known length can also be collected straight into arrays with
+[`array::collect_array`] and [`array::collect_array_padded`].
+
+# Stack Placement
+
+The collections place their items in the stack frames of the functions that insert
+them. The same technique is available for any value:
+
+- [`StackBox`] - a mutable handle to a value placed in a lower stack frame
+- [`Arena`] - a scope that places values in stack frames and keeps count of them
+
+# Text
+
+These modules render and split text without allocating:
+
+- [`fmt::format`] and [`format_collect!`] - `format!` that passes a [`str`] to a continuation
+- [`fmt::str_from_chars`] - the same for an iterator of [`char`]s
+- [`fmt::StackWriter`] - a [`Write`](core::fmt::Write) sink that can truncate or add an ellipsis on overflow
+- [`fmt::write_int`] and [`fmt::write_float`] - number rendering that avoids the core formatting machinery
+- [`encoding`] - hex and Base64 encoding and decoding into stack buffers
+- [`str_ext`] - splitting strings into [`List`]s of pieces
+- [`kv`] - parsing `key=value` pairs and query strings into a [`Map`]
+- [`json`] - parsing JSON into values made of [`List`]s and [`Map`]s
+- [`csv`] - parsing CSV records into [`List`]s of fields
+- [`args`] - parsing command-line arguments into a [`Map`] of options and a [`List`] of positionals
+
+# Statistics
+
+- [`stats::summarize`] - count, mean, variance and range of numbers in one pass without storage
+- [`stats::median`] and [`stats::percentile`] - order statistics of items collected into a [`Set`]
+- [`random::sample`](random/fn.sample.html) and [`List::choose`](list/struct.List.html#method.choose) - unbiased random selection with a `rand_core` generator, with the `rand` feature
+- [`window::window_min`] and [`window::window_max`] - extremes of a sliding window over an iterator in O(1) amortized time
 
 # Use Cases
 
@@ -61,8 +129,63 @@ you could be collecting as well as their size. All the elements are collected on
 not careful, you can get a stack overflow!
 */
 
+pub mod arena;
+pub mod args;
+pub mod array;
+pub mod bag;
+pub mod bit_set;
+pub mod bloom;
+pub mod btree_map;
+pub mod bytes_list;
+pub mod csv;
+pub mod deque;
+pub mod encoding;
+pub mod fixed_string;
+pub mod fixed_vec;
+pub mod fmt;
+pub mod graph;
+pub mod grid;
+pub mod hash_map;
+pub mod heap;
+pub mod interner;
+pub mod interval_map;
+pub mod interval_set;
+pub mod json;
+pub mod kv;
 pub mod list;
 pub mod map;
+pub mod ordered_map;
+pub mod path;
+pub mod queue;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod range_min;
+pub mod range_sum;
+pub mod ring_buffer;
+pub mod rope;
 pub mod set;
-
-pub use {list::List, map::Map, set::Set};
+pub mod slab;
+pub mod small_map;
+pub mod small_set;
+pub mod sorted_list;
+pub mod sparse_set;
+pub mod stack_box;
+pub mod stats;
+pub mod str_ext;
+pub mod str_list;
+mod tree;
+pub mod trie;
+pub mod union_find;
+pub mod vector;
+pub mod window;
+
+pub use {
+    arena::Arena, bag::Bag, bit_set::BitSet, bloom::Bloom, btree_map::BTreeMap,
+    bytes_list::BytesList, deque::Deque, fixed_string::FixedString, fixed_vec::FixedVec,
+    graph::Graph, grid::Grid, hash_map::HashMap, heap::Heap, interner::Interner,
+    interval_map::IntervalMap, interval_set::IntervalSet, list::List, map::Map,
+    ordered_map::OrderedMap, path::Path, queue::Queue, range_min::RangeMin, range_sum::RangeSum,
+    ring_buffer::RingBuffer, rope::Rope, set::Set, slab::Slab, small_map::SmallMap,
+    small_set::SmallSet, sorted_list::SortedList, sparse_set::SparseSet, stack_box::StackBox,
+    str_list::StrList, trie::Trie, union_find::UnionFind, vector::Vector,
+};
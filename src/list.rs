@@ -135,6 +135,18 @@ impl<'a, T> List<'a, T> {
     {
         self.iter().any(|i| i == item)
     }
+    /// Choose an item uniformly at random, or `None` if the list is empty
+    ///
+    /// This requires the `rand` feature. See [`random`](crate::random).
+    ///
+    /// This is an **O(n)** operation.
+    #[cfg(feature = "rand")]
+    pub fn choose(&self, rng: &mut impl rand_core::RngCore) -> Option<&'a T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.iter().nth(crate::random::random_index(rng, self.len))
+    }
     /// Collect an iterator into a list, call a continuation function on it
     ///
     /// The items in the list will be in reversed order. To make the list's order
@@ -272,6 +284,12 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter { node: self.node }
+    }
+}
+
 impl<'a, T> IntoIterator for &'a List<'a, T> {
     type Item = &'a T;
     type IntoIter = Iter<'a, T>;
@@ -299,10 +317,7 @@ impl<'a, T> Default for List<'a, T> {
 
 impl<'a, T> Clone for List<'a, T> {
     fn clone(&self) -> Self {
-        List {
-            head: self.head,
-            len: self.len,
-        }
+        *self
     }
 }
 
@@ -1,20 +1,55 @@
 //! A growable key-value map where all items exist on the stack
+//!
+//! Entries are kept in two structures: a chain in insertion order, which
+//! backs [`Map::head`], [`Map::rest`] and iteration, and a persistent balanced
+//! search tree, which backs lookups. Inserting an entry copies the
+//! **O(logn)** tree nodes along its search path into a single stack frame.
 
-use core::{borrow::Borrow, fmt, ops::Index, ptr};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::{FusedIterator, Peekable},
+    ops::Index,
+    ptr,
+};
+
+use crate::{
+    tree::{self, InOrder, Node},
+    List, Set,
+};
 
 /// A growable key-value map where all items exist on the stack
+///
+/// Maps implement [`PartialOrd`] and [`Hash`] over their entries that are not
+/// shadowed, in key order. They do not implement [`Ord`], because its `min`
+/// and `max` would be called instead of [`Map::min`] and [`Map::max`] on map
+/// values. Use [`Map::cmp_entries`] for a total order.
 pub struct Map<'a, K, V> {
     head: Option<&'a MapNode<'a, K, V>>,
+    root: Option<&'a MapTree<'a, K, V>>,
     len: usize,
+    sorted: usize,
 }
 
 struct MapNode<'a, K, V> {
     key: K,
     value: V,
-    left: Option<&'a Self>,
-    right: Option<&'a Self>,
+    /// The map this entry was inserted into
+    ///
+    /// For entries built by [`Map::collect_sorted`], only `head` is set.
+    prev: Map<'a, K, V>,
+    /// The 1-based position of an entry built by [`Map::collect_sorted`], or 0
+    ///
+    /// Such an entry is only visible in maps whose `sorted` is at least this.
+    ordinal: usize,
+    /// The entry for the same key that this one shadows
+    shadowed: Option<&'a MapNode<'a, K, V>>,
 }
 
+type MapTree<'a, K, V> = Node<'a, MapNode<'a, K, V>>;
+
 impl<'a, K, V> Map<'a, K, V>
 where
     K: PartialOrd,
@@ -23,6 +58,74 @@ where
     pub fn new() -> Self {
         Map::default()
     }
+    /// Get all entries inserterd after the most recent one
+    ///
+    /// On a map built by [`Map::collect_sorted`], the removed entry stays in
+    /// the search tree and is skipped. Walks in key order, such as
+    /// [`Map::min`] and [`Map::max`], may skip every removed entry, so on
+    /// such a map they are **O(n)** in the worst case.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([1, 2, 3, 4].iter().map(|&i| (i, i)), |map| {
+    ///     assert!(!map.rest().contains_key(&4));
+    /// });
+    /// ```
+    pub fn rest(&self) -> Self {
+        let head = if let Some(head) = self.head {
+            head
+        } else {
+            return Map::new();
+        };
+        if head.ordinal == 0 {
+            head.prev
+        } else {
+            Map {
+                head: head.prev.head,
+                root: self.root,
+                len: self.len - 1,
+                sorted: head.ordinal - 1,
+            }
+        }
+    }
+    /// Get the key-value pair with the minimum key in the map
+    ///
+    /// This is an **O(logn)** operation, but entries removed by [`Map::rest`]
+    /// from a map built by [`Map::collect_sorted`] stay in the tree and are
+    /// skipped, so it is **O(n)** in the worst case.
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let node = tree::first(self.root, &|node| self.is_visible(node))?;
+        Some((&node.key, &node.value))
+    }
+    /// Get the key-value pair with the maximum key in the map
+    ///
+    /// This is an **O(logn)** operation, but entries removed by [`Map::rest`]
+    /// from a map built by [`Map::collect_sorted`] stay in the tree and are
+    /// skipped, so it is **O(n)** in the worst case.
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let node = tree::last(self.root, &|node| self.is_visible(node))?;
+        Some((&node.key, &node.value))
+    }
+    /// Compare two maps by their entries that are not shadowed, in key order
+    ///
+    /// This is the total order that agrees with the map's [`PartialOrd`]
+    /// implementation. `Map` does not implement [`Ord`], whose `min` and `max`
+    /// would be called instead of [`Map::min`] and [`Map::max`] on map values.
+    ///
+    /// This is an **O(n)** operation.
+    pub fn cmp_entries(&self, other: &Self) -> Ordering
+    where
+        K: Ord,
+        V: Ord,
+    {
+        let entries = |map: &Self| map.sorted_nodes().map(|node| (&node.key, &node.value));
+        entries(self).cmp(entries(other))
+    }
+}
+
+impl<'a, K, V> Map<'a, K, V> {
     /// Check if the map is empty
     pub fn is_empty(&self) -> bool {
         self.len == 0
@@ -47,64 +150,54 @@ where
         let head = self.head?;
         Some((&head.key, &head.value))
     }
-    /// Get all entries inserterd after the most recent one
+    /// Get an iterator over the key-value pairs of the list
+    ///
+    /// The iterator yields items in the opposite order of their insertion.
     ///
     /// # Example
     /// ```
     /// use nolloc::Map;
     ///
-    /// Map::collect([1, 2, 3, 4].iter().map(|&i| (i, i)), |map| {
-    ///     assert!(!map.rest().contains_key(&4));
+    /// // Iteration does not compare keys, so it needs no bounds on them
+    /// fn pairs<K, V>(map: &Map<K, V>) -> usize {
+    ///     map.iter().count()
+    /// }
+    ///
+    /// Map::collect([(1, 'a'), (2, 'b')], |map| {
+    ///     assert_eq!(pairs(map), 2);
+    ///     assert_eq!(map.values().len(), 2);
     /// });
     /// ```
-    pub fn rest(&self) -> Self {
-        let head = if let Some(head) = self.head {
-            head
-        } else {
-            return Map::new();
-        };
-        match (head.left, head.right) {
-            (None, None) => Map::new(),
-            (None, Some(node)) | (Some(node), None) => Map {
-                head: Some(node),
-                len: self.len - 1,
-            },
-            (Some(left), Some(right)) => {
-                let node = if left.contains_child(right) {
-                    left
-                } else {
-                    right
-                };
-                Map {
-                    head: Some(node),
-                    len: self.len - 1,
-                }
-            }
+    pub fn iter(&self) -> Iter<'a, K, V> {
+        Iter {
+            node: self.head,
+            len: self.len,
         }
     }
-    /// Get the key-value pair with the minimum key in the map
+    /// Get an iterator over the keys of the list
     ///
-    /// This is an **O(logn)** operation.
-    pub fn min(&self) -> Option<(&K, &V)> {
-        let mut curr = self.head?;
-        while let Some(left) = curr.left {
-            curr = left;
-        }
-        Some((&curr.key, &curr.value))
+    /// The iterator yields items in the opposite order of their insertion.
+    /// It can be cloned cheaply to make several passes.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([(1, 'a'), (2, 'b'), (3, 'c')], |map| {
+    ///     let keys = map.keys();
+    ///     let pairs = keys.clone().flat_map(|i| keys.clone().map(move |j| (i, j)));
+    ///     assert_eq!(pairs.count(), 9);
+    /// });
+    /// ```
+    pub fn keys(&self) -> Keys<'a, K, V> {
+        Keys { iter: self.iter() }
     }
-    /// Get the key-value pair with the maximum key in the map
+    /// Get an iterator over the values of the list
     ///
-    /// This is an **O(logn)** operation.
-    pub fn max(&self) -> Option<(&K, &V)> {
-        let mut curr = self.head?;
-        while let Some(right) = curr.right {
-            curr = right;
-        }
-        Some((&curr.key, &curr.value))
+    /// The iterator yields items in the opposite order of their insertion.
+    pub fn values(&self) -> Values<'a, K, V> {
+        Values { iter: self.iter() }
     }
-}
-
-impl<'a, K, V> Map<'a, K, V> {
     /// Check if the map contains a key
     ///
     /// This is an **O(logn)** operation.
@@ -125,29 +218,198 @@ impl<'a, K, V> Map<'a, K, V> {
     {
         Some(&self.get_node(key)?.value)
     }
+    /// Get the map's own key equal to the key and its value
+    ///
+    /// Unlike [`Map::get`], the references live as long as the map's entries
+    /// rather than the borrow of the map.
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let node = self.get_node(key)?;
+        Some((&node.key, &node.value))
+    }
+    /// Get an iterator over every value inserted for the key
+    ///
+    /// The iterator yields values in the opposite order of their insertion,
+    /// so the first value is the one returned by [`Map::get`].
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([("a", 1), ("b", 2), ("a", 3), ("a", 4)], |map| {
+    ///     assert_eq!(map.values_for("a").collect::<Vec<_>>(), [&4, &3, &1]);
+    ///     assert_eq!(map.values_for("b").collect::<Vec<_>>(), [&2]);
+    ///     assert_eq!(map.values_for("c").next(), None);
+    /// });
+    /// ```
+    pub fn values_for<Q>(&self, key: &Q) -> ValuesFor<'a, K, V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        ValuesFor {
+            node: self.get_node(key),
+        }
+    }
+    /// Get the number of values for the key that are shadowed by a more
+    /// recently inserted value
+    ///
+    /// This is an **O(logn + m)** operation, where m is the number of shadowed values.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([("a", 1), ("b", 2), ("a", 3), ("a", 4)], |map| {
+    ///     assert_eq!(map.shadowed_count("a"), 2);
+    ///     assert_eq!(map.shadowed_count("b"), 0);
+    ///     assert_eq!(map.shadowed_count("c"), 0);
+    /// });
+    /// ```
+    pub fn shadowed_count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.values_for(key).count().saturating_sub(1)
+    }
     fn get_node<Q>(&self, key: &Q) -> Option<&'a MapNode<'a, K, V>>
     where
         K: Borrow<Q>,
         Q: PartialOrd + ?Sized,
     {
-        let mut curr = self.head?;
-        loop {
-            let curr_key = curr.key.borrow();
-            if key == curr_key {
-                return Some(curr);
-            } else if key < curr_key {
-                curr = curr.left?;
-            } else {
-                curr = curr.right?;
+        let node = tree::find(self.root, |node| tree::compare(key, node.key.borrow()))?.entry;
+        Some(node).filter(|node| self.is_visible(node))
+    }
+    /// Get the depth of the map's search tree
+    ///
+    /// This is the most entries a lookup may have to compare against. The tree is
+    /// rebalanced on every insertion, so its depth is at most about 1.44 log2(n).
+    ///
+    /// This is an **O(1)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect((0..1000).map(|i| (i, i)), |map| {
+    ///     assert!(map.depth() <= 14);
+    ///     assert!(map.is_balanced());
+    /// });
+    /// ```
+    pub fn depth(&self) -> usize {
+        tree::height(self.root).into()
+    }
+    /// Check if the heights of the two subtrees of every node in the map's
+    /// search tree differ by at most one
+    ///
+    /// This is an **O(n)** operation.
+    pub fn is_balanced(&self) -> bool {
+        tree::is_balanced(self.root)
+    }
+    /// Write the shape of the map's search tree in the Graphviz DOT format
+    ///
+    /// Each node is labeled with its key, and each edge with the side of its child.
+    /// Entries hidden by [`Map::rest`] on a map built with [`Map::collect_sorted`]
+    /// are still in the tree and are drawn dashed.
+    ///
+    /// This is an **O(n)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([(2, 'b'), (1, 'a')], |map| {
+    ///     let mut dot = String::new();
+    ///     map.write_dot(&mut dot).unwrap();
+    ///     assert_eq!(
+    ///         dot,
+    ///         "digraph {\n    n0 [label=\"2\"];\n    n0 -> n1 [label=\"left\"];\n    n1 [label=\"1\"];\n}\n"
+    ///     );
+    /// });
+    /// ```
+    pub fn write_dot<W>(&self, w: &mut W) -> fmt::Result
+    where
+        K: fmt::Debug,
+        W: fmt::Write + ?Sized,
+    {
+        use fmt::Write as _;
+        tree::write_dot(self.root, w, &mut |w, node| {
+            write!(w, "label=\"")?;
+            write!(tree::DotEscape(&mut *w), "{:?}", node.key)?;
+            write!(w, "\"")?;
+            if !self.is_visible(node) {
+                write!(w, ", style=dashed")?;
             }
+            Ok(())
+        })
+    }
+    /// Get an iterator over the entries that are not shadowed, most recent first
+    fn live_nodes(&self) -> LiveNodes<'a, K, V> {
+        LiveNodes {
+            node: self.head,
+            map: *self,
+        }
+    }
+    /// Get an iterator over the entries that are not shadowed, in key order
+    fn sorted_nodes(&self) -> SortedNodes<'a, K, V> {
+        SortedNodes {
+            nodes: InOrder::new(self.root),
+            map: *self,
         }
     }
+    /// Get an iterator over the entries that are not shadowed, in reverse key order
+    fn sorted_nodes_rev(&self) -> SortedNodes<'a, K, V> {
+        SortedNodes {
+            nodes: InOrder::rev(self.root),
+            map: *self,
+        }
+    }
+    fn is_visible(&self, node: &MapNode<K, V>) -> bool {
+        node.ordinal <= self.sorted
+    }
 }
 
 impl<'a, K, V> Map<'a, K, V>
 where
     K: PartialOrd,
 {
+    /// Check the invariants of the map's search tree, panicking if any are violated
+    ///
+    /// The keys must be in order, every node must store its correct height,
+    /// and the tree must be balanced.
+    ///
+    /// This requires the `validate` feature.
+    #[cfg(any(test, feature = "validate"))]
+    pub fn debug_validate(&self) {
+        tree::validate(self.root, |a, b| tree::compare(&a.key, &b.key))
+    }
+    /// Get a cursor positioned at the entry with the minimum key
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([(3, 'c'), (1, 'a'), (5, 'e')], |map| {
+    ///     let mut cursor = map.cursor();
+    ///     assert_eq!(cursor.peek(), Some((&1, &'a')));
+    ///     cursor.seek(&2);
+    ///     assert_eq!(cursor.peek(), Some((&3, &'c')));
+    ///     assert_eq!(cursor.next_key(), Some(&5));
+    ///     assert_eq!(cursor.prev_key(), Some(&3));
+    /// });
+    /// ```
+    pub fn cursor(&self) -> MapCursor<'a, K, V> {
+        MapCursor {
+            map: *self,
+            node: tree::first(self.root, &|node| self.is_visible(node)),
+        }
+    }
     /// Insert a key-value pair into the map if it does not already exist and
     /// call a continuation on the new (or old) map
     ///
@@ -167,150 +429,657 @@ where
     ///
     /// If an entry with the key already exists in the map, it is not removed,
     /// but the new entry is still inserted. All lookups on the new map
-    /// will find the most recently inserted entry for a key.
+    /// will find the most recently inserted entry for a key. Shadowed values
+    /// can still be found with [`Map::values_for`].
     ///
     /// This is an **O(logn)** operation.
     pub fn insert<F, R>(&self, key: K, value: V, then: F) -> R
     where
         F: FnOnce(&Map<K, V>) -> R,
     {
-        let mut node = MapNode {
+        let shadowed = self.get_node(&key);
+        let node = MapNode {
             key,
             value,
-            left: None,
-            right: None,
+            prev: *self,
+            ordinal: 0,
+            shadowed,
         };
-        if let Some(head) = self.head {
-            if node.key < head.key {
-                node.right = Some(head);
-                let mut curr = head;
-                while node.key < curr.key {
-                    curr = if let Some(next) = curr.left.or(curr.right) {
-                        next
-                    } else {
-                        break;
-                    }
-                }
-                if node.key > curr.key {
-                    node.left = Some(curr);
-                }
-            } else {
-                node.left = Some(head);
-                let mut curr = head;
-                while node.key >= curr.key {
-                    curr = if let Some(next) = curr.right.or(curr.left) {
-                        next
-                    } else {
-                        break;
-                    }
-                }
-                if node.key < curr.key {
-                    node.right = Some(curr);
-                }
-            }
-        }
-        then(&Map {
-            head: Some(&node),
-            len: self.len + 1,
+        let cmp = |a: &MapNode<K, V>, b: &MapNode<K, V>| tree::compare(&a.key, &b.key);
+        tree::insert(self.root, &node, &cmp, |root| {
+            then(&Map {
+                head: Some(&node),
+                root: Some(root),
+                len: self.len + 1,
+                sorted: self.sorted,
+            })
         })
     }
-    /// Get an iterator over the key-value pairs of the list
+    /// Collect an iterator into a map and call a continuation function on it
     ///
-    /// The iterator yields items in the opposite order of their insertion.
-    pub fn iter(&self) -> Iter<'a, K, V> {
-        Iter { node: self.head }
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let nums = [(1, 2), (3, 4), (5, 6)];
+    ///
+    /// let sum = Map::collect(nums, |map| {
+    ///     let mut sum = 0;
+    ///     for (k, _) in &nums {
+    ///         sum += *map.get(k).unwrap();
+    ///     }
+    ///     sum
+    /// });
+    ///
+    /// assert_eq!(sum, 12);
+    /// ```
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        K: PartialOrd,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&Map<K, V>) -> R,
+    {
+        Map::default().extend(iter, then)
     }
-    /// Get an iterator over the keys of the list
+    /// Collect an iterator that is sorted by key into a map and call a
+    /// continuation function on it
     ///
-    /// The iterator yields items in the opposite order of their insertion.
-    pub fn keys(&self) -> Keys<'a, K, V> {
-        Keys { iter: self.iter() }
+    /// Rather than inserting the pairs one at a time, this builds a perfectly
+    /// balanced tree directly, without comparing keys. This is an **O(n)** operation.
+    ///
+    /// The keys must be in strictly ascending order. This is only checked
+    /// in debug builds. The pairs are considered inserted in iteration order,
+    /// so the last pair is the map's [`head`](Map::head).
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let table = [(1, 'a'), (2, 'b'), (3, 'c'), (5, 'e'), (8, 'h')];
+    ///
+    /// Map::collect_sorted(table, |map| {
+    ///     assert_eq!(map.len(), 5);
+    ///     assert_eq!(map.get(&5), Some(&'e'));
+    ///     assert_eq!(map.head(), Some((&8, &'h')));
+    ///     assert!(!map.rest().contains_key(&8));
+    /// });
+    /// ```
+    pub fn collect_sorted<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (K, V)>,
+        I::IntoIter: ExactSizeIterator,
+        F: FnOnce(&Map<K, V>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        let len = iter.len();
+        let mut then = Some(then);
+        build_sorted(len, &mut iter, None, &mut |_, root, head| {
+            (then.take().expect("continuation called more than once"))(&Map {
+                head,
+                root,
+                len,
+                sorted: len,
+            })
+        })
     }
-    /// Get an iterator over the values of the list
+    /// Extend the map with an iterator and call a continuation function on it
     ///
-    /// The iterator yields items in the opposite order of their insertion.
-    pub fn values(&self) -> Values<'a, K, V> {
-        Values { iter: self.iter() }
+    /// Pairs are inserted several at a time per stack frame, which takes less
+    /// stack than calling [`Map::insert`] for each of them.
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        K: PartialOrd,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&Map<K, V>) -> R,
+    {
+        let mut then = Some(then);
+        extend_chunks(self, &mut iter.into_iter(), &mut |map| {
+            (then.take().expect("continuation called more than once"))(map)
+        })
     }
-    /// Collect an iterator into a map and call a continuation function on it
+    /// Collect the entries that are not shadowed into a [`List`] in key order
+    /// and call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([(2, 'b'), (1, 'a'), (2, 'c')], |map| {
+    ///     map.to_list(|list| {
+    ///         assert!(list.iter().eq(&[(&1, &'a'), (&2, &'c')]));
+    ///     });
+    /// });
+    /// ```
+    pub fn to_list<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&List<(&'a K, &'a V)>) -> R,
+    {
+        List::collect(
+            self.sorted_nodes_rev().map(|node| (&node.key, &node.value)),
+            then,
+        )
+    }
+    /// Collect the keys into a [`Set`] and call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([(2, 'b'), (1, 'a'), (2, 'c')], |map| {
+    ///     map.keys_to_set(|set| {
+    ///         assert_eq!(set.len(), 2);
+    ///         assert!(set.contains(&&1));
+    ///     });
+    /// });
+    /// ```
+    pub fn keys_to_set<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&Set<&'a K>) -> R,
+    {
+        Set::collect(self.live_nodes().map(|node| &node.key), then)
+    }
+    /// Collect the entries that are not shadowed into a new map, transforming
+    /// or skipping each one, and call a continuation function on it
+    ///
+    /// Entries are visited in key order, so if several of them map to the same
+    /// new key, the one with the greatest original key takes precedence.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let features = [("fast", true), ("safe", true), ("loud", false)];
+    ///
+    /// Map::collect(features, |map| {
+    ///     map.filter_map_collect(
+    ///         |name, &enabled| if enabled { Some((name.len(), *name)) } else { None },
+    ///         |enabled| {
+    ///             assert_eq!(enabled.len(), 2);
+    ///             assert_eq!(enabled.get(&4), Some(&"safe"));
+    ///         },
+    ///     );
+    /// });
+    /// ```
+    pub fn filter_map_collect<K2, V2, M, F, R>(&self, mut f: M, then: F) -> R
+    where
+        K2: PartialOrd,
+        M: FnMut(&'a K, &'a V) -> Option<(K2, V2)>,
+        F: FnOnce(&Map<K2, V2>) -> R,
+    {
+        Map::collect(
+            self.sorted_nodes()
+                .filter_map(|node| f(&node.key, &node.value)),
+            then,
+        )
+    }
+    /// Get an iterator over the entries whose keys start with a prefix, in key order
+    ///
+    /// Shadowed entries are skipped. The ordering of the keys must match the
+    /// ordering of their [`str`]s. Finding the first entry is an **O(logn)** operation,
+    /// or **O(n)** in the worst case if it skips entries removed by [`Map::rest`].
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let commands = [("get", 1), ("help", 2), ("set", 3), ("settings", 4), ("sleep", 5)];
+    ///
+    /// Map::collect(commands, |map| {
+    ///     assert!(map.iter_prefix("se").map(|(k, _)| *k).eq(["set", "settings"]));
+    ///     assert_eq!(map.iter_prefix("x").count(), 0);
+    /// });
+    /// ```
+    pub fn iter_prefix<'p>(&self, prefix: &'p str) -> IterPrefix<'a, 'p, K, V>
+    where
+        K: Borrow<str>,
+    {
+        IterPrefix {
+            nodes: SortedNodes {
+                nodes: InOrder::lower_bound(self.root, |node| node.key.borrow() < prefix),
+                map: *self,
+            },
+            prefix,
+        }
+    }
+    /// Get a view into the entry at the given key
+    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
+        Entry { key, map: self }
+    }
+    /// Swap the keys and values of the map and call a continuation function
+    /// on the inverted map
+    ///
+    /// Shadowed entries are ignored. If several keys have equal values, the
+    /// most recently inserted key is kept. To choose differently, use [`Map::invert_with`].
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let ids = [(1, "one"), (2, "two"), (3, "three")];
+    ///
+    /// Map::collect(ids, |by_id| {
+    ///     by_id.invert(|by_name| {
+    ///         assert_eq!(by_id[&2], "two");
+    ///         assert_eq!(*by_name[&"two"], 2);
+    ///     })
+    /// });
+    /// ```
+    pub fn invert<F, R>(&self, then: F) -> R
+    where
+        V: PartialOrd,
+        F: FnOnce(&Map<&V, &K>) -> R,
+    {
+        self.invert_with(|_, kept, _| kept, then)
+    }
+    /// Swap the keys and values of the map, resolving keys with equal values
+    /// with a function, and call a continuation function on the inverted map
+    ///
+    /// `resolve` is called with a value, the key kept for it so far, and another
+    /// key with an equal value, and returns the key to keep. Keys are visited
+    /// from most to least recently inserted.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let scores = [("ann", 3), ("bob", 5), ("cat", 3)];
+    ///
+    /// Map::collect(scores, |scores| {
+    ///     scores.invert_with(|_, a, b| a.min(b), |by_score| {
+    ///         assert_eq!(*by_score[&3], "ann");
+    ///         assert_eq!(*by_score[&5], "bob");
+    ///     })
+    /// });
+    /// ```
+    pub fn invert_with<C, F, R>(&self, resolve: C, then: F) -> R
+    where
+        V: PartialOrd,
+        C: FnMut(&'a V, &'a K, &'a K) -> &'a K,
+        F: FnOnce(&Map<&V, &K>) -> R,
+    {
+        let entries = self.live_nodes().map(|node| (&node.key, &node.value));
+        invert_entries(&Map::new(), entries, resolve, then)
+    }
+    /// Get an iterator over the differences between this map and another
+    ///
+    /// Entries only in the other map are [`Change::Added`], entries only in this
+    /// map are [`Change::Removed`], and keys whose values differ are [`Change::Changed`].
+    /// Shadowed entries are ignored, and the changes are yielded in key order.
+    ///
+    /// Both maps are walked once, so this is an **O(n + m)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::{map::Change, Map};
+    ///
+    /// Map::collect([("baud", 9600), ("parity", 0), ("bits", 8)], |old| {
+    ///     Map::collect([("baud", 115200), ("bits", 8), ("echo", 1)], |new| {
+    ///         let changes: Vec<_> = old.diff(new).collect();
+    ///         assert_eq!(changes, [
+    ///             Change::Changed(&"baud", &9600, &115200),
+    ///             Change::Added(&"echo", &1),
+    ///             Change::Removed(&"parity", &0),
+    ///         ]);
+    ///     })
+    /// });
+    /// ```
+    pub fn diff(&self, other: &Map<'a, K, V>) -> Diff<'a, K, V>
+    where
+        V: PartialEq,
+    {
+        Diff {
+            old: self.sorted_nodes().peekable(),
+            new: other.sorted_nodes().peekable(),
+        }
+    }
+    /// Get a value that displays the map in key order with custom separators
+    ///
+    /// `entry_sep` goes between entries, and `kv_sep` goes between each key and its value.
+    /// Shadowed entries are not displayed.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([("b", 2), ("a", 1), ("b", 3)], |map| {
+    ///     assert_eq!(map.to_string(), "{a: 1, b: 3}");
+    ///     assert_eq!(map.display_with("; ", "=").to_string(), "{a=1; b=3}");
+    /// });
+    /// ```
+    pub fn display_with<'s>(
+        &self,
+        entry_sep: &'s str,
+        kv_sep: &'s str,
+    ) -> DisplayWith<'a, 's, K, V> {
+        DisplayWith {
+            map: *self,
+            entry_sep,
+            kv_sep,
+        }
+    }
+    /// Check if every entry in this map is also in another map
+    ///
+    /// Shadowed entries are ignored.
+    ///
+    /// This is an **O(nlogm)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([("uart", 1), ("spi", 2)], |required| {
+    ///     Map::collect([("uart", 1), ("spi", 2), ("i2c", 3)], |available| {
+    ///         assert!(required.is_submap(available));
+    ///         assert!(!available.is_submap(required));
+    ///     })
+    /// });
+    /// ```
+    pub fn is_submap(&self, other: &Map<K, V>) -> bool
+    where
+        V: PartialEq,
+    {
+        self.sorted_nodes().all(|node| {
+            other
+                .get_node(&node.key)
+                .is_some_and(|other| node.value == other.value)
+        })
+    }
+    /// Check if every key in this map is also in another map, regardless of values
+    ///
+    /// This is an **O(nlogm)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::collect([("uart", 1), ("spi", 2)], |required| {
+    ///     Map::collect([("uart", 0), ("spi", 0), ("i2c", 0)], |available| {
+    ///         assert!(!required.is_submap(available));
+    ///         assert!(required.is_key_subset(available));
+    ///     })
+    /// });
+    /// ```
+    pub fn is_key_subset<W>(&self, other: &Map<K, W>) -> bool {
+        self.sorted_nodes()
+            .all(|node| other.contains_key(&node.key))
+    }
+}
+
+fn invert_entries<'a, K, V, I, C, F, R>(
+    inverted: &Map<&'a V, &'a K>,
+    mut entries: I,
+    mut resolve: C,
+    then: F,
+) -> R
+where
+    V: PartialOrd,
+    I: Iterator<Item = (&'a K, &'a V)>,
+    C: FnMut(&'a V, &'a K, &'a K) -> &'a K,
+    F: FnOnce(&Map<&V, &K>) -> R,
+{
+    if let Some((key, value)) = entries.next() {
+        let key = match inverted.get(&value) {
+            Some(&kept) => {
+                let key = resolve(value, kept, key);
+                if ptr::eq(key, kept) {
+                    return invert_entries(inverted, entries, resolve, then);
+                }
+                key
+            }
+            None => key,
+        };
+        inverted.insert(value, key, |inverted| {
+            invert_entries(inverted, entries, resolve, then)
+        })
+    } else {
+        then(inverted)
+    }
+}
+
+impl<'a, T> Map<'a, T, usize>
+where
+    T: PartialOrd,
+{
+    /// Count the occurrences of each item in an iterator and call a
+    /// continuation function on the map of counts
+    ///
+    /// Each occurrence inserts the updated count for its item, so the map's
+    /// [`len`](Map::len) is the total number of items, while lookups find
+    /// the final counts.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// let text = "the cat and the dog and the bird";
+    ///
+    /// Map::counter(text.split(' '), |counts| {
+    ///     assert_eq!(counts["the"], 3);
+    ///     assert_eq!(counts["and"], 2);
+    ///     assert_eq!(counts["cat"], 1);
+    ///     assert_eq!(counts.get("fish"), None);
+    /// });
+    /// ```
+    pub fn counter<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Map<T, usize>) -> R,
+    {
+        Map::default().extend_counts(iter, then)
+    }
+    /// Add the occurrences of each item in an iterator to the counts in
+    /// the map and call a continuation function on it
+    pub fn extend_counts<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Map<T, usize>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            let count = self.get(&item).copied().unwrap_or(0) + 1;
+            self.insert(item, count, |map| map.extend_counts(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+impl<'a, K, V> Map<'a, K, List<'a, V>>
+where
+    K: PartialOrd,
+{
+    /// Group the items of an iterator by a key and call a continuation
+    /// function on the map of groups
+    ///
+    /// Each group is a [`List`] whose items are in the opposite order of the
+    /// iterator's. Each item inserts the updated group for its key, so the map's
+    /// [`len`](Map::len) is the total number of items, while lookups find
+    /// the complete groups.
     ///
     /// # Example
     /// ```
     /// use nolloc::Map;
     ///
-    /// let nums = [(1, 2), (3, 4), (5, 6)];
+    /// let words = ["apple", "bean", "avocado", "cherry", "banana"];
     ///
-    /// let sum = Map::collect(nums, |map| {
-    ///     let mut sum = 0;
-    ///     for (k, _) in &nums {
-    ///         sum += *map.get(k).unwrap();
-    ///     }
-    ///     sum
+    /// Map::group_by(words, |word| word.as_bytes()[0], |groups| {
+    ///     assert_eq!(groups[&b'a'].iter().collect::<Vec<_>>(), [&"avocado", &"apple"]);
+    ///     assert_eq!(groups[&b'b'].len(), 2);
+    ///     assert_eq!(groups[&b'c'].head(), Some(&"cherry"));
     /// });
-    ///
-    /// assert_eq!(sum, 12);
     /// ```
-    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    pub fn group_by<I, G, F, R>(iter: I, key_fn: G, then: F) -> R
     where
-        K: PartialOrd,
-        I: IntoIterator<Item = (K, V)>,
-        F: FnOnce(&Map<K, V>) -> R,
+        I: IntoIterator<Item = V>,
+        G: FnMut(&V) -> K,
+        F: FnOnce(&Map<K, List<V>>) -> R,
     {
-        Map::default().extend(iter, then)
+        Map::default().extend_groups(iter, key_fn, then)
     }
-    /// Extend the map with an iterator and call a continuation function on it
-    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    /// Add the items of an iterator to the groups in the map by a key and
+    /// call a continuation function on it
+    pub fn extend_groups<I, G, F, R>(&self, iter: I, mut key_fn: G, then: F) -> R
     where
-        K: PartialOrd,
-        I: IntoIterator<Item = (K, V)>,
-        F: FnOnce(&Map<K, V>) -> R,
+        I: IntoIterator<Item = V>,
+        G: FnMut(&V) -> K,
+        F: FnOnce(&Map<K, List<V>>) -> R,
     {
         let mut iter = iter.into_iter();
-        if let Some((k, v)) = iter.next() {
-            self.insert(k, v, |list| list.extend(iter, then))
+        if let Some(item) = iter.next() {
+            let key = key_fn(&item);
+            let group = self.get(&key).copied().unwrap_or_default();
+            group.push(item, |group| {
+                self.insert(key, *group, |map| map.extend_groups(iter, key_fn, then))
+            })
         } else {
             then(self)
         }
     }
-    /// Get a view into the entry at the given key
-    pub fn entry(&'a self, key: K) -> Entry<'a, K, V> {
-        Entry { key, map: self }
-    }
 }
 
-/// An iterator over the key-value pairs of a [`Map`]
-pub struct Iter<'a, K, V> {
-    node: Option<&'a MapNode<'a, K, V>>,
+type BuildThen<'f, I, K, V, R> =
+    dyn FnMut(&mut I, Option<&MapTree<K, V>>, Option<&MapNode<K, V>>) -> R + 'f;
+
+/// The most pairs [`Map::extend`] inserts per stack frame
+const EXTEND_CHUNK: usize = 8;
+
+type ExtendThen<'t, K, V, R> = dyn FnMut(&Map<K, V>) -> R + 't;
+
+fn extend_chunks<I, K, V, R>(map: &Map<K, V>, iter: &mut I, then: &mut ExtendThen<K, V, R>) -> R
+where
+    I: Iterator<Item = (K, V)>,
+    K: PartialOrd,
+{
+    // The tree buffer is sized by height so that a chunk usually fills it
+    match tree::height(map.root) {
+        0..=5 => extend_chunk::<_, _, _, _, { EXTEND_CHUNK * 8 }>(map, iter, then),
+        6..=9 => extend_chunk::<_, _, _, _, { EXTEND_CHUNK * 12 }>(map, iter, then),
+        10..=13 => extend_chunk::<_, _, _, _, { EXTEND_CHUNK * 16 }>(map, iter, then),
+        _ => extend_chunk::<_, _, _, _, { EXTEND_CHUNK * 24 }>(map, iter, then),
+    }
 }
 
-impl<'a, K, V> MapNode<'a, K, V> {
-    fn contains_child(&self, child: &Self) -> bool {
-        self.left.map_or(false, |node| ptr::eq(node, child))
-            || self.right.map_or(false, |node| ptr::eq(node, child))
-            || self.left.map_or(false, |node| node.contains_child(child))
-            || self.right.map_or(false, |node| node.contains_child(child))
+/// Insert pairs until the iterator or the frame's buffers run out, then
+/// continue in a new frame
+// Kept out of line so that only the chosen buffer size takes up stack space
+#[inline(never)]
+fn extend_chunk<I, K, V, R, const S: usize>(
+    map: &Map<K, V>,
+    iter: &mut I,
+    then: &mut ExtendThen<K, V, R>,
+) -> R
+where
+    I: Iterator<Item = (K, V)>,
+    K: PartialOrd,
+{
+    let mut entries: [Option<MapNode<K, V>>; EXTEND_CHUNK] = core::array::from_fn(|_| None);
+    let mut free_entries: &mut [Option<MapNode<K, V>>] = &mut entries;
+    let mut nodes = [None; S];
+    let mut free: &mut [Option<MapTree<K, V>>] = &mut nodes;
+    let cmp = |a: &MapNode<K, V>, b: &MapNode<K, V>| tree::compare(&a.key, &b.key);
+    let mut map = *map;
+    while free.len() >= tree::insert_room(map.root) {
+        let (slot, rest) = match free_entries.split_first_mut() {
+            Some(split) => split,
+            None => break,
+        };
+        let (key, value) = match iter.next() {
+            Some(pair) => pair,
+            None => return then(&map),
+        };
+        free_entries = rest;
+        let node = &*slot.insert(MapNode {
+            shadowed: map.get_node(&key),
+            key,
+            value,
+            prev: map,
+            ordinal: 0,
+        });
+        map = Map {
+            head: Some(node),
+            root: Some(tree::insert_into(map.root, node, &cmp, &mut free)),
+            len: map.len + 1,
+            sorted: map.sorted,
+        };
     }
+    extend_chunks(&map, iter, then)
 }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V>
+/// Build a balanced tree from the next `len` pairs of a sorted iterator
+///
+/// The continuation receives the iterator, the root of the tree, and the last entry built.
+fn build_sorted<'a, I, K, V, R>(
+    len: usize,
+    iter: &mut I,
+    prev: Option<&'a MapNode<'a, K, V>>,
+    then: &mut BuildThen<I, K, V, R>,
+) -> R
 where
+    I: Iterator<Item = (K, V)>,
     K: PartialOrd,
 {
+    if len == 0 {
+        return then(iter, None, prev);
+    }
+    build_sorted(len / 2, iter, prev, &mut |iter, left, prev| {
+        let (key, value) = iter
+            .next()
+            .expect("iterator shorter than its reported length");
+        debug_assert!(
+            match prev {
+                Some(prev) => prev.key < key,
+                None => true,
+            },
+            "keys passed to Map::collect_sorted are not strictly ascending"
+        );
+        let node = MapNode {
+            key,
+            value,
+            prev: Map {
+                head: prev,
+                ..Map::default()
+            },
+            ordinal: prev.map_or(0, |prev| prev.ordinal) + 1,
+            shadowed: None,
+        };
+        build_sorted(
+            len - len / 2 - 1,
+            iter,
+            Some(&node),
+            &mut |iter, right, last| then(iter, Some(&Node::new(&node, left, right)), last),
+        )
+    })
+}
+
+/// An iterator over the key-value pairs of a [`Map`]
+pub struct Iter<'a, K, V> {
+    node: Option<&'a MapNode<'a, K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
     type Item = (&'a K, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
         let node = self.node?;
-        let res = (&node.key, &node.value);
-        self.node = match (node.left, node.right) {
-            (None, None) => None,
-            (None, Some(right)) => Some(right),
-            (Some(left), None) => Some(left),
-            (Some(left), Some(right)) => Some(if left.contains_child(right) {
-                left
-            } else {
-                right
-            }),
-        };
-        Some(res)
+        self.node = node.prev.head;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            node: self.node,
+            len: self.len,
+        }
     }
 }
 
@@ -319,14 +1088,26 @@ pub struct Keys<'a, K, V> {
     iter: Iter<'a, K, V>,
 }
 
-impl<'a, K, V> Iterator for Keys<'a, K, V>
-where
-    K: PartialOrd,
-{
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
     type Item = &'a K;
     fn next(&mut self) -> Option<Self::Item> {
         Some(self.iter.next()?.0)
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> Clone for Keys<'a, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            iter: self.iter.clone(),
+        }
+    }
 }
 
 /// An iterator over the values of a [`Map`]
@@ -334,20 +1115,241 @@ pub struct Values<'a, K, V> {
     iter: Iter<'a, K, V>,
 }
 
-impl<'a, K, V> Iterator for Values<'a, K, V>
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()?.1)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> Clone for Values<'a, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            iter: self.iter.clone(),
+        }
+    }
+}
+
+/// An iterator over the entries of a [`Map`] that are not shadowed
+struct LiveNodes<'a, K, V> {
+    node: Option<&'a MapNode<'a, K, V>>,
+    map: Map<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for LiveNodes<'a, K, V>
 where
     K: PartialOrd,
 {
-    type Item = &'a V;
+    type Item = &'a MapNode<'a, K, V>;
     fn next(&mut self) -> Option<Self::Item> {
-        Some(self.iter.next()?.1)
+        loop {
+            let node = self.node?;
+            self.node = node.prev.head;
+            if self
+                .map
+                .get_node(&node.key)
+                .is_some_and(|live| ptr::eq(live, node))
+            {
+                return Some(node);
+            }
+        }
+    }
+}
+
+/// An iterator over the entries of a [`Map`] that are not shadowed, in key order
+struct SortedNodes<'a, K, V> {
+    nodes: InOrder<'a, MapNode<'a, K, V>>,
+    map: Map<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for SortedNodes<'a, K, V> {
+    type Item = &'a MapNode<'a, K, V>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let map = self.map;
+        self.nodes.find(|node| map.is_visible(node))
+    }
+}
+
+/// A difference between two [`Map`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a, K, V> {
+    /// An entry that is only in the other map
+    Added(&'a K, &'a V),
+    /// An entry that is only in this map
+    Removed(&'a K, &'a V),
+    /// A key that is in both maps, with this map's value and the other map's value
+    Changed(&'a K, &'a V, &'a V),
+}
+
+impl<'a, K, V> Change<'a, K, V> {
+    /// Get the key of the changed entry
+    pub fn key(&self) -> &'a K {
+        match *self {
+            Change::Added(key, _) | Change::Removed(key, _) | Change::Changed(key, _, _) => key,
+        }
+    }
+}
+
+/// An iterator over the entries of a [`Map`] whose keys start with a prefix
+///
+/// This is returned by [`Map::iter_prefix`].
+pub struct IterPrefix<'a, 'p, K, V> {
+    nodes: SortedNodes<'a, K, V>,
+    prefix: &'p str,
+}
+
+impl<'a, 'p, K, V> Iterator for IterPrefix<'a, 'p, K, V>
+where
+    K: Borrow<str>,
+{
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?;
+        if node.key.borrow().starts_with(self.prefix) {
+            Some((&node.key, &node.value))
+        } else {
+            // Every later key is past the prefix too
+            self.nodes.nodes = InOrder::new(None);
+            None
+        }
+    }
+}
+
+impl<'a, 'p, K, V> FusedIterator for IterPrefix<'a, 'p, K, V> where K: Borrow<str> {}
+
+/// An iterator over the differences between two [`Map`]s
+///
+/// This is returned by [`Map::diff`].
+pub struct Diff<'a, K, V> {
+    old: Peekable<SortedNodes<'a, K, V>>,
+    new: Peekable<SortedNodes<'a, K, V>>,
+}
+
+impl<'a, K, V> Iterator for Diff<'a, K, V>
+where
+    K: PartialOrd,
+    V: PartialEq,
+{
+    type Item = Change<'a, K, V>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let order = match (self.old.peek(), self.new.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(old), Some(new)) => tree::compare(&old.key, &new.key),
+            };
+            match order {
+                Ordering::Less => {
+                    let old = self.old.next()?;
+                    return Some(Change::Removed(&old.key, &old.value));
+                }
+                Ordering::Greater => {
+                    let new = self.new.next()?;
+                    return Some(Change::Added(&new.key, &new.value));
+                }
+                Ordering::Equal => {
+                    let (old, new) = (self.old.next()?, self.new.next()?);
+                    if old.value != new.value {
+                        return Some(Change::Changed(&old.key, &old.value, &new.value));
+                    }
+                }
+            }
+        }
     }
 }
 
-impl<'a, K, V> IntoIterator for &'a Map<'a, K, V>
+/// A cursor that moves over the entries of a [`Map`] in key order
+///
+/// Each move is an **O(logn)** operation, or **O(n)** in the worst case if it
+/// skips entries removed by [`Map::rest`]. Moving past either end leaves the
+/// cursor without an entry.
+///
+/// This is returned by [`Map::cursor`].
+pub struct MapCursor<'a, K, V> {
+    map: Map<'a, K, V>,
+    node: Option<&'a MapNode<'a, K, V>>,
+}
+
+impl<'a, K, V> MapCursor<'a, K, V>
 where
     K: PartialOrd,
 {
+    /// Get the entry the cursor is at
+    pub fn peek(&self) -> Option<(&'a K, &'a V)> {
+        self.node.map(|node| (&node.key, &node.value))
+    }
+    /// Move the cursor to the entry with the smallest key that is not less than the given key
+    pub fn seek<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.node = tree::lower_bound(self.map.root, |node| {
+            tree::compare(node.key.borrow(), key) == Ordering::Less
+        });
+        if self.node.is_some_and(|node| !self.map.is_visible(node)) {
+            self.next_key();
+        }
+    }
+    /// Move the cursor to the next entry and get its key
+    pub fn next_key(&mut self) -> Option<&'a K> {
+        loop {
+            let curr = self.node?;
+            self.node = tree::lower_bound(self.map.root, |node| {
+                tree::compare(&node.key, &curr.key) != Ordering::Greater
+            });
+            if let Some(node) = self.node.filter(|node| self.map.is_visible(node)) {
+                return Some(&node.key);
+            }
+        }
+    }
+    /// Move the cursor to the previous entry and get its key
+    pub fn prev_key(&mut self) -> Option<&'a K> {
+        loop {
+            let curr = self.node?;
+            self.node = tree::last_before(self.map.root, |node| {
+                tree::compare(&node.key, &curr.key) == Ordering::Less
+            });
+            if let Some(node) = self.node.filter(|node| self.map.is_visible(node)) {
+                return Some(&node.key);
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Clone for MapCursor<'a, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for MapCursor<'a, K, V> {}
+
+/// An iterator over every value inserted for a key in a [`Map`]
+///
+/// This is returned by [`Map::values_for`].
+pub struct ValuesFor<'a, K, V> {
+    node: Option<&'a MapNode<'a, K, V>>,
+}
+
+impl<'a, K, V> Iterator for ValuesFor<'a, K, V> {
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.node?;
+        self.node = node.shadowed;
+        Some(&node.value)
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Map<'a, K, V> {
     type Item = (&'a K, &'a V);
     type IntoIter = Iter<'a, K, V>;
     fn into_iter(self) -> Self::IntoIter {
@@ -355,10 +1357,7 @@ where
     }
 }
 
-impl<'a, K, V> IntoIterator for Map<'a, K, V>
-where
-    K: PartialOrd,
-{
+impl<'a, K, V> IntoIterator for Map<'a, K, V> {
     type Item = (&'a K, &'a V);
     type IntoIter = Iter<'a, K, V>;
     fn into_iter(self) -> Self::IntoIter {
@@ -368,38 +1367,41 @@ where
 
 impl<'a, K, V> Default for Map<'a, K, V> {
     fn default() -> Self {
-        Map { head: None, len: 0 }
+        Map {
+            head: None,
+            root: None,
+            len: 0,
+            sorted: 0,
+        }
     }
 }
 
 impl<'a, K, V> Clone for Map<'a, K, V> {
     fn clone(&self) -> Self {
-        Map {
-            head: self.head,
-            len: self.len,
-        }
+        *self
     }
 }
 
 impl<'a, K, V> Copy for Map<'a, K, V> {}
 
+/// Map equality is an **O(n)** operation
+///
+/// Only entries that are not shadowed are compared, so the order of insertion does not matter.
 impl<'a, K, V> PartialEq for Map<'a, K, V>
 where
     K: PartialOrd,
     V: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
-        for (key, value) in self {
-            if !other.get(key).map_or(false, |other_val| value == other_val) {
-                return false;
-            }
-        }
-        for (key, value) in other {
-            if !self.get(key).map_or(false, |other_val| value == other_val) {
-                return false;
+        let mut a = self.sorted_nodes();
+        let mut b = other.sorted_nodes();
+        loop {
+            match (a.next(), b.next()) {
+                (None, None) => return true,
+                (Some(a), Some(b)) if a.key == b.key && a.value == b.value => {}
+                _ => return false,
             }
         }
-        true
     }
 }
 
@@ -410,9 +1412,41 @@ where
 {
 }
 
+/// Maps are ordered lexicographically by their entries that are not shadowed, in key order
+impl<'a, K, V> PartialOrd for Map<'a, K, V>
+where
+    K: PartialOrd,
+    V: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let entries = |map: &Self| map.sorted_nodes().map(|node| (&node.key, &node.value));
+        entries(self).partial_cmp(entries(other))
+    }
+}
+
+/// Only entries that are not shadowed are hashed, in key order
+impl<'a, K, V> Hash for Map<'a, K, V>
+where
+    K: Hash,
+    V: Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        let mut count = 0;
+        for node in self.sorted_nodes() {
+            node.key.hash(state);
+            node.value.hash(state);
+            count += 1;
+        }
+        state.write_usize(count);
+    }
+}
+
 impl<'a, K, V> fmt::Debug for Map<'a, K, V>
 where
-    K: PartialOrd + fmt::Debug,
+    K: fmt::Debug,
     V: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -420,6 +1454,43 @@ where
     }
 }
 
+/// Maps are displayed in key order, without shadowed entries
+impl<'a, K, V> fmt::Display for Map<'a, K, V>
+where
+    K: PartialOrd + fmt::Display,
+    V: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.display_with(", ", ": "), f)
+    }
+}
+
+/// Displays a [`Map`] with custom separators
+///
+/// This is returned by [`Map::display_with`].
+pub struct DisplayWith<'a, 's, K, V> {
+    map: Map<'a, K, V>,
+    entry_sep: &'s str,
+    kv_sep: &'s str,
+}
+
+impl<'a, 's, K, V> fmt::Display for DisplayWith<'a, 's, K, V>
+where
+    K: fmt::Display,
+    V: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, node) in self.map.sorted_nodes().enumerate() {
+            if i > 0 {
+                write!(f, "{}", self.entry_sep)?;
+            }
+            write!(f, "{}{}{}", node.key, self.kv_sep, node.value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
 /// A view into a single entry in a [`Map`]
 #[derive(Debug)]
 pub struct Entry<'a, K, V>
@@ -496,6 +1567,41 @@ where
                 .insert(self.key, value, |map| then(map, &map.head.unwrap().value))
         }
     }
+    /// Try to insert a value if the entry does not already exist in the map
+    /// and call a continuation
+    ///
+    /// If constructing the value fails, the error is returned and the
+    /// continuation is not called.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Map;
+    ///
+    /// Map::new().entry("a").or_insert(1, |map, _| {
+    ///     let parsed = map
+    ///         .entry("b")
+    ///         .or_try_insert_with(|_| "2".parse::<i32>(), |map, v| *v + map["a"]);
+    ///     assert_eq!(parsed, Ok(3));
+    ///     let failed = map
+    ///         .entry("c")
+    ///         .or_try_insert_with(|_| "x".parse::<i32>(), |_, v| *v);
+    ///     assert!(failed.is_err());
+    /// });
+    /// ```
+    pub fn or_try_insert_with<F, R, G, E>(self, get_value: G, then: F) -> Result<R, E>
+    where
+        F: FnOnce(&Map<K, V>, &V) -> R,
+        G: FnOnce(&K) -> Result<V, E>,
+    {
+        if let Some(value) = self.map.get(&self.key) {
+            Ok(then(self.map, value))
+        } else {
+            let value = get_value(&self.key)?;
+            Ok(self
+                .map
+                .insert(self.key, value, |map| then(map, &map.head.unwrap().value)))
+        }
+    }
     /// Insert the default value if the entry does not already exist in the map
     /// and call a continuation
     pub fn of_default<F, R, G>(self, then: F) -> R
@@ -527,3 +1633,131 @@ where
         self.get(index).expect("no entry found for key")
     }
 }
+
+#[test]
+fn map_lookups() {
+    Map::collect([(0, 'a'), (2, 'b'), (4, 'c'), (2, 'd')], |map| {
+        assert_eq!(map.get(&0), Some(&'a'));
+        assert_eq!(map.get(&2), Some(&'d'));
+        assert_eq!(map.rest().get(&2), Some(&'b'));
+    });
+    Map::collect((0..200).map(|i| (i, i)), |map| {
+        assert!((0..200).all(|i| map.get(&i) == Some(&i)));
+    });
+    Map::collect_sorted((0..10).map(|i| (i, i)), |map| {
+        let rest = map.rest().rest();
+        assert!(!rest.contains_key(&9));
+        assert_eq!(rest.max(), Some((&7, &7)));
+        rest.insert(9, 90, |map| {
+            assert_eq!(map.get(&9), Some(&90));
+            assert!(!map.contains_key(&8));
+            assert_eq!(map.rest(), rest);
+        });
+    });
+}
+
+#[test]
+fn map_eq() {
+    Map::collect([(1, 'a'), (2, 'b'), (1, 'c')], |a| {
+        Map::collect([(2, 'b'), (1, 'c')], |b| {
+            assert_eq!(*a, *b);
+            assert_ne!(a.rest(), *b);
+            assert_ne!(*a, b.rest());
+        });
+        Map::collect_sorted([(1, 'c'), (2, 'b'), (3, 'd')], |b| {
+            assert_ne!(*a, *b);
+            assert_eq!(*a, b.rest());
+        });
+    });
+}
+
+#[test]
+fn map_ord_hash() {
+    // FNV-1a, kept local so the test does not depend on other modules
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        struct Fnv(u64);
+        impl Hasher for Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+            fn write(&mut self, bytes: &[u8]) {
+                for &byte in bytes {
+                    self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+                }
+            }
+        }
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    Map::collect([(1, 'a'), (2, 'b'), (1, 'c')], |a| {
+        Map::collect([(2, 'b'), (1, 'c')], |b| {
+            assert_eq!(a.cmp_entries(b), Ordering::Equal);
+            assert_eq!(hash(a), hash(b));
+            assert!(a.rest() < *b);
+        });
+        Map::collect([(1, 'c'), (2, 'c')], |b| assert!(*a < *b));
+    });
+}
+
+#[test]
+fn map_write_dot() {
+    struct Dot([u8; 256], usize);
+    impl fmt::Write for Dot {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.1 + s.len();
+            self.0
+                .get_mut(self.1..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.1 = end;
+            Ok(())
+        }
+    }
+    let mut dot = Dot([0; 256], 0);
+    Map::collect_sorted([("a\"b", 1), ("c", 2)], |map| {
+        map.rest().write_dot(&mut dot).unwrap();
+    });
+    assert_eq!(
+        core::str::from_utf8(&dot.0[..dot.1]).unwrap(),
+        "digraph {\n    n0 [label=\"\\\"c\\\"\", style=dashed];\n    \
+         n0 -> n1 [label=\"left\"];\n    n1 [label=\"\\\"a\\\\\\\"b\\\"\"];\n}\n"
+    );
+}
+
+#[test]
+fn map_cursor() {
+    Map::collect_sorted((0..10).map(|i| (i * 2, i)), |map| {
+        let rest = map.rest().rest();
+        rest.insert(17, 0, |map| {
+            let mut cursor = map.cursor();
+            cursor.seek(&15);
+            assert_eq!(cursor.peek(), Some((&17, &0)));
+            assert_eq!(cursor.next_key(), None);
+            assert_eq!(cursor.peek(), None);
+            cursor.seek(&18);
+            assert_eq!(cursor.peek(), None);
+            cursor.seek(&17);
+            assert_eq!(cursor.prev_key(), Some(&14));
+            cursor.seek(&0);
+            assert_eq!(cursor.prev_key(), None);
+        });
+    });
+}
+
+#[test]
+fn map_validate() {
+    Map::collect((0..500).map(|i| (i, i)), |map| {
+        map.debug_validate();
+        assert!(map.depth() <= 13);
+    });
+    Map::collect((0..500).rev().map(|i| (i % 37, i)), |map| {
+        map.debug_validate()
+    });
+    Map::collect_sorted((0..500).map(|i| (i, i)), |map| {
+        map.debug_validate();
+        assert_eq!(map.depth(), 9);
+        map.rest().insert(1000, 0, |map| map.debug_validate());
+    });
+}
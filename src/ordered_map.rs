@@ -0,0 +1,331 @@
+//! A growable key-value map with unique keys that iterates in insertion order
+//!
+//! Entries are kept in a chain in insertion order, which backs
+//! [`OrderedMap::head`] and [`OrderedMap::rest`], and in two persistent balanced
+//! search trees: one ordered by key, which backs lookups, and one ordered by
+//! the position each key was first inserted at, which backs iteration.
+
+use core::{borrow::Borrow, fmt, iter::FusedIterator};
+
+use crate::tree::{self, InOrder, Node};
+
+/// A growable key-value map with unique keys that iterates in insertion order
+///
+/// As with [`Map`](crate::Map), the map with an inserted entry cannot be
+/// accessed from the same scope. Instead, a continuation function is called
+/// on the new map, and its result is returned to the calling scope.
+///
+/// Inserting a key that is already in the map shadows its entry. The new
+/// value takes the old one's place in the iteration order.
+///
+/// # Example
+/// ```
+/// use nolloc::OrderedMap;
+///
+/// let fields = [("id", 7), ("name", 1), ("tags", 0), ("name", 2)];
+///
+/// OrderedMap::collect(fields, |doc| {
+///     assert_eq!(doc.len(), 3);
+///     assert_eq!(doc.get("name"), Some(&2));
+///     assert!(doc.keys().eq(&["id", "name", "tags"]));
+/// });
+/// ```
+pub struct OrderedMap<'a, K, V> {
+    head: Option<&'a OrderedNode<'a, K, V>>,
+    /// The live entries ordered by key
+    keys: Option<&'a OrderedTree<'a, K, V>>,
+    /// The live entries ordered by position
+    order: Option<&'a OrderedTree<'a, K, V>>,
+}
+
+struct OrderedNode<'a, K, V> {
+    key: K,
+    value: V,
+    /// The map this entry was inserted into
+    prev: OrderedMap<'a, K, V>,
+    /// The position of this entry in the chain, starting at 1
+    index: usize,
+    /// The index of the first entry inserted for this key
+    position: usize,
+}
+
+type OrderedTree<'a, K, V> = Node<'a, OrderedNode<'a, K, V>>;
+
+impl<'a, K, V> OrderedMap<'a, K, V> {
+    /// Create a new map
+    pub fn new() -> Self {
+        OrderedMap::default()
+    }
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_none()
+    }
+    /// Get the number of keys in the map
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        tree::size(self.keys)
+    }
+    /// Get the most recently inserted key-value pair in the map
+    pub fn head(&self) -> Option<(&K, &V)> {
+        let head = self.head?;
+        Some((&head.key, &head.value))
+    }
+    /// Get the map before the most recent insertion
+    pub fn rest(&self) -> Self {
+        self.head.map_or_else(OrderedMap::new, |head| head.prev)
+    }
+    /// Get the key-value pair that comes first in insertion order
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+    /// Get the key-value pair that comes last in insertion order
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let node = InOrder::rev(self.order).next()?;
+        Some((&node.key, &node.value))
+    }
+    /// Get an iterator over the key-value pairs of the map in insertion order
+    ///
+    /// Iterating over the whole map is an **O(n)** operation.
+    pub fn iter(&self) -> Iter<'a, K, V> {
+        Iter {
+            nodes: InOrder::new(self.order),
+            len: self.len(),
+        }
+    }
+    /// Get an iterator over the keys of the map in insertion order
+    pub fn keys(&self) -> Keys<'a, K, V> {
+        Keys { iter: self.iter() }
+    }
+    /// Get an iterator over the values of the map in insertion order
+    pub fn values(&self) -> Values<'a, K, V> {
+        Values { iter: self.iter() }
+    }
+    /// Get the index of the next entry in the chain
+    fn next_index(&self) -> usize {
+        self.head.map_or(0, |head| head.index) + 1
+    }
+}
+
+impl<'a, K, V> OrderedMap<'a, K, V>
+where
+    K: PartialOrd,
+{
+    /// Check if the map contains a key
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get_node(key).is_some()
+    }
+    /// Get the value corresponding to the key
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(&self.get_node(key)?.value)
+    }
+    fn get_node<Q>(&self, key: &Q) -> Option<&'a OrderedNode<'a, K, V>>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(tree::find(self.keys, |node| tree::compare(key, node.key.borrow()))?.entry)
+    }
+    /// Check the invariants of the map's search trees, panicking if any are violated
+    ///
+    /// This requires the `validate` feature.
+    #[cfg(any(test, feature = "validate"))]
+    pub fn debug_validate(&self) {
+        tree::validate(self.keys, |a, b| tree::compare(&a.key, &b.key));
+        tree::validate(self.order, |a, b| a.position.cmp(&b.position));
+        assert_eq!(tree::size(self.keys), tree::size(self.order));
+    }
+    /// Insert a key-value pair into the map, call a continuation on the
+    /// new map, and return its result
+    ///
+    /// If the key is already in the map, its entry is shadowed, and the new
+    /// value takes its place in the iteration order.
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, key: K, value: V, then: F) -> R
+    where
+        F: FnOnce(&OrderedMap<K, V>) -> R,
+    {
+        let index = self.next_index();
+        let position = self.get_node(&key).map_or(index, |old| old.position);
+        let node = OrderedNode {
+            key,
+            value,
+            prev: *self,
+            index,
+            position,
+        };
+        let by_key = |a: &OrderedNode<K, V>, b: &OrderedNode<K, V>| tree::compare(&a.key, &b.key);
+        let by_position =
+            |a: &OrderedNode<K, V>, b: &OrderedNode<K, V>| a.position.cmp(&b.position);
+        tree::insert(self.keys, &node, &by_key, |keys| {
+            tree::insert(self.order, &node, &by_position, |order| {
+                then(&OrderedMap {
+                    head: Some(&node),
+                    keys: Some(keys),
+                    order: Some(order),
+                })
+            })
+        })
+    }
+    /// Collect an iterator into a map and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&OrderedMap<K, V>) -> R,
+    {
+        OrderedMap::default().extend(iter, then)
+    }
+    /// Extend the map with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce(&OrderedMap<K, V>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some((key, value)) = iter.next() {
+            self.insert(key, value, |map| map.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the key-value pairs of an [`OrderedMap`] in insertion order
+///
+/// This is returned by [`OrderedMap::iter`].
+pub struct Iter<'a, K, V> {
+    nodes: InOrder<'a, OrderedNode<'a, K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+/// An iterator over the keys of an [`OrderedMap`] in insertion order
+///
+/// This is returned by [`OrderedMap::keys`].
+pub struct Keys<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()?.0)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+/// An iterator over the values of an [`OrderedMap`] in insertion order
+///
+/// This is returned by [`OrderedMap::values`].
+pub struct Values<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.iter.next()?.1)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> IntoIterator for &'a OrderedMap<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> Default for OrderedMap<'a, K, V> {
+    fn default() -> Self {
+        OrderedMap {
+            head: None,
+            keys: None,
+            order: None,
+        }
+    }
+}
+
+impl<'a, K, V> Clone for OrderedMap<'a, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, K, V> Copy for OrderedMap<'a, K, V> {}
+
+impl<'a, K, V> fmt::Debug for OrderedMap<'a, K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn ordered_map_order() {
+    let keys = (0..50).map(|i| (i * 29) % 50);
+    OrderedMap::collect(keys.clone().map(|k| (k, 0)), |map| {
+        map.debug_validate();
+        assert!(map.keys().copied().eq(keys.clone()));
+        map.extend((0..50).step_by(7).map(|k| (k, k)), |map| {
+            map.debug_validate();
+            assert_eq!(map.len(), 50);
+            assert!(map.keys().copied().eq(keys.clone()));
+            assert!(map
+                .iter()
+                .all(|(&k, &v)| v == if k % 7 == 0 { k } else { 0 }));
+            assert_eq!(map.first(), Some((&0, &0)));
+            assert_eq!(map.last(), Some((&21, &21)));
+            assert_eq!(map.head(), Some((&49, &49)));
+            assert_eq!(map.rest().get(&49), Some(&0));
+        });
+    });
+}
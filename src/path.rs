@@ -0,0 +1,278 @@
+//! A path made of [`str`](prim@str) segments that exist on the stack
+//!
+//! Paths are handled purely lexically: nothing is looked up in a filesystem,
+//! so [`Path::normalize`] resolves `..` by dropping the segment before it.
+
+use core::fmt::{self, Write};
+
+use crate::{list, List};
+
+/// A path made of [`str`](prim@str) segments that exist on the stack
+///
+/// Segments are appended with [`Path::join`]. As with [`List`], the joined
+/// path cannot be accessed from the same scope. Instead, a continuation
+/// function is called on the new path, and its result is returned to the
+/// calling scope.
+///
+/// A path is either absolute or relative, and it is displayed with its
+/// separator between segments, `/` by default.
+///
+/// # Example
+/// ```
+/// use nolloc::Path;
+///
+/// Path::parse("/srv/./www/../logs", '/', |path| {
+///     assert!(path.is_absolute());
+///     assert_eq!(path.file_name(), Some("logs"));
+///     path.normalize(|path| {
+///         assert_eq!(path.to_string(), "/srv/logs");
+///         path.join("today.log", |file| {
+///             assert_eq!(file.to_string(), "/srv/logs/today.log");
+///             assert_eq!(file.parent(), Some(*path));
+///         })
+///     })
+/// });
+/// ```
+pub struct Path<'a> {
+    /// The segments, last first
+    segments: List<'a, &'a str>,
+    absolute: bool,
+    separator: char,
+}
+
+impl<'a> Path<'a> {
+    /// Create a new empty relative path
+    pub fn new() -> Self {
+        Path::default()
+    }
+    /// Create a new absolute path with no segments
+    pub fn root() -> Self {
+        Path {
+            absolute: true,
+            ..Path::default()
+        }
+    }
+    /// Get the path with a different separator
+    ///
+    /// The separator is only used when the path is displayed.
+    pub fn with_separator(self, separator: char) -> Self {
+        Path { separator, ..self }
+    }
+    /// Get the path's separator
+    pub fn separator(&self) -> char {
+        self.separator
+    }
+    /// Check if the path is absolute
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+    /// Check if the path has no segments
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+    /// Get the number of segments in the path
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+    /// Get the last segment of the path
+    ///
+    /// This is an **O(1)** operation.
+    pub fn file_name(&self) -> Option<&'a str> {
+        self.segments.head().copied()
+    }
+    /// Get the path without its last segment, or `None` if it has no segments
+    ///
+    /// This is an **O(1)** operation.
+    pub fn parent(&self) -> Option<Self> {
+        match self.segments.pop() {
+            (segments, Some(_)) => Some(Path { segments, ..*self }),
+            (_, None) => None,
+        }
+    }
+    /// Get an iterator over the segments of the path, last first
+    pub fn segments_rev(&self) -> list::Iter<'a, &'a str> {
+        self.segments.iter()
+    }
+    /// Append a segment to the path and call a continuation function on it
+    ///
+    /// The segment is not split, so it should not contain the separator.
+    pub fn join<F, R>(&self, segment: &'a str, then: F) -> R
+    where
+        F: FnOnce(&Path) -> R,
+    {
+        self.segments.push(segment, |segments| {
+            then(&Path {
+                segments: *segments,
+                ..*self
+            })
+        })
+    }
+    /// Append the segments of an iterator to the path and call a continuation
+    /// function on it
+    pub fn extend<'s, I, F, R>(&self, iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: IntoIterator<Item = &'s str>,
+        F: FnOnce(&Path) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(segment) = iter.next() {
+            self.join(segment, |path| path.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+    /// Split a string into a path at a separator and call a continuation
+    /// function on it
+    ///
+    /// The path is absolute if the string starts with the separator.
+    /// Empty segments are skipped.
+    pub fn parse<F, R>(s: &'a str, separator: char, then: F) -> R
+    where
+        F: FnOnce(&Path) -> R,
+    {
+        let path = Path {
+            segments: List::new(),
+            absolute: s.starts_with(separator),
+            separator,
+        };
+        path.extend(s.split(separator).filter(|s| !s.is_empty()), then)
+    }
+    /// Resolve the `.` and `..` segments of the path and call a continuation
+    /// function on the result
+    ///
+    /// Each `..` removes the segment before it. Leading `..` segments are
+    /// kept in a relative path and dropped from an absolute one.
+    pub fn normalize<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&Path) -> R,
+    {
+        let empty = Path {
+            segments: List::new(),
+            ..*self
+        };
+        self.segments
+            .reverse(|segments| empty.push_normalized(segments.iter().map(|s| **s), then))
+    }
+    fn push_normalized<'s, I, F, R>(&self, mut iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: Iterator<Item = &'s str>,
+        F: FnOnce(&Path) -> R,
+    {
+        match iter.next() {
+            None => then(self),
+            Some(".") => self.push_normalized(iter, then),
+            Some("..") => match self.segments.head() {
+                Some(&last) if last != ".." => {
+                    let parent = Path {
+                        segments: self.segments.rest(),
+                        ..*self
+                    };
+                    parent.push_normalized(iter, then)
+                }
+                None if self.absolute => self.push_normalized(iter, then),
+                _ => self.join("..", |path| path.push_normalized(iter, then)),
+            },
+            Some(segment) => self.join(segment, |path| path.push_normalized(iter, then)),
+        }
+    }
+    /// Write the path to a writer
+    ///
+    /// This recurses once per segment. An empty relative path is written as `.`.
+    pub fn write_to<W>(&self, w: &mut W) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        fn write<W>(segments: List<&str>, separator: char, w: &mut W) -> fmt::Result
+        where
+            W: fmt::Write + ?Sized,
+        {
+            match segments.pop() {
+                (rest, Some(s)) => {
+                    if !rest.is_empty() {
+                        write(rest, separator, w)?;
+                        w.write_char(separator)?;
+                    }
+                    w.write_str(s)
+                }
+                (_, None) => Ok(()),
+            }
+        }
+        if self.absolute {
+            w.write_char(self.separator)?;
+        } else if self.is_empty() {
+            w.write_char('.')?;
+        }
+        write(self.segments, self.separator, w)
+    }
+}
+
+impl<'a> Default for Path<'a> {
+    fn default() -> Self {
+        Path {
+            segments: List::new(),
+            absolute: false,
+            separator: '/',
+        }
+    }
+}
+
+impl<'a> Clone for Path<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for Path<'a> {}
+
+impl<'a> PartialEq for Path<'a> {
+    /// Paths are equal if they have the same segments and are both absolute
+    /// or both relative, whatever their separators
+    fn eq(&self, other: &Self) -> bool {
+        self.absolute == other.absolute && self.segments == other.segments
+    }
+}
+
+impl<'a> Eq for Path<'a> {}
+
+impl<'a> fmt::Debug for Path<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        self.write_to(f)?;
+        f.write_char('"')
+    }
+}
+
+impl<'a> fmt::Display for Path<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+#[test]
+fn path_normalize() {
+    use crate::FixedString;
+    fn normalized(s: &str, then: impl FnOnce(&str)) {
+        Path::parse(s, '/', |path| {
+            path.normalize(|path| {
+                let mut out = FixedString::<32>::new();
+                path.write_to(&mut out).unwrap();
+                then(out.as_str())
+            })
+        })
+    }
+    normalized("a/./b/../c", |s| assert_eq!(s, "a/c"));
+    normalized("../a/../../b", |s| assert_eq!(s, "../../b"));
+    normalized("/../a/..", |s| assert_eq!(s, "/"));
+    normalized("a/..", |s| assert_eq!(s, "."));
+    normalized("//x//y/", |s| assert_eq!(s, "/x/y"));
+    Path::parse(r"C:\Windows\System32", '\\', |path| {
+        assert!(!path.is_absolute());
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.parent().and_then(|p| p.file_name()), Some("Windows"));
+        assert_eq!(Path::new().parent(), None);
+    });
+}
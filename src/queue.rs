@@ -0,0 +1,192 @@
+//! A growable first-in-first-out queue where all items exist on the stack
+//!
+//! A [`Queue`] is made of two [`List`]s: one holding references to the next
+//! items to dequeue, in order, and one holding the most recently enqueued items,
+//! newest first. When the first list runs out, the second is reversed into it.
+
+use core::fmt;
+
+use crate::List;
+
+/// A growable first-in-first-out queue where all items exist on the stack
+///
+/// As with [`List`], the queue with an enqueued item cannot be accessed from
+/// the same scope. Instead, a continuation function is called on the new queue,
+/// and its result is returned to the calling scope.
+///
+/// Each item is reversed into the front of the queue at most once for a given
+/// sequence of operations, so enqueueing and dequeueing are **O(1)** amortized.
+/// Dequeueing from the same queue more than once repeats the reversal.
+///
+/// # Example
+/// ```
+/// use nolloc::Queue;
+///
+/// Queue::collect(["a", "b", "c"], |queue| {
+///     assert_eq!(queue.peek(), Some(&"a"));
+///     queue.dequeue(|queue, first| {
+///         assert_eq!(first, Some(&"a"));
+///         queue.enqueue("d", |queue| {
+///             queue.to_list(|list| assert!(list.iter().eq(&[&"b", &"c", &"d"])));
+///         })
+///     })
+/// });
+/// ```
+pub struct Queue<'a, T> {
+    /// The next items to dequeue, in order
+    ///
+    /// This is only empty if `back` is empty too.
+    front: List<'a, &'a T>,
+    /// The items enqueued since `front` was last rebuilt, newest first
+    back: List<'a, T>,
+}
+
+impl<'a, T> Queue<'a, T> {
+    /// Create a new queue
+    pub fn new() -> Self {
+        Queue::default()
+    }
+    /// Check if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty()
+    }
+    /// Get the queue's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+    /// Get the next item to be dequeued
+    ///
+    /// This is an **O(1)** operation.
+    pub fn peek(&self) -> Option<&'a T> {
+        self.front.head().copied()
+    }
+    /// Add an item to the back of the queue and call a continuation function
+    pub fn enqueue<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Queue<T>) -> R,
+    {
+        self.back.push(item, |back| {
+            Queue {
+                front: self.front,
+                back: *back,
+            }
+            .rebuild(then)
+        })
+    }
+    /// Remove the item at the front of the queue, call a continuation function
+    /// on the new queue and the removed item, and return its result
+    ///
+    /// The continuation gets [`None`] and the queue unchanged if the queue is empty.
+    pub fn dequeue<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&Queue<T>, Option<&T>) -> R,
+    {
+        let (front, item) = self.front.pop();
+        Queue {
+            front,
+            back: self.back,
+        }
+        .rebuild(|queue| then(queue, item.copied()))
+    }
+    /// Move the back items to the front if the front is empty
+    fn rebuild<F, R>(self, then: F) -> R
+    where
+        F: FnOnce(&Queue<T>) -> R,
+    {
+        if self.front.is_empty() && !self.back.is_empty() {
+            self.back.reverse(|front| {
+                then(&Queue {
+                    front: *front,
+                    back: List::default(),
+                })
+            })
+        } else {
+            then(&self)
+        }
+    }
+    /// Collect the items of the queue into a [`List`] in dequeue order and
+    /// call a continuation function on it
+    ///
+    /// This is an **O(n)** operation that uses stack space for each item.
+    pub fn to_list<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&List<&T>) -> R,
+    {
+        // A list's head is the last item collected, so the front must come last
+        self.front.reverse(|front| {
+            List::collect(self.back.iter().chain(front.iter().copied().copied()), then)
+        })
+    }
+    /// Collect an iterator into a queue and call a continuation function on it
+    ///
+    /// The first item of the iterator is the first to be dequeued.
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Queue<T>) -> R,
+    {
+        Queue::default().extend(iter, then)
+    }
+    /// Enqueue the items of an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Queue<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.enqueue(item, |queue| queue.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+impl<'a, T> Default for Queue<'a, T> {
+    fn default() -> Self {
+        Queue {
+            front: List::default(),
+            back: List::default(),
+        }
+    }
+}
+
+impl<'a, T> Clone for Queue<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Queue<'a, T> {}
+
+impl<'a, T> fmt::Debug for Queue<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Queue")
+            .field("front", &self.front)
+            .field("back", &self.back)
+            .finish()
+    }
+}
+
+#[test]
+fn queue_order() {
+    fn drain(queue: &Queue<i32>, expected: i32) {
+        queue.dequeue(|queue, item| match item {
+            Some(&item) => {
+                assert_eq!(item, expected);
+                if item == 3 {
+                    queue.extend(6..=8, |queue| drain(queue, expected + 1))
+                } else {
+                    drain(queue, expected + 1)
+                }
+            }
+            None => assert_eq!(expected, 9),
+        })
+    }
+    Queue::collect(1..=5, |queue| drain(queue, 1));
+}
@@ -0,0 +1,125 @@
+//! Unbiased random selection from iterators
+//!
+//! Randomness is taken from any [`RngCore`] generator. This module requires
+//! the `rand` feature.
+
+use rand_core::RngCore;
+
+/// Get a uniformly random index below `n` from a generator
+///
+/// This uses a widening multiply, so the bias is at most `n / 2^64`.
+pub(crate) fn random_index(rng: &mut impl RngCore, n: usize) -> usize {
+    ((u128::from(rng.next_u64()) * n as u128) >> 64) as usize
+}
+
+/// Choose `K` items from an iterator uniformly at random
+///
+/// This is reservoir sampling, so each item is equally likely to be chosen
+/// without the length of the iterator being known. It draws one [`u64`] from
+/// `rng` for each item after the first `K`. The chosen items are in no particular order.
+///
+/// Returns `None` if the iterator has fewer than `K` items.
+///
+/// # Example
+/// ```
+/// use nolloc::random::sample;
+/// use rand_core::{impls, Error, RngCore};
+///
+/// // A xorshift generator, standing in for a real one
+/// struct XorShift(u64);
+///
+/// impl RngCore for XorShift {
+///     fn next_u64(&mut self) -> u64 {
+///         self.0 ^= self.0 << 13;
+///         self.0 ^= self.0 >> 7;
+///         self.0 ^= self.0 << 17;
+///         self.0
+///     }
+///     fn next_u32(&mut self) -> u32 {
+///         self.next_u64() as u32
+///     }
+///     fn fill_bytes(&mut self, dest: &mut [u8]) {
+///         impls::fill_bytes_via_next(self, dest)
+///     }
+///     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+///         self.fill_bytes(dest);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+///
+/// let readings = (0..1000).map(|i| i * 3);
+/// let chosen: [u32; 4] = sample(readings, &mut rng).unwrap();
+/// assert!(chosen.iter().all(|n| n % 3 == 0 && *n < 3000));
+///
+/// assert_eq!(sample::<_, 4>(0..3, &mut rng), None);
+/// ```
+pub fn sample<T, const K: usize>(
+    iter: impl IntoIterator<Item = T>,
+    rng: &mut impl RngCore,
+) -> Option<[T; K]> {
+    let mut iter = iter.into_iter();
+    let mut reservoir: [Option<T>; K] = core::array::from_fn(|_| iter.next());
+    if reservoir.iter().any(Option::is_none) {
+        return None;
+    }
+    for (i, item) in iter.enumerate() {
+        if let Some(slot) = reservoir.get_mut(random_index(rng, K + i + 1)) {
+            *slot = Some(item);
+        }
+    }
+    Some(reservoir.map(|item| item.expect("reservoir is full")))
+}
+
+/// A xorshift generator for tests, and a counter of how many numbers it drew
+#[cfg(test)]
+struct XorShift(u64, usize);
+
+#[cfg(test)]
+impl RngCore for XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.1 += 1;
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[test]
+fn sample_uniformity() {
+    let mut rng = XorShift(1, 0);
+    let mut counts = [0; 8];
+    for _ in 0..8000 {
+        for n in sample::<_, 2>(0..8, &mut rng).unwrap() {
+            counts[n] += 1;
+        }
+    }
+    // Each item is expected to be chosen 2000 times
+    assert!(counts.iter().all(|&count| (1800..2200).contains(&count)));
+    let drawn = rng.1;
+    assert_eq!(sample::<_, 3>(0..3, &mut rng), Some([0, 1, 2]));
+    assert_eq!(sample::<i32, 0>(None, &mut rng), Some([]));
+    assert_eq!(rng.1, drawn);
+    crate::List::collect(0..8, |list| {
+        let mut counts = [0; 8];
+        for _ in 0..8000 {
+            counts[*list.choose(&mut rng).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&count| (850..1150).contains(&count)));
+    });
+    let drawn = rng.1;
+    assert_eq!(crate::List::<i32>::new().choose(&mut rng), None);
+    assert_eq!(rng.1, drawn);
+}
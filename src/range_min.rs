@@ -0,0 +1,224 @@
+//! A sequence with a fixed capacity that answers range minimum queries,
+//! stored inline as a segment tree
+//!
+//! The tree is laid out bottom-up over all `N` slots: node `i` has children
+//! `2 * i` and `2 * i + 1`, and nodes `N` to `2 * N - 1` are the slots. The
+//! leaves and the inner nodes are kept in separate arrays of `N`.
+
+use core::{fmt, iter::FromIterator, ops::Range};
+
+/// A sequence of up to `N` items that can find the minimum of any range of
+/// them in **O(logN)**
+///
+/// For range maximums, wrap the items in [`Reverse`](core::cmp::Reverse).
+///
+/// # Example
+/// ```
+/// use nolloc::RangeMin;
+///
+/// let mut latency: RangeMin<u32, 16> = [30, 12, 45, 18, 27].iter().copied().collect();
+///
+/// assert_eq!(latency.min(2..5), Some(18));
+/// latency.set(3, 50);
+/// assert_eq!(latency.min(2..5), Some(27));
+/// assert_eq!(latency.min(3..3), None);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RangeMin<T, const N: usize> {
+    leaves: [Option<T>; N],
+    /// The minimums of the inner nodes, of which node 0 is unused
+    inner: [Option<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> RangeMin<T, N>
+where
+    T: PartialOrd + Copy,
+{
+    /// Create a new empty sequence
+    pub fn new() -> Self {
+        RangeMin {
+            leaves: [None; N],
+            inner: [None; N],
+            len: 0,
+        }
+    }
+    /// Check if the sequence is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the sequence is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the sequence's length
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the sequence's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the item at an index
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.leaves[..self.len].get(index).copied().flatten()
+    }
+    fn node(&self, i: usize) -> Option<T> {
+        if i < N {
+            self.inner[i]
+        } else {
+            self.leaves[i - N]
+        }
+    }
+    /// Set a slot and update the minimums of its ancestors
+    fn write(&mut self, index: usize, item: T) {
+        self.leaves[index] = Some(item);
+        let mut i = (index + N) / 2;
+        while i > 0 {
+            self.inner[i] = lesser(self.node(2 * i), self.node(2 * i + 1));
+            i /= 2;
+        }
+    }
+    /// Push an item onto the end of the sequence
+    ///
+    /// If the sequence is full, the item is returned as an error.
+    /// This is an **O(logN)** operation.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.write(self.len, item);
+        self.len += 1;
+        Ok(())
+    }
+    /// Push the items of an iterator onto the end of the sequence
+    ///
+    /// If the sequence fills up, the first item that did not fit is returned
+    /// as an error.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        iter.into_iter().try_for_each(|item| self.push(item))
+    }
+    /// Replace the item at an index
+    ///
+    /// This is an **O(logN)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, index: usize, item: T) {
+        assert!(
+            index < self.len,
+            "index {} is out of bounds of a sequence of length {}",
+            index,
+            self.len
+        );
+        self.write(index, item);
+    }
+    /// Get the minimum of a range of the items, or `None` if the range is empty
+    ///
+    /// Items that are not comparable are treated as equal, so either may be
+    /// returned. This is an **O(logN)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or out of bounds.
+    pub fn min(&self, range: Range<usize>) -> Option<T> {
+        assert!(range.start <= range.end, "range is decreasing");
+        assert!(
+            range.end <= self.len,
+            "range end {} is out of bounds of a sequence of length {}",
+            range.end,
+            self.len
+        );
+        let (mut start, mut end) = (range.start + N, range.end + N);
+        let mut min = None;
+        while start < end {
+            if start % 2 == 1 {
+                min = lesser(min, self.node(start));
+                start += 1;
+            }
+            if end % 2 == 1 {
+                end -= 1;
+                min = lesser(min, self.node(end));
+            }
+            start /= 2;
+            end /= 2;
+        }
+        min
+    }
+    /// Remove all items from the sequence
+    pub fn clear(&mut self) {
+        *self = RangeMin::new();
+    }
+}
+
+fn lesser<T>(a: Option<T>, b: Option<T>) -> Option<T>
+where
+    T: PartialOrd,
+{
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for RangeMin<T, N>
+where
+    T: PartialOrd + Copy,
+{
+    /// Collect the items of an iterator into a sequence
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than `N` items.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut mins = RangeMin::new();
+        if mins.try_extend(iter).is_err() {
+            panic!("iterator has more than {} items", N);
+        }
+        mins
+    }
+}
+
+impl<T, const N: usize> Default for RangeMin<T, N>
+where
+    T: PartialOrd + Copy,
+{
+    fn default() -> Self {
+        RangeMin::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for RangeMin<T, N>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.leaves[..self.len].iter().flatten())
+            .finish()
+    }
+}
+
+#[test]
+fn range_min_queries() {
+    let values = [8, 3, 9, 3, 7, 1, 6, 4, 5, 2, 10];
+    let mut mins: RangeMin<i32, 13> = values.iter().copied().collect();
+    for start in 0..=values.len() {
+        for end in start..=values.len() {
+            let expected = values[start..end].iter().copied().min();
+            assert_eq!(mins.min(start..end), expected);
+        }
+    }
+    mins.set(5, 11);
+    assert_eq!(mins.min(4..9), Some(4));
+    assert_eq!(mins.get(5), Some(11));
+    assert_eq!(mins.get(11), None);
+    mins.clear();
+    assert_eq!(mins.push(20), Ok(()));
+    assert_eq!(mins.min(0..1), Some(20));
+}
@@ -0,0 +1,234 @@
+//! A sequence with a fixed capacity that answers range sum queries, stored
+//! inline as a Fenwick tree
+//!
+//! Item `i` of the tree (counting from 1) holds the sum of the `i & i.wrapping_neg()`
+//! items ending at `i`, so any prefix sum is the sum of **O(logn)** of them.
+
+use core::{
+    fmt,
+    iter::FromIterator,
+    ops::{Add, Range, Sub},
+};
+
+/// A sequence of up to `N` numbers that can sum any range of them in **O(logn)**
+///
+/// `T` is any type whose [`Default`] value is zero and that can be added and
+/// subtracted, such as the primitive integers and floats.
+///
+/// # Example
+/// ```
+/// use nolloc::RangeSum;
+///
+/// let mut rainfall: RangeSum<u32, 16> = [3, 0, 5, 2, 8, 1].iter().copied().collect();
+///
+/// assert_eq!(rainfall.sum(1..4), 7);
+/// rainfall.set(2, 9);
+/// assert_eq!(rainfall.sum(1..4), 11);
+/// assert_eq!(rainfall.prefix(6), 23);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RangeSum<T, const N: usize> {
+    tree: [T; N],
+    len: usize,
+}
+
+impl<T, const N: usize> RangeSum<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    /// Create a new empty sequence
+    pub fn new() -> Self {
+        RangeSum {
+            tree: [T::default(); N],
+            len: 0,
+        }
+    }
+    /// Check if the sequence is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the sequence is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the sequence's length
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the sequence's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Push a number onto the end of the sequence
+    ///
+    /// If the sequence is full, the number is returned as an error.
+    /// This is an **O(logn)** operation.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        let i = self.len + 1;
+        let covered = self.prefix(i - 1) - self.prefix(i - lowest_bit(i));
+        self.tree[i - 1] = value + covered;
+        self.len = i;
+        Ok(())
+    }
+    /// Push the numbers of an iterator onto the end of the sequence
+    ///
+    /// If the sequence fills up, the first number that did not fit is returned
+    /// as an error.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        iter.into_iter().try_for_each(|value| self.push(value))
+    }
+    /// Get the sum of the first `len` numbers
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is greater than the sequence's length.
+    pub fn prefix(&self, len: usize) -> T {
+        assert!(
+            len <= self.len,
+            "prefix of length {} is out of bounds of a sequence of length {}",
+            len,
+            self.len
+        );
+        let mut sum = T::default();
+        let mut i = len;
+        while i > 0 {
+            sum = sum + self.tree[i - 1];
+            i -= lowest_bit(i);
+        }
+        sum
+    }
+    /// Get the sum of a range of the numbers
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is decreasing or out of bounds.
+    pub fn sum(&self, range: Range<usize>) -> T {
+        assert!(range.start <= range.end, "range is decreasing");
+        self.prefix(range.end) - self.prefix(range.start)
+    }
+    /// Get the number at an index
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len {
+            Some(self.sum(index..index + 1))
+        } else {
+            None
+        }
+    }
+    /// Add to the number at an index
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn add(&mut self, index: usize, delta: T) {
+        self.update(index, |sum| sum + delta);
+    }
+    /// Replace the number at an index
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: T) {
+        let old = self.get(index).unwrap_or_default();
+        // Subtract before adding so that unsigned sums never underflow
+        self.update(index, |sum| sum - old + value);
+    }
+    /// Update every sum that covers an index
+    fn update<F>(&mut self, index: usize, f: F)
+    where
+        F: Fn(T) -> T,
+    {
+        assert!(
+            index < self.len,
+            "index {} is out of bounds of a sequence of length {}",
+            index,
+            self.len
+        );
+        let mut i = index + 1;
+        while i <= self.len {
+            self.tree[i - 1] = f(self.tree[i - 1]);
+            i += lowest_bit(i);
+        }
+    }
+    /// Remove all numbers from the sequence
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+fn lowest_bit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl<T, const N: usize> FromIterator<T> for RangeSum<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    /// Collect the numbers of an iterator into a sequence
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than `N` numbers.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut sums = RangeSum::new();
+        if sums.try_extend(iter).is_err() {
+            panic!("iterator has more than {} numbers", N);
+        }
+        sums
+    }
+}
+
+impl<T, const N: usize> Default for RangeSum<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T>,
+{
+    fn default() -> Self {
+        RangeSum::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for RangeSum<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T> + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries((0..self.len).flat_map(|i| self.get(i)))
+            .finish()
+    }
+}
+
+#[test]
+fn range_sum_queries() {
+    let values = [4i64, -2, 7, 0, 3, 3, -5, 9, 1, 6, 2];
+    let mut sums: RangeSum<i64, 16> = values.iter().copied().collect();
+    for start in 0..=values.len() {
+        for end in start..=values.len() {
+            assert_eq!(sums.sum(start..end), values[start..end].iter().sum());
+        }
+    }
+    sums.add(7, -9);
+    sums.set(0, 10);
+    assert_eq!(sums.get(7), Some(0));
+    assert_eq!(sums.prefix(11), 28 - 9 + 6);
+    assert_eq!(sums.get(11), None);
+    let mut small = RangeSum::<u8, 2>::new();
+    assert_eq!(small.try_extend([9, 2, 3]), Err(3));
+    small.set(0, 1);
+    assert_eq!(small.sum(0..2), 3);
+}
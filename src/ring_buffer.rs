@@ -0,0 +1,282 @@
+//! A circular buffer with a fixed capacity that is stored inline
+
+use core::{
+    fmt,
+    iter::{FromIterator, FusedIterator},
+};
+
+/// A circular buffer with a fixed capacity of `N` items that is stored inline
+///
+/// Items are pushed to the back and popped from the front. When the buffer is
+/// full, [`RingBuffer::push`] overwrites the oldest item, while
+/// [`RingBuffer::try_push`] rejects the new one.
+///
+/// # Example
+/// ```
+/// use nolloc::RingBuffer;
+///
+/// let mut history = RingBuffer::<u16, 3>::new();
+/// for sample in [10, 20, 30, 40] {
+///     history.push(sample);
+/// }
+///
+/// assert!(history.iter().eq(&[20, 30, 40]));
+/// assert!(history.iter().rev().eq(&[40, 30, 20]));
+/// assert_eq!(history.try_push(50), Err(50));
+/// ```
+#[derive(Clone, Copy)]
+pub struct RingBuffer<T, const N: usize> {
+    slots: [Option<T>; N],
+    /// The slot of the oldest item
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    /// Create a new empty buffer
+    pub fn new() -> Self {
+        RingBuffer {
+            slots: core::array::from_fn(|_| None),
+            start: 0,
+            len: 0,
+        }
+    }
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the buffer is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the buffer's length
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the buffer's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the slot of the item at an index, counting from the oldest
+    fn slot(&self, index: usize) -> usize {
+        (self.start + index) % N
+    }
+    /// Get the item at an index, counting from the oldest
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            self.slots[self.slot(index)].as_ref()
+        } else {
+            None
+        }
+    }
+    /// Get the oldest item in the buffer
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+    /// Get the newest item in the buffer
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+    /// Push an item onto the back of the buffer
+    ///
+    /// If the buffer is full, the oldest item is removed and returned.
+    pub fn push(&mut self, item: T) -> Option<T> {
+        if N == 0 {
+            return Some(item);
+        }
+        let evicted = if self.is_full() {
+            self.pop_front()
+        } else {
+            None
+        };
+        let slot = self.slot(self.len);
+        self.slots[slot] = Some(item);
+        self.len += 1;
+        evicted
+    }
+    /// Push an item onto the back of the buffer
+    ///
+    /// If the buffer is full, the item is returned as an error.
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.push(item);
+        Ok(())
+    }
+    /// Remove the oldest item from the buffer and return it
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let item = self.slots[self.start].take();
+        self.start = self.slot(1);
+        self.len -= 1;
+        item
+    }
+    /// Remove the newest item from the buffer and return it
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        let slot = self.slot(self.len);
+        self.slots[slot].take()
+    }
+    /// Remove all items from the buffer
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+    /// Get an iterator over the items of the buffer, from oldest to newest
+    ///
+    /// Reverse the iterator to go from newest to oldest.
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        Iter {
+            buffer: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+}
+
+/// An iterator over the items of a [`RingBuffer`]
+///
+/// This is returned by [`RingBuffer::iter`].
+pub struct Iter<'a, T, const N: usize> {
+    buffer: &'a RingBuffer<T, N>,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.buffer.get(self.front - 1)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T, const N: usize> DoubleEndedIterator for Iter<'a, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.buffer.get(self.back)
+    }
+}
+
+impl<'a, T, const N: usize> ExactSizeIterator for Iter<'a, T, N> {}
+
+impl<'a, T, const N: usize> FusedIterator for Iter<'a, T, N> {}
+
+impl<'a, T, const N: usize> Clone for Iter<'a, T, N> {
+    fn clone(&self) -> Self {
+        Iter {
+            buffer: self.buffer,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a RingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, N>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for RingBuffer<T, N> {
+    /// Push the items of an iterator onto the back of the buffer, overwriting
+    /// the oldest items when it is full
+    ///
+    /// Only the last `N` items are kept, so the iterator may be unbounded
+    /// as long as it ends.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for RingBuffer<T, N> {
+    /// Collect the last `N` items of an iterator into a buffer
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::RingBuffer;
+    ///
+    /// let recent: RingBuffer<u32, 3> = (0..1_000_000).filter(|n| n % 7 == 0).collect();
+    /// assert!(recent.iter().eq(&[999_985, 999_992, 999_999]));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buffer = RingBuffer::new();
+        buffer.extend(iter);
+        buffer
+    }
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        RingBuffer::new()
+    }
+}
+
+impl<T, U, const N: usize, const M: usize> PartialEq<RingBuffer<U, M>> for RingBuffer<T, N>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &RingBuffer<U, M>) -> bool {
+        self.len == other.len && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<T, const N: usize> Eq for RingBuffer<T, N> where T: Eq {}
+
+impl<T, const N: usize> fmt::Debug for RingBuffer<T, N>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn ring_buffer_wraps() {
+    let mut ring = RingBuffer::<u32, 4>::new();
+    for i in 0..10 {
+        let evicted = ring.push(i);
+        assert_eq!(evicted, i.checked_sub(4));
+        assert_eq!(ring.back(), Some(&i));
+    }
+    assert!(ring.iter().eq(&[6, 7, 8, 9]));
+    assert_eq!(ring.pop_back(), Some(9));
+    assert_eq!(ring.pop_front(), Some(6));
+    assert_eq!(ring.try_push(10), Ok(()));
+    assert_eq!(ring.try_push(11), Ok(()));
+    assert_eq!(ring.try_push(12), Err(12));
+    assert!(ring.iter().rev().eq(&[11, 10, 8, 7]));
+    let mut iter = ring.iter();
+    assert_eq!(iter.next(), Some(&7));
+    assert_eq!(iter.next_back(), Some(&11));
+    assert_eq!(iter.len(), 2);
+    ring.clear();
+    assert!(ring.is_empty());
+    assert_eq!(RingBuffer::<u32, 0>::new().push(1), Some(1));
+    ring.extend(0..3);
+    assert_eq!(ring, (0..3).collect::<RingBuffer<u32, 4>>());
+    assert!((0..100)
+        .collect::<RingBuffer<u32, 4>>()
+        .iter()
+        .eq(&[96, 97, 98, 99]));
+    assert!(core::iter::empty::<u32>()
+        .collect::<RingBuffer<u32, 0>>()
+        .is_empty());
+}
@@ -0,0 +1,367 @@
+//! A string made of [`str`](prim@str) chunks joined in a tree on the stack
+//!
+//! A [`Rope`] is a view of a byte range of either a single [`str`](prim@str) or a
+//! concatenation of two other ropes. Slicing a rope only narrows the range,
+//! and concatenating two ropes places one node on the stack.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    ops::{Bound, RangeBounds},
+    str,
+};
+
+/// A string made of [`str`](prim@str) chunks joined in a tree on the stack
+///
+/// The concatenation of two ropes cannot be accessed from the same scope.
+/// Instead, a continuation function is called on the new rope, and its
+/// result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Rope;
+///
+/// let key = Rope::from("baud=");
+/// key.concat(&Rope::from("9600\n"), |line| {
+///     assert_eq!(line.len(), 10);
+///     assert_eq!(line.byte(5), b'9');
+///     assert_eq!(line.slice(3..9), "d=9600");
+///     assert_eq!(line.slice(5..).slice(..4), "9600");
+/// });
+/// ```
+pub struct Rope<'a> {
+    root: Piece<'a>,
+    /// The byte range of the root that this rope views
+    start: usize,
+    end: usize,
+}
+
+enum Piece<'a> {
+    Str(&'a str),
+    Concat(&'a Concat<'a>),
+}
+
+struct Concat<'a> {
+    left: Rope<'a>,
+    right: Rope<'a>,
+}
+
+impl<'a> Rope<'a> {
+    /// Create a new empty rope
+    pub fn new() -> Self {
+        Rope::default()
+    }
+    /// Check if the rope is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+    /// Get the length of the rope in bytes
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+    /// Get the byte at an index
+    ///
+    /// This is an **O(d)** operation, where d is the depth of the tree of
+    /// concatenations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn byte(&self, index: usize) -> u8 {
+        assert!(index < self.len(), "byte index {} is out of bounds", index);
+        let (s, start, _) = self.leaf_at(index);
+        s.as_bytes()[start]
+    }
+    /// Get the [`char`] at a char index
+    ///
+    /// This is an **O(n)** operation.
+    pub fn char(&self, index: usize) -> Option<char> {
+        self.chars().nth(index)
+    }
+    /// Check if a byte index is the start or end of a [`char`]
+    ///
+    /// This is an **O(d)** operation.
+    pub fn is_char_boundary(&self, index: usize) -> bool {
+        match index.cmp(&self.len()) {
+            Ordering::Less => (self.byte(index) as i8) >= -0x40,
+            Ordering::Equal => true,
+            Ordering::Greater => false,
+        }
+    }
+    /// Get a view of a byte range of the rope
+    ///
+    /// This is an **O(d)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or does not start and end on
+    /// [`char`] boundaries.
+    pub fn slice<B>(&self, range: B) -> Rope<'a>
+    where
+        B: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(
+            start <= end && end <= self.len(),
+            "range {}..{} is out of bounds of a rope of length {}",
+            start,
+            end,
+            self.len()
+        );
+        assert!(
+            self.is_char_boundary(start) && self.is_char_boundary(end),
+            "range {}..{} is not on char boundaries",
+            start,
+            end
+        );
+        self.sub(start, end)
+    }
+    /// Concatenate another rope to the end of this one, call a continuation
+    /// function on the new rope, and return its result
+    ///
+    /// This is an **O(1)** operation.
+    pub fn concat<F, R>(&self, other: &Rope<'a>, then: F) -> R
+    where
+        F: FnOnce(&Rope) -> R,
+    {
+        let node = Concat {
+            left: *self,
+            right: *other,
+        };
+        then(&Rope {
+            root: Piece::Concat(&node),
+            start: 0,
+            end: self.len() + other.len(),
+        })
+    }
+    /// Append a [`str`](prim@str) to the end of the rope, call a continuation function
+    /// on the new rope, and return its result
+    ///
+    /// This is an **O(1)** operation.
+    pub fn push_str<F, R>(&self, s: &'a str, then: F) -> R
+    where
+        F: FnOnce(&Rope) -> R,
+    {
+        self.concat(&Rope::from(s), then)
+    }
+    /// Get an iterator over the [`str`](prim@str) chunks of the rope
+    ///
+    /// Finding each chunk is an **O(d)** operation.
+    pub fn chunks(&self) -> Chunks<'a> {
+        Chunks {
+            rope: *self,
+            pos: 0,
+        }
+    }
+    /// Get an iterator over the [`char`]s of the rope
+    pub fn chars(&self) -> Chars<'a> {
+        Chars {
+            chunks: self.chunks(),
+            chars: "".chars(),
+        }
+    }
+    /// Get an iterator over the bytes of the rope
+    pub fn bytes(&self) -> Bytes<'a> {
+        Bytes {
+            chunks: self.chunks(),
+            bytes: "".bytes(),
+        }
+    }
+    /// Narrow the view to a range relative to it
+    fn sub(&self, start: usize, end: usize) -> Rope<'a> {
+        Rope {
+            root: self.root,
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+    /// Get the longest chunk starting at a byte index, which must be in bounds
+    /// and on a [`char`] boundary
+    fn chunk_at(&self, pos: usize) -> &'a str {
+        let (s, start, end) = self.leaf_at(pos);
+        &s[start..end]
+    }
+    /// Get the [`str`](prim@str) containing a byte index, which must be in bounds,
+    /// and the range of it from the index to the end of the view
+    fn leaf_at(&self, pos: usize) -> (&'a str, usize, usize) {
+        let mut rope = *self;
+        let mut pos = pos;
+        loop {
+            let node = match rope.root {
+                Piece::Str(s) => return (s, rope.start + pos, rope.end),
+                Piece::Concat(node) => node,
+            };
+            let split = node.left.len();
+            let at = rope.start + pos;
+            if at < split {
+                rope = node.left.sub(rope.start, rope.end.min(split));
+            } else {
+                let start = rope.start.max(split);
+                rope = node.right.sub(start - split, rope.end - split);
+                pos = at - start;
+            }
+        }
+    }
+}
+
+/// An iterator over the [`str`](prim@str) chunks of a [`Rope`]
+///
+/// This is returned by [`Rope::chunks`].
+pub struct Chunks<'a> {
+    rope: Rope<'a>,
+    pos: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.rope.len() {
+            return None;
+        }
+        let chunk = self.rope.chunk_at(self.pos);
+        self.pos += chunk.len();
+        Some(chunk)
+    }
+}
+
+/// An iterator over the [`char`]s of a [`Rope`]
+///
+/// This is returned by [`Rope::chars`].
+pub struct Chars<'a> {
+    chunks: Chunks<'a>,
+    chars: str::Chars<'a>,
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(c) = self.chars.next() {
+                return Some(c);
+            }
+            self.chars = self.chunks.next()?.chars();
+        }
+    }
+}
+
+/// An iterator over the bytes of a [`Rope`]
+///
+/// This is returned by [`Rope::bytes`].
+pub struct Bytes<'a> {
+    chunks: Chunks<'a>,
+    bytes: str::Bytes<'a>,
+}
+
+impl<'a> Iterator for Bytes<'a> {
+    type Item = u8;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(b) = self.bytes.next() {
+                return Some(b);
+            }
+            self.bytes = self.chunks.next()?.bytes();
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Rope<'a> {
+    fn from(s: &'a str) -> Self {
+        Rope {
+            root: Piece::Str(s),
+            start: 0,
+            end: s.len(),
+        }
+    }
+}
+
+impl<'a> Default for Rope<'a> {
+    fn default() -> Self {
+        Rope::from("")
+    }
+}
+
+impl<'a> Clone for Rope<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for Rope<'a> {}
+
+impl<'a> Clone for Piece<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for Piece<'a> {}
+
+impl<'a, 'b> PartialEq<Rope<'b>> for Rope<'a> {
+    fn eq(&self, other: &Rope<'b>) -> bool {
+        self.len() == other.len() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl<'a> Eq for Rope<'a> {}
+
+impl<'a> PartialEq<str> for Rope<'a> {
+    fn eq(&self, other: &str) -> bool {
+        *self == Rope::from(other)
+    }
+}
+
+impl<'a> PartialEq<&str> for Rope<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == Rope::from(*other)
+    }
+}
+
+impl<'a> fmt::Debug for Rope<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for c in self.chars() {
+            fmt::Display::fmt(&c.escape_debug(), f)?;
+        }
+        f.write_str("\"")
+    }
+}
+
+impl<'a> fmt::Display for Rope<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn rope_slices() {
+    Rope::from("héllo").push_str(", ", |a| {
+        a.concat(&Rope::from("wörld!").slice(..6), |rope| {
+            assert_eq!(*rope, "héllo, wörld");
+            assert!(rope.chunks().eq(["héllo", ", ", "wörld"]));
+            assert_eq!(rope.char(8), Some('ö'));
+            assert!(!rope.is_char_boundary(2));
+            for start in 0..=rope.len() {
+                for end in start..=rope.len() {
+                    if rope.is_char_boundary(start) && rope.is_char_boundary(end) {
+                        let expected = &"héllo, wörld"[start..end];
+                        assert_eq!(rope.slice(start..end), expected);
+                    }
+                }
+            }
+        })
+    });
+}
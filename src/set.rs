@@ -1,19 +1,85 @@
 //! A growable set where all items exist on the stack
+//!
+//! Like [`Map`], items are kept in a chain in insertion order,
+//! which backs [`Set::head`], [`Set::rest`] and iteration, and a persistent
+//! balanced search tree, which backs lookups.
 
-use core::{borrow::Borrow, fmt, ptr};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    ops::{Bound, RangeBounds},
+    ptr,
+};
+
+use crate::{
+    tree::{self, InOrder, Node},
+    List, Map,
+};
+
+/// Create a [`Set`] from a list of items and call a continuation on it
+///
+/// The items are inserted in order, as with [`Set::collect`].
+///
+/// # Example
+/// ```
+/// use nolloc::set;
+///
+/// let allowed = set![0x01, 0x03, 0x10 => |opcodes| {
+///     assert_eq!(opcodes.len(), 3);
+///     opcodes.contains(&0x03)
+/// }];
+/// assert!(allowed);
+/// ```
+#[macro_export]
+macro_rules! set {
+    ($($item:expr),* $(,)? => $then:expr) => {
+        $crate::Set::collect([$($item),*], $then)
+    };
+}
 
 /// A growable set where all items exist on the stack
+///
+/// Sets implement [`PartialOrd`] and [`Hash`] over their items that are not
+/// shadowed, in ascending order. They do not implement [`Ord`], because its
+/// `min` and `max` would be called instead of [`Set::min`] and [`Set::max`]
+/// on set values. Use [`Set::cmp_items`] for a total order.
 pub struct Set<'a, T> {
     head: Option<&'a SetNode<'a, T>>,
+    root: Option<&'a SetTree<'a, T>>,
     len: usize,
+    sorted: usize,
+    /// The number of nodes in the tree that are not visible
+    hidden: usize,
 }
 
 struct SetNode<'a, T> {
     item: T,
-    left: Option<&'a Self>,
-    right: Option<&'a Self>,
+    /// The set this item was inserted into
+    ///
+    /// For items built by [`Set::collect_sorted`] or [`Set::from_array`],
+    /// this is empty.
+    prev: Set<'a, T>,
+    /// The node inserted before this one
+    older: Option<&'a Self>,
+    /// The position of this node in the chain, starting at 1
+    index: usize,
+    /// The 1-based position of an item built by [`Set::collect_sorted`] or
+    /// [`Set::from_array`], or 0
+    ///
+    /// Such an item is only visible in sets whose `sorted` is at least this.
+    ordinal: usize,
+    /// The index of the oldest entry for an equal item since it was last removed
+    first: usize,
+    /// The number of entries for an equal item since it was last removed,
+    /// or 0 if this node is a tombstone
+    count: usize,
 }
 
+type SetTree<'a, T> = Node<'a, SetNode<'a, T>>;
+
 impl<'a, T> Set<'a, T>
 where
     T: PartialOrd,
@@ -28,11 +94,28 @@ where
     }
     /// Get the set's length
     ///
-    /// This is an **O(1)** operation.
+    /// Shadowed duplicates are counted. This is an **O(1)** operation.
     pub fn len(&self) -> usize {
         self.len
     }
-    /// Get the most recently inserted item in the set
+    /// Get the number of distinct items in the set
+    ///
+    /// Unlike [`Set::len`], this does not count shadowed duplicates.
+    /// This is an **O(1)** operation.
+    pub fn distinct_len(&self) -> usize {
+        tree::size(self.root) - self.hidden
+    }
+    /// Check the invariants of the set's search tree, panicking if any are violated
+    ///
+    /// The items must be in order, every node must store its correct height,
+    /// and the tree must be balanced.
+    ///
+    /// This requires the `validate` feature.
+    #[cfg(any(test, feature = "validate"))]
+    pub fn debug_validate(&self) {
+        tree::validate(self.root, |a, b| tree::compare(&a.item, &b.item))
+    }
+    /// Get the most recently inserted item in the set that has not been removed
     ///
     /// # Example
     /// ```
@@ -43,10 +126,15 @@ where
     /// });
     /// ```
     pub fn head(&self) -> Option<&T> {
-        Some(&self.head?.item)
+        self.iter().next()
     }
     /// Get all items inserterd after the most recent one
     ///
+    /// This undoes the most recent insertion or removal. The items of a set
+    /// built by [`Set::collect_sorted`] or [`Set::from_array`] are considered
+    /// inserted in ascending order, so for such a set this removes the greatest
+    /// item.
+    ///
     /// # Example
     /// ```
     /// use nolloc::Set;
@@ -61,22 +149,16 @@ where
         } else {
             return Set::new();
         };
-        match (head.left, head.right) {
-            (None, None) => Set::new(),
-            (None, Some(node)) | (Some(node), None) => Set {
-                head: Some(node),
+        if head.ordinal == 0 {
+            head.prev
+        } else {
+            Set {
+                head: head.older,
+                root: self.root,
                 len: self.len - 1,
-            },
-            (Some(left), Some(right)) => {
-                let node = if left.contains_child(right) {
-                    left
-                } else {
-                    right
-                };
-                Set {
-                    head: Some(node),
-                    len: self.len - 1,
-                }
+                sorted: head.ordinal - 1,
+                // Equal items from an array are only hidden once none are left
+                hidden: self.hidden + usize::from(head.count == 1),
             }
         }
     }
@@ -93,11 +175,7 @@ where
     /// });
     /// ```
     pub fn min(&self) -> Option<&T> {
-        let mut curr = self.head?;
-        while let Some(left) = curr.left {
-            curr = left;
-        }
-        Some(&curr.item)
+        Some(&self.min_node()?.item)
     }
     /// Get the item with the maximum value in the set
     ///
@@ -112,44 +190,452 @@ where
     /// });
     /// ```
     pub fn max(&self) -> Option<&T> {
-        let mut curr = self.head?;
-        while let Some(right) = curr.right {
-            curr = right;
+        Some(&self.max_node()?.item)
+    }
+    /// Compare two sets by their items that are not shadowed, in ascending
+    /// order
+    ///
+    /// This is the total order that agrees with the set's [`PartialOrd`]
+    /// implementation. `Set` does not implement [`Ord`], whose `min` and `max`
+    /// would be called instead of [`Set::min`] and [`Set::max`] on set values.
+    ///
+    /// This is an **O(n)** operation.
+    pub fn cmp_items(&self, other: &Self) -> Ordering
+    where
+        T: Ord,
+    {
+        self.sorted_items().cmp(other.sorted_items())
+    }
+    /// Get the greatest item in the set that is less than a value
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([10, 20, 30], |steps| {
+    ///     assert_eq!(steps.get_lt(&20), Some(&10));
+    ///     assert_eq!(steps.get_le(&20), Some(&20));
+    ///     assert_eq!(steps.get_gt(&20), Some(&30));
+    ///     assert_eq!(steps.get_ge(&25), Some(&30));
+    ///     assert_eq!(steps.get_lt(&10), None);
+    /// });
+    /// ```
+    pub fn get_lt<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.last_before(|item| item.borrow() < value)
+    }
+    /// Get the greatest item in the set that is less than or equal to a value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get_le<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.last_before(|item| item.borrow() <= value)
+    }
+    /// Get the least item in the set that is greater than a value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get_gt<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.first_after(|item| item.borrow() <= value)
+    }
+    /// Get the least item in the set that is greater than or equal to a value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get_ge<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.first_after(|item| item.borrow() < value)
+    }
+    /// Get the number of items in the set that are less than a value
+    ///
+    /// Shadowed items are not counted. This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([50, 10, 40, 20, 30], |samples| {
+    ///     assert_eq!(samples.rank(&30), 2);
+    ///     assert_eq!(samples.rank(&35), 3);
+    ///     assert_eq!(samples.nth(samples.len() / 2), Some(&30));
+    ///     assert_eq!(samples.nth(5), None);
+    /// });
+    /// ```
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.count_before(|item| item.borrow() < value)
+    }
+    /// Get the number of items in the set that are in a range
+    ///
+    /// Shadowed items are not counted. This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([3, 8, 12, 15, 21, 27, 30], |samples| {
+    ///     assert_eq!(samples.range_count(10..20), 2);
+    ///     assert_eq!(samples.range_count(..=12), 3);
+    ///     assert_eq!(samples.range_count(20..), 3);
+    ///     assert_eq!(samples.range_count(..), 7);
+    /// });
+    /// ```
+    pub fn range_count<Q, B>(&self, range: B) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        B: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(value) => self.rank(value),
+            Bound::Excluded(value) => self.count_before(|item| item.borrow() <= value),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(value) => self.count_before(|item| item.borrow() <= value),
+            Bound::Excluded(value) => self.rank(value),
+            Bound::Unbounded => self.distinct_len(),
+        };
+        end.saturating_sub(start)
+    }
+    /// Get the item at an index in ascending order
+    ///
+    /// Shadowed items are skipped. This is an **O(logn)** operation.
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        if self.hidden == 0 {
+            Some(&self.visible(tree::nth(self.root, n)?)?.item)
+        } else {
+            self.sorted_items().nth(n)
+        }
+    }
+    /// Count the items for which `before` returns true
+    ///
+    /// `before` must return true for all items less than any item it returns true for.
+    fn count_before<P>(&self, before: P) -> usize
+    where
+        P: Fn(&T) -> bool,
+    {
+        if self.hidden == 0 {
+            tree::rank(self.root, |node| before(&node.item))
+        } else {
+            self.sorted_items().take_while(|item| before(item)).count()
         }
-        Some(&curr.item)
+    }
+    /// Find the greatest item for which `before` returns true
+    ///
+    /// `before` must return true for all items less than any item it returns true for.
+    fn last_before<P>(&self, before: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let node = if self.hidden == 0 {
+            tree::last_before(self.root, |node| before(&node.item))
+        } else {
+            tree::last(self.root, &|node: &SetNode<'a, T>| {
+                before(&node.item) && self.visible(node).is_some()
+            })
+        };
+        Some(&self.visible(node?)?.item)
+    }
+    /// Find the least item for which `before` returns false
+    ///
+    /// `before` must return true for all items less than any item it returns true for.
+    fn first_after<P>(&self, before: P) -> Option<&T>
+    where
+        P: Fn(&T) -> bool,
+    {
+        let mut nodes = InOrder::lower_bound(self.root, |node| before(&node.item));
+        Some(&nodes.find_map(|node| self.visible(node))?.item)
     }
 }
 
 impl<'a, T> Set<'a, T> {
+    /// Get the depth of the set's search tree
+    ///
+    /// This is the most items a lookup may have to compare against. The tree is
+    /// rebalanced on every insertion, so its depth is at most about 1.44 log2(n).
+    ///
+    /// This is an **O(1)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(0..1000, |set| {
+    ///     assert!(set.depth() <= 14);
+    ///     assert!(set.is_balanced());
+    /// });
+    /// ```
+    pub fn depth(&self) -> usize {
+        tree::height(self.root).into()
+    }
+    /// Check if the heights of the two subtrees of every node in the set's
+    /// search tree differ by at most one
+    ///
+    /// This is an **O(n)** operation.
+    pub fn is_balanced(&self) -> bool {
+        tree::is_balanced(self.root)
+    }
+    /// Write the shape of the set's search tree in the Graphviz DOT format
+    ///
+    /// Each node is labeled with its item, and each edge with the side of its child.
+    /// Items inserted more than once are drawn with a double border. Items
+    /// hidden by [`Set::rest`] on a set built with [`Set::collect_sorted`] or
+    /// [`Set::from_array`] are still in the tree and are drawn dashed.
+    ///
+    /// This is an **O(n)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([2, 1, 2], |set| {
+    ///     let mut dot = String::new();
+    ///     set.write_dot(&mut dot).unwrap();
+    ///     assert_eq!(
+    ///         dot,
+    ///         "digraph {\n    n0 [label=\"2\", peripheries=2];\n    n0 -> n1 [label=\"left\"];\n    n1 [label=\"1\"];\n}\n"
+    ///     );
+    /// });
+    /// ```
+    pub fn write_dot<W>(&self, w: &mut W) -> fmt::Result
+    where
+        T: fmt::Debug,
+        W: fmt::Write + ?Sized,
+    {
+        use fmt::Write as _;
+        tree::write_dot(self.root, w, &mut |w, node| {
+            write!(w, "label=\"")?;
+            write!(tree::DotEscape(&mut *w), "{:?}", node.item)?;
+            write!(w, "\"")?;
+            if node.count > 1 {
+                write!(w, ", peripheries=2")?;
+            }
+            if self.visible(node).is_none() {
+                write!(w, ", style=dashed")?;
+            }
+            Ok(())
+        })
+    }
+    /// Get an iterator over the items that start with a prefix, in ascending order
+    ///
+    /// Shadowed items are skipped. The ordering of the items must match the
+    /// ordering of their [`str`]s. Finding the first item is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// let commands = ["get", "help", "set", "settings", "sleep"];
+    ///
+    /// Set::collect(commands, |set| {
+    ///     assert!(set.iter_prefix("se").eq(&["set", "settings"]));
+    ///     assert_eq!(set.iter_prefix("x").count(), 0);
+    /// });
+    /// ```
+    pub fn iter_prefix<'p>(&self, prefix: &'p str) -> IterPrefix<'a, 'p, T>
+    where
+        T: Borrow<str>,
+    {
+        IterPrefix {
+            nodes: InOrder::lower_bound(self.root, |node| node.item.borrow() < prefix),
+            set: *self,
+            prefix,
+        }
+    }
+    /// Get the live node for an item
+    fn get_node<Q>(&self, item: &Q) -> Option<&'a SetNode<'a, T>>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.find_node(|other| tree::compare(item, other.borrow()))
+    }
+    /// Get the live node for which `probe` returns [`Ordering::Equal`]
+    ///
+    /// `probe` returns the ordering of the searched-for item relative to an item.
+    fn find_node<P>(&self, probe: P) -> Option<&'a SetNode<'a, T>>
+    where
+        P: FnMut(&T) -> Ordering,
+    {
+        self.visible(self.find_raw(probe)?)
+    }
+    /// Get the node in the tree for which `probe` returns [`Ordering::Equal`],
+    /// even if it is not visible
+    fn find_raw<P>(&self, mut probe: P) -> Option<&'a SetNode<'a, T>>
+    where
+        P: FnMut(&T) -> Ordering,
+    {
+        Some(tree::find(self.root, |node| probe(&node.item))?.entry)
+    }
+    /// Get the live node for a node in the tree, or `None` if the node is
+    /// hidden by [`Set::rest`] on a set built with [`Set::collect_sorted`]
+    /// or [`Set::from_array`]
+    fn visible(&self, mut node: &'a SetNode<'a, T>) -> Option<&'a SetNode<'a, T>> {
+        // Only the newest of a run of equal items from an array is in the
+        // tree, so fall back to older items of the run that are visible
+        while node.ordinal > self.sorted {
+            let run_start = node.first;
+            node = node.older.filter(|older| older.ordinal >= run_start)?;
+        }
+        Some(node)
+    }
+    /// Get the live node with the least item
+    fn min_node(&self) -> Option<&'a SetNode<'a, T>> {
+        InOrder::new(self.root).find_map(|node| self.visible(node))
+    }
+    /// Get the live node with the greatest item
+    fn max_node(&self) -> Option<&'a SetNode<'a, T>> {
+        InOrder::rev(self.root).find_map(|node| self.visible(node))
+    }
+    /// Get an iterator over the items that are not shadowed, in ascending order
+    fn sorted_items(&self) -> SortedItems<'a, T> {
+        SortedItems {
+            nodes: InOrder::new(self.root),
+            set: *self,
+            len: tree::size(self.root) - self.hidden,
+        }
+    }
+    /// Get an iterator over the items that are not shadowed, in descending order
+    fn sorted_items_rev(&self) -> SortedItems<'a, T> {
+        SortedItems {
+            nodes: InOrder::rev(self.root),
+            set: *self,
+            len: tree::size(self.root) - self.hidden,
+        }
+    }
+    /// Get the number of nodes in the tree that are not visible once an item
+    /// is inserted into it
+    fn hidden_after_insert<C>(&self, item: &T, cmp: C) -> usize
+    where
+        C: Fn(&T, &T) -> Ordering,
+    {
+        match self.find_raw(|other| cmp(item, other)) {
+            Some(raw) if self.visible(raw).is_none() => self.hidden - 1,
+            _ => self.hidden,
+        }
+    }
+    /// Get the index of the next node in the chain
+    fn next_index(&self) -> usize {
+        self.head.map_or(0, |head| head.index) + 1
+    }
+    /// Insert an item, ordered by a comparison function, and call a continuation on the new set
+    fn insert_by<C, F, R>(&self, item: T, cmp: C, then: F) -> R
+    where
+        C: Fn(&T, &T) -> Ordering,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let index = self.next_index();
+        let (first, count) = self
+            .find_node(|other| cmp(&item, other))
+            .map_or((index, 1), |live| (live.first, live.count + 1));
+        let node = SetNode {
+            item,
+            prev: *self,
+            older: self.head,
+            index,
+            ordinal: 0,
+            first,
+            count,
+        };
+        self.insert_node(node, self.len + 1, cmp, then)
+    }
+    /// Insert a node into the tree and call a continuation on the new set
+    fn insert_node<C, F, R>(&self, node: SetNode<'a, T>, len: usize, cmp: C, then: F) -> R
+    where
+        C: Fn(&T, &T) -> Ordering,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let hidden = self.hidden_after_insert(&node.item, &cmp);
+        let cmp = |a: &SetNode<T>, b: &SetNode<T>| cmp(&a.item, &b.item);
+        tree::insert(self.root, &node, &cmp, |root| {
+            then(&Set {
+                head: Some(&node),
+                root: Some(root),
+                len,
+                sorted: self.sorted,
+                hidden,
+            })
+        })
+    }
     /// Check if the set contains an item
     ///
     /// This is an **O(logn)** operation.
     pub fn contains<Q>(&self, item: &Q) -> bool
     where
         T: Borrow<Q>,
-        Q: PartialOrd,
+        Q: PartialOrd + ?Sized,
     {
         self.get(item).is_some()
     }
+    /// Check if the set contains any of some items
+    ///
+    /// This stops at the first item that is found. Another [`Set`] can be
+    /// passed by reference. This is an **O(mlogn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([0x01, 0x04], |pending| {
+    ///     assert!(pending.contains_any([0x02, 0x04]));
+    ///     assert!(!pending.contains_any(&[0x08, 0x10]));
+    ///     Set::collect([0x10, 0x01], |mask| assert!(pending.contains_any(mask)));
+    /// });
+    /// ```
+    pub fn contains_any<I>(&self, items: I) -> bool
+    where
+        T: PartialOrd,
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        items.into_iter().any(|item| self.contains(item.borrow()))
+    }
     /// Get an item in that set that is equals to to the item
     ///
     /// This is an **O(logn)** operation.
     pub fn get<Q>(&self, item: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
-        Q: PartialOrd,
+        Q: PartialOrd + ?Sized,
     {
-        let mut curr = self.head?;
-        loop {
-            let curr_item = curr.item.borrow();
-            if item == curr_item {
-                return Some(&curr.item);
-            } else if item < curr_item {
-                curr = curr.left?;
-            } else {
-                curr = curr.right?;
-            }
-        }
+        Some(&self.get_node(item)?.item)
+    }
+    /// Get the number of entries equal to an item, counting shadowed duplicates
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["ack", "nak", "ack"], |replies| {
+    ///     assert_eq!(replies.count("ack"), 2);
+    ///     assert_eq!(replies.count("err"), 0);
+    /// });
+    /// ```
+    pub fn count<Q>(&self, item: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.get_node(item).map_or(0, |node| node.count)
     }
 }
 
@@ -160,16 +646,83 @@ where
     /// Insert an item into the set if it does not already exist,
     /// call a continuation on the new (or old) set, and return its result
     ///
+    /// The continuation also gets whether the item was inserted.
+    ///
     /// This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2], |set| {
+    ///     assert!(!set.try_insert(2, |_, inserted| inserted));
+    ///     set.try_insert(3, |set, inserted| {
+    ///         assert!(inserted);
+    ///         assert_eq!(set.len(), 3);
+    ///     });
+    /// });
+    /// ```
     pub fn try_insert<F, R>(&self, item: T, then: F) -> R
     where
-        F: FnOnce(&Set<T>) -> R,
+        F: FnOnce(&Set<T>, bool) -> R,
     {
         if self.contains(&item) {
-            then(self)
+            then(self, false)
         } else {
-            self.insert(item, then)
+            self.insert(item, |set| then(set, true))
+        }
+    }
+    /// Get the item equal to a key, inserting one made by `make` if there is
+    /// none, call a continuation on the new (or old) set and the item, and
+    /// return its result
+    ///
+    /// The item made by `make` must be equal to the key. This is only checked
+    /// in debug builds. If the item is missing, it is inserted without
+    /// searching the tree again. This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([String::from("ok")], |names| {
+    ///     names.get_or_insert_with("ok", || unreachable!(), |names, ok| {
+    ///         assert_eq!(ok, "ok");
+    ///         assert_eq!(names.len(), 1);
+    ///     });
+    ///     names.get_or_insert_with("err", || String::from("err"), |names, err| {
+    ///         assert_eq!(err, "err");
+    ///         assert_eq!(names.len(), 2);
+    ///     });
+    /// });
+    /// ```
+    pub fn get_or_insert_with<Q, M, F, R>(&self, key: &Q, make: M, then: F) -> R
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        M: FnOnce() -> T,
+        F: FnOnce(&Set<T>, &T) -> R,
+    {
+        if let Some(live) = self.get_node(key) {
+            return then(self, &live.item);
         }
+        let item = make();
+        debug_assert!(
+            item.borrow() == key,
+            "item made by Set::get_or_insert_with is not equal to its key"
+        );
+        let index = self.next_index();
+        let node = SetNode {
+            item,
+            prev: *self,
+            older: self.head,
+            index,
+            ordinal: 0,
+            first: index,
+            count: 1,
+        };
+        self.insert_node(node, self.len + 1, tree::compare, |set| {
+            then(set, &set.head.unwrap().item)
+        })
     }
     /// Insert an item into the set, call a continuation on the
     /// new set, and return its result
@@ -178,127 +731,1323 @@ where
     /// but the new entry is still inserted. All lookups on the new set
     /// will find the most recently inserted item.
     ///
-    /// This is an **O(logn)** operation.
+    /// This is an **O(logn)** operation. The tree is rebalanced on every
+    /// insertion, so this holds regardless of the order items are inserted in.
     pub fn insert<F, R>(&self, item: T, then: F) -> R
     where
         F: FnOnce(&Set<T>) -> R,
     {
-        let mut node = SetNode {
-            item,
-            left: None,
-            right: None,
-        };
-        if let Some(head) = self.head {
-            if node.item < head.item {
-                node.right = Some(head);
-                let mut curr = head;
-                while node.item < curr.item {
-                    curr = if let Some(next) = curr.left.or(curr.right) {
-                        next
-                    } else {
-                        break;
-                    }
-                }
-                if node.item > curr.item {
-                    node.left = Some(curr);
-                }
-            } else {
-                node.left = Some(head);
-                let mut curr = head;
-                while node.item >= curr.item {
-                    curr = if let Some(next) = curr.right.or(curr.left) {
-                        next
-                    } else {
-                        break;
-                    }
-                }
-                if node.item < curr.item {
-                    node.right = Some(curr);
-                }
-            }
-        }
-        then(&Set {
-            head: Some(&node),
-            len: self.len + 1,
-        })
+        self.insert_by(item, tree::compare, then)
     }
-    /// Get an iterator over the key/item pairs of the list
+    /// Insert an item into the set, replacing an equal item if there is one,
+    /// call a continuation on the new set and the replaced item, and return its result
     ///
-    /// The iterator yields items in the opposite order of their insertion.
-    pub fn iter(&self) -> Iter<'a, T> {
-        Iter { node: self.head }
-    }
-    /// Collect an iterator into a set and call a continuation function on it
+    /// The replaced item is shadowed, so it is no longer seen by the new set's
+    /// lookups, iteration or length.
+    ///
+    /// This is an **O(logn)** operation.
     ///
     /// # Example
     /// ```
     /// use nolloc::Set;
     ///
-    /// let nums = [2, 6, 2, 8, 5, 4];
-    ///
-    /// Set::collect(nums, |set| {
-    ///     for n in &nums {
-    ///         assert!(set.contains(n));
+    /// Set::collect(["a", "b"], |set| {
+    ///     set.replace("a", |set, old| {
+    ///         assert_eq!(old, Some(&"a"));
+    ///         assert_eq!(set.len(), 2);
+    ///         assert!(set.iter().eq(&["a", "b"]));
+    ///     })
+    /// });
+    /// ```
+    pub fn replace<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Set<T>, Option<&T>) -> R,
+    {
+        let old = self.get_node(&item);
+        let index = self.next_index();
+        let node = SetNode {
+            item,
+            prev: *self,
+            older: self.head,
+            index,
+            ordinal: 0,
+            first: index,
+            count: 1,
+        };
+        let len = self.len - old.map_or(0, |old| old.count) + 1;
+        self.insert_node(node, len, tree::compare, |set| {
+            then(set, old.map(|old| &old.item))
+        })
+    }
+    /// Remove an item from the set, call a continuation on the new set,
+    /// and return its result
+    ///
+    /// Nothing is actually freed. Instead, a tombstone is inserted, so the new
+    /// set's lookups, iteration and length no longer see any entry with the item.
+    /// [`Set::rest`] on the new set undoes the removal. If the set does not
+    /// contain the item, the continuation gets the set unchanged.
+    ///
+    /// This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 3, 2], |set| {
+    ///     set.remove(2, |set| {
+    ///         assert!(!set.contains(&2));
+    ///         assert_eq!(set.len(), 2);
+    ///         assert!(set.iter().eq(&[3, 1]));
+    ///         set.insert(2, |set| assert!(set.iter().eq(&[2, 3, 1])));
+    ///     })
+    /// });
+    /// ```
+    pub fn remove<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Set<T>) -> R,
+    {
+        if let Some(live) = self.get_node(&item) {
+            self.remove_live(item, live, then)
+        } else {
+            then(self)
+        }
+    }
+    /// Remove the least item from the set, call a continuation on the new set
+    /// and the removed item, and return its result
+    ///
+    /// Like [`Set::remove`], this inserts a tombstone, so shadowed duplicates
+    /// of the item are removed as well. The continuation gets [`None`] and the
+    /// set unchanged if the set is empty. This is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([3, 1, 2], |queue| {
+    ///     queue.pop_min(|queue, first| {
+    ///         assert_eq!(first, Some(&1));
+    ///         queue.pop_min(|queue, second| {
+    ///             assert_eq!(second, Some(&2));
+    ///             assert!(queue.iter().eq(&[3]));
+    ///         })
+    ///     })
+    /// });
+    /// ```
+    pub fn pop_min<F, R>(&self, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&Set<T>, Option<&T>) -> R,
+    {
+        match self.min_node() {
+            Some(live) => {
+                self.remove_live(live.item.clone(), live, |set| then(set, Some(&live.item)))
+            }
+            None => then(self, None),
+        }
+    }
+    /// Remove the greatest item from the set, call a continuation on the new
+    /// set and the removed item, and return its result
+    ///
+    /// See [`Set::pop_min`]. This is an **O(logn)** operation.
+    pub fn pop_max<F, R>(&self, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&Set<T>, Option<&T>) -> R,
+    {
+        match self.max_node() {
+            Some(live) => {
+                self.remove_live(live.item.clone(), live, |set| then(set, Some(&live.item)))
+            }
+            None => then(self, None),
+        }
+    }
+    /// Insert a tombstone for an item, given its live node, and call a
+    /// continuation on the new set
+    fn remove_live<F, R>(&self, item: T, live: &SetNode<'a, T>, then: F) -> R
+    where
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let index = self.next_index();
+        let node = SetNode {
+            item,
+            prev: *self,
+            older: self.head,
+            index,
+            ordinal: 0,
+            first: index,
+            count: 0,
+        };
+        tree::remove(
+            self.root,
+            |entry| tree::compare(&node.item, &entry.item),
+            |root| {
+                then(&Set {
+                    head: Some(&node),
+                    root,
+                    len: self.len - live.count,
+                    sorted: self.sorted,
+                    hidden: self.hidden,
+                })
+            },
+        )
+    }
+    /// Get an iterator over the items of the set
+    ///
+    /// The iterator yields items in the opposite order of their insertion.
+    /// Each step is an **O(1)** operation, unless items have been removed or
+    /// replaced, in which case it is **O(logn)**. Either way, iteration stops
+    /// as soon as [`Set::len`] items have been yielded.
+    pub fn iter(&self) -> Iter<'a, T> {
+        // Removed items only need to be filtered out if there are tombstones
+        let has_tombstones = self.head.is_some_and(|head| head.index != self.len);
+        Iter {
+            node: self.head,
+            live: Some((*self, Set::is_live as LiveCheck<T>)).filter(|_| has_tombstones),
+            len: self.len,
+        }
+    }
+    /// Check that a node has not been removed or replaced
+    fn is_live(&self, node: &SetNode<'a, T>) -> bool {
+        self.get_node(&node.item)
+            .is_some_and(|live| node.index >= live.first)
+    }
+    /// Collect an iterator into a set and call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// let nums = [2, 6, 2, 8, 5, 4];
+    ///
+    /// Set::collect(nums, |set| {
+    ///     for n in &nums {
+    ///         assert!(set.contains(n));
     ///     }
     /// });
     /// ```
     pub fn collect<I, F, R>(iter: I, then: F) -> R
     where
-        T: PartialOrd,
+        T: PartialOrd,
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        Set::default().extend(iter, then)
+    }
+    /// Collect an iterator of sorted, distinct items into a set and call a
+    /// continuation function on it
+    ///
+    /// Rather than inserting the items one at a time, this builds a perfectly
+    /// balanced tree directly, without comparing items. This is an **O(n)** operation.
+    ///
+    /// The items must be in strictly ascending order. This is only checked
+    /// in debug builds. The items are considered inserted in ascending order,
+    /// so the greatest one is the set's [`head`](Set::head), and [`Set::rest`]
+    /// removes it.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// let table = [0x01, 0x03, 0x10, 0x20, 0x7f];
+    ///
+    /// Set::collect_sorted(table, |opcodes| {
+    ///     assert_eq!(opcodes.len(), 5);
+    ///     assert!(opcodes.contains(&0x10));
+    ///     assert_eq!(opcodes.head(), Some(&0x7f));
+    ///     assert_eq!(opcodes.rest().max(), Some(&0x20));
+    /// });
+    /// ```
+    pub fn collect_sorted<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        let len = iter.len();
+        let mut then = Some(then);
+        build_sorted(len, &mut iter, None, &mut |_, root, head| {
+            (then.take().expect("continuation called more than once"))(&Set {
+                head,
+                root,
+                len,
+                sorted: len,
+                hidden: 0,
+            })
+        })
+    }
+    /// Build a set from an array and call a continuation function on it
+    ///
+    /// The array is sorted and a perfectly balanced tree is built from it in a
+    /// single stack frame. This is an **O(nlogn)** operation. The items are
+    /// considered inserted in ascending order, so iteration yields them in
+    /// descending order, and [`Set::rest`] removes the greatest one.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::from_array([30, 10, 20, 10], |allowed| {
+    ///     assert_eq!(allowed.len(), 4);
+    ///     assert!(allowed.contains(&20));
+    ///     assert_eq!(allowed.nth(1), Some(&20));
+    ///     assert!(allowed.iter().eq(&[30, 20, 10, 10]));
+    ///     assert!(allowed.rest().iter().eq(&[20, 10, 10]));
+    /// });
+    /// ```
+    pub fn from_array<const N: usize, F, R>(items: [T; N], then: F) -> R
+    where
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let mut items = items;
+        items.sort_unstable_by(|a, b| tree::compare(a, b));
+        // Chain the items, starting a run for each group of equal items
+        let mut nodes: [Option<SetNode<T>>; N] = core::array::from_fn(|_| None);
+        let mut free_nodes: &mut [Option<SetNode<T>>] = &mut nodes;
+        let mut live: [Option<&SetNode<T>>; N] = [None; N];
+        let mut live_len = 0;
+        let mut older: Option<&SetNode<T>> = None;
+        for (i, item) in IntoIterator::into_iter(items).enumerate() {
+            let (first, count) = match older {
+                Some(older) if older.item == item => {
+                    live_len -= 1;
+                    (older.first, older.count + 1)
+                }
+                _ => (i + 1, 1),
+            };
+            let (slot, rest) = core::mem::take(&mut free_nodes)
+                .split_first_mut()
+                .expect("one node per item");
+            free_nodes = rest;
+            let node = &*slot.insert(SetNode {
+                item,
+                prev: Set::new(),
+                older,
+                index: i + 1,
+                ordinal: i + 1,
+                first,
+                count,
+            });
+            live[live_len] = Some(node);
+            live_len += 1;
+            older = Some(node);
+        }
+        // Build the tree from the last item of each run
+        let mut tree_nodes = [None; N];
+        let mut free: &mut [Option<SetTree<T>>] = &mut tree_nodes;
+        let root = tree::build(&live[..live_len], &mut free);
+        then(&Set {
+            head: older,
+            root,
+            len: N,
+            sorted: N,
+            hidden: 0,
+        })
+    }
+    /// Extend the set with an iterator and call a continuation function on it
+    ///
+    /// Items are inserted several at a time per stack frame, which takes less
+    /// stack than calling [`Set::insert`] for each of them.
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        T: PartialOrd,
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let mut then = Some(then);
+        extend_chunks(self, &mut iter.into_iter(), false, &mut |set| {
+            (then.take().expect("continuation called more than once"))(set)
+        })
+    }
+    /// Collect an iterator of results into a set and call a continuation
+    /// function on it
+    ///
+    /// Collection stops at the first error, which is returned without calling
+    /// the continuation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// let ids = ["3", "1", "3", "2"];
+    /// let distinct = Set::try_collect(ids.iter().map(|id| id.parse::<u8>()), |set| {
+    ///     set.distinct_len()
+    /// });
+    /// assert_eq!(distinct, Ok(3));
+    ///
+    /// let ids = ["3", "x", "2"];
+    /// assert!(Set::try_collect(ids.iter().map(|id| id.parse::<u8>()), |_| ()).is_err());
+    /// ```
+    pub fn try_collect<I, E, F, R>(iter: I, then: F) -> Result<R, E>
+    where
+        T: PartialOrd,
+        I: IntoIterator<Item = Result<T, E>>,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        Set::default().try_extend(iter, then)
+    }
+    /// Extend the set with an iterator of results and call a continuation
+    /// function on it
+    ///
+    /// Extension stops at the first error, which is returned without calling
+    /// the continuation.
+    pub fn try_extend<I, E, F, R>(&self, iter: I, then: F) -> Result<R, E>
+    where
+        T: PartialOrd,
+        I: IntoIterator<Item = Result<T, E>>,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        match iter.next() {
+            Some(Ok(item)) => self.insert(item, |set| set.try_extend(iter, then)),
+            Some(Err(e)) => Err(e),
+            None => Ok(then(self)),
+        }
+    }
+    /// Get an iterator over the items in either this set or another
+    ///
+    /// Each item is yielded once, without building a new set. Items of this set
+    /// come first, and an item in both sets is yielded from this set.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 3], |a| {
+    ///     Set::collect([3, 4], |b| {
+    ///         let union = a.union(b);
+    ///         assert!(union.contains(&4));
+    ///         assert_eq!(union.count(), 4);
+    ///     })
+    /// });
+    /// ```
+    pub fn union(&self, other: &Set<'a, T>) -> Union<'a, T> {
+        Union {
+            first: *self,
+            first_items: self.iter_unique(),
+            second_items: other.iter_unique(),
+        }
+    }
+    /// Collect the items in either this set or another into a new set and
+    /// call a continuation function on it
+    ///
+    /// Each item is collected once, so the new set has no shadowed duplicates.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["a", "b", "a"], |a| {
+    ///     Set::collect(["b", "c"], |b| {
+    ///         a.union_collect(b, |both| {
+    ///             assert_eq!(both.len(), 3);
+    ///             assert!(both.contains(&&"c"));
+    ///         })
+    ///     })
+    /// });
+    /// ```
+    pub fn union_collect<F, R>(&self, other: &Set<'a, T>, then: F) -> R
+    where
+        F: FnOnce(&Set<&'a T>) -> R,
+    {
+        Set::collect(self.union(other), then)
+    }
+    /// Extend the set with the items of another set that it does not already
+    /// contain and call a continuation function on it
+    ///
+    /// Shadowed duplicates in the other set are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2], |a| {
+    ///     Set::collect([2, 3, 3], |b| {
+    ///         a.extend_from_set(b, |a| {
+    ///             assert_eq!(a.len(), 3);
+    ///             assert!(a.iter().eq(&[3, 2, 1]));
+    ///         })
+    ///     })
+    /// });
+    /// ```
+    pub fn extend_from_set<F, R>(&self, other: &Set<T>, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&Set<T>) -> R,
+    {
+        let items = other.iter_unique().filter(|item| !self.contains(*item));
+        self.extend(items.cloned(), then)
+    }
+    /// Get an iterator over the items in both this set and another
+    ///
+    /// The smaller set is iterated and the larger one probed, so this is an
+    /// **O(nlogm)** operation. Items are yielded from the smaller set.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["fly", "swim", "walk"], |a| {
+    ///     Set::collect(["walk", "fly"], |b| {
+    ///         assert_eq!(a.intersection(b).count(), 2);
+    ///         assert!(a.intersection(b).all(|cap| *cap != "swim"));
+    ///     })
+    /// });
+    /// ```
+    pub fn intersection(&self, other: &Set<'a, T>) -> Intersection<'a, T> {
+        let (smaller, larger) = if self.len <= other.len {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Intersection {
+            items: smaller.iter_unique(),
+            other: *larger,
+        }
+    }
+    /// Collect the items in both this set and another into a new set and
+    /// call a continuation function on it
+    ///
+    /// As with [`Set::intersection`], the items come from the smaller set.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 3, 4], |a| {
+    ///     Set::collect([4, 2, 6], |b| {
+    ///         a.intersection_collect(b, |common| {
+    ///             assert_eq!(common.len(), 2);
+    ///             assert_eq!(common.min(), Some(&&2));
+    ///         })
+    ///     })
+    /// });
+    /// ```
+    pub fn intersection_collect<F, R>(&self, other: &Set<'a, T>, then: F) -> R
+    where
+        F: FnOnce(&Set<&'a T>) -> R,
+    {
+        Set::collect(self.intersection(other), then)
+    }
+    /// Get an iterator over the items in this set but not in another
+    ///
+    /// This is an **O(nlogm)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 3, 4], |now| {
+    ///     Set::collect([1, 3], |before| {
+    ///         assert_eq!(now.difference(before).count(), 2);
+    ///         assert!(now.difference(before).all(|n| n % 2 == 0));
+    ///     })
+    /// });
+    /// ```
+    pub fn difference(&self, other: &Set<'a, T>) -> Difference<'a, T> {
+        Difference {
+            items: self.iter_unique(),
+            other: *other,
+        }
+    }
+    /// Collect the items in this set but not in another into a new set and
+    /// call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["a", "b", "c"], |now| {
+    ///     Set::collect(["b"], |before| {
+    ///         now.difference_collect(before, |new| {
+    ///             assert_eq!(new.len(), 2);
+    ///             assert!(new.contains(&&"c"));
+    ///         })
+    ///     })
+    /// });
+    /// ```
+    pub fn difference_collect<F, R>(&self, other: &Set<'a, T>, then: F) -> R
+    where
+        F: FnOnce(&Set<&'a T>) -> R,
+    {
+        Set::collect(self.difference(other), then)
+    }
+    /// Get an iterator over the items in exactly one of this set and another
+    ///
+    /// Items only in this set come first. This is an **O((n+m)log(n+m))** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["debug", "fast"], |old| {
+    ///     Set::collect(["fast", "lto"], |new| {
+    ///         assert!(old.symmetric_difference(new).eq(&["debug", "lto"]));
+    ///     })
+    /// });
+    /// ```
+    pub fn symmetric_difference(&self, other: &Set<'a, T>) -> SymmetricDifference<'a, T> {
+        SymmetricDifference {
+            first: self.difference(other),
+            second: other.difference(self),
+        }
+    }
+    /// Check if every item in this set is also in another
+    ///
+    /// This stops at the first item that is missing. It is an **O(nlogm)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["read"], |required| {
+    ///     Set::collect(["read", "write"], |granted| {
+    ///         assert!(required.is_subset(granted));
+    ///         assert!(granted.is_superset(required));
+    ///         assert!(!granted.is_subset(required));
+    ///     })
+    /// });
+    /// ```
+    pub fn is_subset(&self, other: &Set<T>) -> bool {
+        self.iter_unique().all(|item| other.contains(item))
+    }
+    /// Check if every item in another set is also in this one
+    ///
+    /// This stops at the first item that is missing. It is an **O(mlogn)** operation.
+    pub fn is_superset(&self, other: &Set<T>) -> bool {
+        other.is_subset(self)
+    }
+    /// Get a view of the items for which a predicate returns true
+    ///
+    /// The view is lazy: nothing is collected, and the predicate is called
+    /// whenever the view is queried. See [`Set::retain_collect`] for an eager version.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 3, 4, 5, 6], |set| {
+    ///     let even = set.retain_view(|n| n % 2 == 0);
+    ///     assert!(even.contains(&4));
+    ///     assert!(!even.contains(&5));
+    ///     assert_eq!(even.len(), 3);
+    ///     assert!(even.iter().eq(&[6, 4, 2]));
+    /// });
+    /// ```
+    pub fn retain_view<P>(&self, pred: P) -> RetainView<'a, T, P>
+    where
+        P: Fn(&T) -> bool,
+    {
+        RetainView { set: *self, pred }
+    }
+    /// Collect the items for which a predicate returns true into a new set
+    /// and call a continuation function on it
+    ///
+    /// Shadowed items are skipped. This is an **O(nlogn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["main.rs", "lib.rs", "README.md"], |files| {
+    ///     files.retain_collect(
+    ///         |name| name.ends_with(".rs"),
+    ///         |sources| {
+    ///             assert_eq!(sources.len(), 2);
+    ///             assert!(!sources.contains(&&"README.md"));
+    ///         },
+    ///     )
+    /// });
+    /// ```
+    pub fn retain_collect<P, F, R>(&self, pred: P, then: F) -> R
+    where
+        P: Fn(&T) -> bool,
+        F: FnOnce(&Set<&'a T>) -> R,
+    {
+        Set::collect(self.iter_unique().filter(|item| pred(item)), then)
+    }
+    /// Get an iterator over the items that were inserted more than once,
+    /// along with how many times, in ascending order
+    ///
+    /// Insertions from before an item was last removed or replaced are not
+    /// counted. This is an **O(n)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([7, 3, 7, 1, 3, 7], |seen| {
+    ///     assert!(seen.iter_duplicates().eq([(&3, 2), (&7, 3)]));
+    /// });
+    /// ```
+    pub fn iter_duplicates(&self) -> IterDuplicates<'a, T> {
+        IterDuplicates {
+            nodes: InOrder::new(self.root),
+            set: *self,
+        }
+    }
+    /// Get an iterator over the distinct items of the set and the number of
+    /// entries for each, in ascending order
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([7, 3, 7, 1, 3, 7], |seen| {
+    ///     assert!(seen.iter_counts().eq([(&1, 1), (&3, 2), (&7, 3)]));
+    /// });
+    /// ```
+    pub fn iter_counts(&self) -> IterCounts<'a, T> {
+        IterCounts {
+            nodes: InOrder::new(self.root),
+            set: *self,
+        }
+    }
+    /// Collect the items that are not shadowed into a [`List`] in ascending
+    /// order and call a continuation function on it
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([3, 1, 2, 1], |set| {
+    ///     set.to_list(|list| assert!(list.iter().eq(&[&1, &2, &3])));
+    /// });
+    /// ```
+    pub fn to_list<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(&List<&'a T>) -> R,
+    {
+        List::collect(self.sorted_items_rev(), then)
+    }
+    /// Collect the items that are not shadowed into a [`Map`] with values
+    /// computed from them and call a continuation function on it
+    ///
+    /// The items are already sorted, so the map's tree is built directly.
+    /// This is an **O(n)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["cat", "horse", "ox"], |animals| {
+    ///     animals.to_map_with(|name| name.len(), |lengths| {
+    ///         assert_eq!(lengths.get(&"horse"), Some(&5));
+    ///         assert_eq!(lengths.len(), 3);
+    ///     })
+    /// });
+    /// ```
+    pub fn to_map_with<V, M, F, R>(&self, mut f: M, then: F) -> R
+    where
+        M: FnMut(&'a T) -> V,
+        F: FnOnce(&Map<&'a T, V>) -> R,
+    {
+        Map::collect_sorted(self.sorted_items().map(|item| (item, f(item))), then)
+    }
+    /// Collect the items that are not shadowed into a new set, transforming
+    /// each one, and call a continuation function on it
+    ///
+    /// Items are visited in ascending order. If several of them transform to
+    /// equal items, only the first is kept, so the new set has no shadowed duplicates.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect(["Apple", "apple", "Banana"], |names| {
+    ///     names.map_collect(|name| name.to_lowercase(), |lower| {
+    ///         assert_eq!(lower.len(), 2);
+    ///         assert!(lower.contains(&String::from("apple")));
+    ///     })
+    /// });
+    /// ```
+    pub fn map_collect<U, M, F, R>(&self, f: M, then: F) -> R
+    where
+        U: PartialOrd,
+        M: FnMut(&'a T) -> U,
+        F: FnOnce(&Set<U>) -> R,
+    {
+        let mut then = Some(then);
+        extend_chunks(
+            &Set::default(),
+            &mut self.sorted_items().map(f),
+            true,
+            &mut |set| (then.take().expect("continuation called more than once"))(set),
+        )
+    }
+    /// Get an iterator over the items of the set, skipping shadowed duplicates
+    ///
+    /// Like [`Set::iter`], this yields items in the opposite order of their
+    /// insertion, but each distinct item is only yielded once, as the entry
+    /// that lookups find. Each step is an **O(logn)** operation.
+    ///
+    /// # Example
+    /// ```
+    /// use nolloc::Set;
+    ///
+    /// Set::collect([1, 2, 1, 3, 2], |set| {
+    ///     assert_eq!(set.len(), 5);
+    ///     assert_eq!(set.distinct_len(), 3);
+    ///     assert!(set.iter_unique().eq(&[2, 3, 1]));
+    /// });
+    /// ```
+    pub fn iter_unique(&self) -> IterUnique<'a, T> {
+        IterUnique {
+            items: self.iter(),
+            set: *self,
+        }
+    }
+}
+
+/// The most items [`Set::extend`] inserts per stack frame
+const EXTEND_CHUNK: usize = 8;
+
+type ExtendThen<'t, T, R> = dyn FnMut(&Set<T>) -> R + 't;
+
+/// Extend a set with chunks of items
+///
+/// If `distinct` is true, items the set already contains are skipped.
+fn extend_chunks<I, T, R>(
+    set: &Set<T>,
+    iter: &mut I,
+    distinct: bool,
+    then: &mut ExtendThen<T, R>,
+) -> R
+where
+    I: Iterator<Item = T>,
+    T: PartialOrd,
+{
+    // The tree buffer is sized by height so that a chunk usually fills it
+    match tree::height(set.root) {
+        0..=5 => extend_chunk::<_, _, _, { EXTEND_CHUNK * 8 }>(set, iter, distinct, then),
+        6..=9 => extend_chunk::<_, _, _, { EXTEND_CHUNK * 12 }>(set, iter, distinct, then),
+        10..=13 => extend_chunk::<_, _, _, { EXTEND_CHUNK * 16 }>(set, iter, distinct, then),
+        _ => extend_chunk::<_, _, _, { EXTEND_CHUNK * 24 }>(set, iter, distinct, then),
+    }
+}
+
+/// Insert items until the iterator or the frame's buffers run out, then
+/// continue in a new frame
+// Kept out of line so that only the chosen buffer size takes up stack space
+#[inline(never)]
+fn extend_chunk<I, T, R, const S: usize>(
+    set: &Set<T>,
+    iter: &mut I,
+    distinct: bool,
+    then: &mut ExtendThen<T, R>,
+) -> R
+where
+    I: Iterator<Item = T>,
+    T: PartialOrd,
+{
+    let mut items: [Option<SetNode<T>>; EXTEND_CHUNK] = core::array::from_fn(|_| None);
+    let mut free_items: &mut [Option<SetNode<T>>] = &mut items;
+    let mut nodes = [None; S];
+    let mut free: &mut [Option<SetTree<T>>] = &mut nodes;
+    let cmp = |a: &SetNode<T>, b: &SetNode<T>| tree::compare(&a.item, &b.item);
+    let mut set = *set;
+    while !free_items.is_empty() && free.len() >= tree::insert_room(set.root) {
+        let item = match iter.next() {
+            Some(item) => item,
+            None => return then(&set),
+        };
+        let live = set.get_node(&item);
+        if distinct && live.is_some() {
+            continue;
+        }
+        let (slot, rest) = core::mem::take(&mut free_items)
+            .split_first_mut()
+            .expect("free_items is not empty");
+        free_items = rest;
+        let index = set.next_index();
+        let (first, count) = live.map_or((index, 1), |live| (live.first, live.count + 1));
+        let hidden = set.hidden_after_insert(&item, tree::compare);
+        let node = &*slot.insert(SetNode {
+            item,
+            prev: set,
+            older: set.head,
+            index,
+            ordinal: 0,
+            first,
+            count,
+        });
+        set = Set {
+            head: Some(node),
+            root: Some(tree::insert_into(set.root, node, &cmp, &mut free)),
+            len: set.len + 1,
+            sorted: set.sorted,
+            hidden,
+        };
+    }
+    extend_chunks(&set, iter, distinct, then)
+}
+
+type BuildThen<'f, I, T, R> = dyn FnMut(&mut I, Option<&SetTree<T>>, Option<&SetNode<T>>) -> R + 'f;
+
+/// Build a balanced tree from the next `len` items of a sorted iterator
+///
+/// The continuation receives the iterator, the root of the tree, and the last node built.
+fn build_sorted<'a, I, T, R>(
+    len: usize,
+    iter: &mut I,
+    older: Option<&'a SetNode<'a, T>>,
+    then: &mut BuildThen<I, T, R>,
+) -> R
+where
+    I: Iterator<Item = T>,
+    T: PartialOrd,
+{
+    if len == 0 {
+        return then(iter, None, older);
+    }
+    build_sorted(len / 2, iter, older, &mut |iter, left, older| {
+        let item = iter
+            .next()
+            .expect("iterator shorter than its reported length");
+        debug_assert!(
+            match older {
+                Some(older) => older.item < item,
+                None => true,
+            },
+            "items passed to Set::collect_sorted are not strictly ascending"
+        );
+        let index = older.map_or(0, |older| older.index) + 1;
+        let node = SetNode {
+            item,
+            prev: Set::default(),
+            older,
+            index,
+            ordinal: index,
+            first: index,
+            count: 1,
+        };
+        build_sorted(
+            len - len / 2 - 1,
+            iter,
+            Some(&node),
+            &mut |iter, right, last| then(iter, Some(&Node::new(&node, left, right)), last),
+        )
+    })
+}
+
+/// A growable set where all items exist on the stack, ordered by a comparison function
+///
+/// This lets items be ordered by a projection or a case-insensitive rule without
+/// wrapping them in a newtype. Lookups take an item to compare against.
+///
+/// # Example
+/// ```
+/// use nolloc::set::SetBy;
+///
+/// let cmp = |a: &&str, b: &&str| a.to_lowercase().cmp(&b.to_lowercase());
+///
+/// SetBy::collect(["Apple", "banana", "APPLE"], cmp, |fruits| {
+///     assert!(fruits.contains(&"apple"));
+///     assert_eq!(fruits.get(&"apple"), Some(&"APPLE"));
+///     assert_eq!(fruits.min(), Some(&"APPLE"));
+///     assert_eq!(fruits.max(), Some(&"banana"));
+/// });
+/// ```
+pub struct SetBy<'a, T, C> {
+    set: Set<'a, T>,
+    cmp: C,
+}
+
+impl<'a, T, C> SetBy<'a, T, C>
+where
+    C: Fn(&T, &T) -> Ordering + Copy,
+{
+    /// Create a new set ordered by a comparison function
+    pub fn new(cmp: C) -> Self {
+        SetBy {
+            set: Set::default(),
+            cmp,
+        }
+    }
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.set.len == 0
+    }
+    /// Get the set's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.set.len
+    }
+    /// Get the most recently inserted item in the set
+    pub fn head(&self) -> Option<&T> {
+        self.iter().next()
+    }
+    /// Get all items inserted after the most recent one
+    pub fn rest(&self) -> Self {
+        SetBy {
+            set: self.set.head.map_or(self.set, |head| head.prev),
+            cmp: self.cmp,
+        }
+    }
+    /// Check if the set contains an item that compares equal to the given one
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains(&self, item: &T) -> bool {
+        self.get(item).is_some()
+    }
+    /// Get the item in the set that compares equal to the given one
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, item: &T) -> Option<&T> {
+        Some(&self.set.find_node(|other| (self.cmp)(item, other))?.item)
+    }
+    /// Get the least item in the set
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn min(&self) -> Option<&T> {
+        Some(&tree::lower_bound(self.set.root, |_| false)?.item)
+    }
+    /// Get the greatest item in the set
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn max(&self) -> Option<&T> {
+        Some(&tree::last_before(self.set.root, |_| true)?.item)
+    }
+    /// Get the item at an index in ascending order
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn nth(&self, n: usize) -> Option<&T> {
+        Some(&tree::nth(self.set.root, n)?.item)
+    }
+    /// Insert an item into the set, call a continuation on the
+    /// new set, and return its result
+    ///
+    /// As with [`Set::insert`], an item that compares equal to the new one is
+    /// shadowed rather than removed.
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&SetBy<T, C>) -> R,
+    {
+        let cmp = self.cmp;
+        self.set
+            .insert_by(item, cmp, |set| then(&SetBy { set: *set, cmp }))
+    }
+    /// Insert an item into the set if it does not already exist,
+    /// call a continuation on the new (or old) set, and return its result
+    ///
+    /// The continuation also gets whether the item was inserted.
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn try_insert<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&SetBy<T, C>, bool) -> R,
+    {
+        if self.contains(&item) {
+            then(self, false)
+        } else {
+            self.insert(item, |set| then(set, true))
+        }
+    }
+    /// Get an iterator over the items of the set
+    ///
+    /// The iterator yields items in the opposite order of their insertion.
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            node: self.set.head,
+            live: None,
+            len: self.set.len,
+        }
+    }
+    /// Collect an iterator into a set ordered by a comparison function and
+    /// call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, cmp: C, then: F) -> R
+    where
         I: IntoIterator<Item = T>,
-        F: FnOnce(&Set<T>) -> R,
+        F: FnOnce(&SetBy<T, C>) -> R,
     {
-        Set::default().extend(iter, then)
+        SetBy::new(cmp).extend(iter, then)
     }
     /// Extend the set with an iterator and call a continuation function on it
     pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
     where
-        T: PartialOrd,
         I: IntoIterator<Item = T>,
-        F: FnOnce(&Set<T>) -> R,
+        F: FnOnce(&SetBy<T, C>) -> R,
     {
         let mut iter = iter.into_iter();
         if let Some(item) = iter.next() {
-            self.insert(item, |list| list.extend(iter, then))
+            self.insert(item, |set| set.extend(iter, then))
         } else {
             then(self)
         }
     }
 }
 
-/// An iterator over the key/item pairs of a [`Set`]
-pub struct Iter<'a, T> {
-    node: Option<&'a SetNode<'a, T>>,
+impl<'a, T, C> Clone for SetBy<'a, T, C>
+where
+    C: Copy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-impl<'a, T> SetNode<'a, T> {
-    fn contains_child(&self, child: &Self) -> bool {
-        self.left.map_or(false, |node| ptr::eq(node, child))
-            || self.right.map_or(false, |node| ptr::eq(node, child))
-            || self.left.map_or(false, |node| node.contains_child(child))
-            || self.right.map_or(false, |node| node.contains_child(child))
+impl<'a, T, C> Copy for SetBy<'a, T, C> where C: Copy {}
+
+impl<'a, T, C> fmt::Debug for SetBy<'a, T, C>
+where
+    T: fmt::Debug,
+    C: Fn(&T, &T) -> Ordering + Copy,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl<'a, T> Iterator for Iter<'a, T>
+/// An iterator over the items of a [`Set`] that are not shadowed
+///
+/// This is returned by [`Set::iter_unique`].
+pub struct IterUnique<'a, T> {
+    items: Iter<'a, T>,
+    set: Set<'a, T>,
+}
+
+impl<'a, T> Iterator for IterUnique<'a, T>
 where
     T: PartialOrd,
 {
     type Item = &'a T;
     fn next(&mut self) -> Option<Self::Item> {
-        let node = self.node?;
-        let res = &node.item;
-        self.node = match (node.left, node.right) {
-            (None, None) => None,
-            (None, Some(right)) => Some(right),
-            (Some(left), None) => Some(left),
-            (Some(left), Some(right)) => Some(if left.contains_child(right) {
-                left
-            } else {
-                right
-            }),
-        };
-        Some(res)
+        let set = self.set;
+        self.items
+            .find(|&item| set.get(item).is_some_and(|live| ptr::eq(live, item)))
+    }
+}
+
+/// An iterator over the items in either of two [`Set`]s
+///
+/// This is returned by [`Set::union`].
+pub struct Union<'a, T> {
+    first: Set<'a, T>,
+    first_items: IterUnique<'a, T>,
+    second_items: IterUnique<'a, T>,
+}
+
+impl<'a, T> Union<'a, T>
+where
+    T: PartialOrd,
+{
+    /// Check if either set contains an item
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd,
+    {
+        self.first.contains(item) || self.second_items.set.contains(item)
+    }
+}
+
+impl<'a, T> Iterator for Union<'a, T>
+where
+    T: PartialOrd,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.first_items.next() {
+            return Some(item);
+        }
+        let first = self.first;
+        self.second_items.find(|item| !first.contains(*item))
+    }
+}
+
+/// An iterator over the items in both of two [`Set`]s
+///
+/// This is returned by [`Set::intersection`].
+pub struct Intersection<'a, T> {
+    items: IterUnique<'a, T>,
+    other: Set<'a, T>,
+}
+
+impl<'a, T> Iterator for Intersection<'a, T>
+where
+    T: PartialOrd,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.items.find(|item| other.contains(*item))
+    }
+}
+
+/// An iterator over the items in one [`Set`] but not another
+///
+/// This is returned by [`Set::difference`].
+pub struct Difference<'a, T> {
+    items: IterUnique<'a, T>,
+    other: Set<'a, T>,
+}
+
+impl<'a, T> Iterator for Difference<'a, T>
+where
+    T: PartialOrd,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.items.find(|item| !other.contains(*item))
+    }
+}
+
+/// An iterator over the items in exactly one of two [`Set`]s
+///
+/// This is returned by [`Set::symmetric_difference`].
+pub struct SymmetricDifference<'a, T> {
+    first: Difference<'a, T>,
+    second: Difference<'a, T>,
+}
+
+impl<'a, T> Iterator for SymmetricDifference<'a, T>
+where
+    T: PartialOrd,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.first.next().or_else(|| self.second.next())
+    }
+}
+
+/// A view of the items in a [`Set`] for which a predicate returns true
+///
+/// This is returned by [`Set::retain_view`].
+pub struct RetainView<'a, T, P> {
+    set: Set<'a, T>,
+    pred: P,
+}
+
+impl<'a, T, P> RetainView<'a, T, P>
+where
+    T: PartialOrd,
+    P: Fn(&T) -> bool,
+{
+    /// Check if the view is empty
+    ///
+    /// This stops at the first item that is retained.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+    /// Get the number of items in the view
+    ///
+    /// This is an **O(n)** operation.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+    /// Check if the view contains an item
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd,
+    {
+        self.get(item).is_some()
+    }
+    /// Get an item in the view that is equal to the item
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get<Q>(&self, item: &Q) -> Option<&'a T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd,
+    {
+        Some(&self.set.get_node(item)?.item).filter(|item| (self.pred)(item))
+    }
+    /// Get an iterator over the items of the view
+    ///
+    /// The iterator yields items in the opposite order of their insertion.
+    pub fn iter(&self) -> RetainIter<'a, '_, T, P> {
+        RetainIter {
+            items: self.set.iter(),
+            pred: &self.pred,
+        }
+    }
+}
+
+impl<'a, T, P> fmt::Debug for RetainView<'a, T, P>
+where
+    T: PartialOrd + fmt::Debug,
+    P: Fn(&T) -> bool,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the items of a [`RetainView`]
+pub struct RetainIter<'a, 'v, T, P> {
+    items: Iter<'a, T>,
+    pred: &'v P,
+}
+
+impl<'a, 'v, T, P> Iterator for RetainIter<'a, 'v, T, P>
+where
+    P: Fn(&T) -> bool,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let pred = self.pred;
+        self.items.find(|item| pred(item))
+    }
+}
+
+/// An iterator over the items of a [`Set`]
+pub struct Iter<'a, T> {
+    node: Option<&'a SetNode<'a, T>>,
+    /// The set to check entries against and how to check them, if some have been removed
+    live: Option<(Set<'a, T>, LiveCheck<'a, T>)>,
+    /// The number of items left to yield
+    len: usize,
+}
+
+type LiveCheck<'a, T> = fn(&Set<'a, T>, &SetNode<'a, T>) -> bool;
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        loop {
+            let node = self.node?;
+            self.node = node.older;
+            if node.count == 0 {
+                continue;
+            }
+            let live = match self.live {
+                Some((set, is_live)) => is_live(&set, node),
+                None => true,
+            };
+            if live {
+                self.len -= 1;
+                return Some(&node.item);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            node: self.node,
+            live: self.live,
+            len: self.len,
+        }
     }
 }
 
@@ -326,16 +2075,19 @@ where
 
 impl<'a, T> Default for Set<'a, T> {
     fn default() -> Self {
-        Set { head: None, len: 0 }
+        Set {
+            head: None,
+            root: None,
+            len: 0,
+            sorted: 0,
+            hidden: 0,
+        }
     }
 }
 
 impl<'a, T> Clone for Set<'a, T> {
     fn clone(&self) -> Self {
-        Set {
-            head: self.head,
-            len: self.len,
-        }
+        *self
     }
 }
 
@@ -362,6 +2114,34 @@ where
 
 impl<'a, T> Eq for Set<'a, T> where T: PartialOrd + Eq {}
 
+/// Sets are ordered lexicographically by their items that are not shadowed, in ascending order
+impl<'a, T> PartialOrd for Set<'a, T>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.sorted_items().partial_cmp(other.sorted_items())
+    }
+}
+
+/// Only items that are not shadowed are hashed, in ascending order
+impl<'a, T> Hash for Set<'a, T>
+where
+    T: Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        let mut count = 0;
+        for item in self.sorted_items() {
+            item.hash(state);
+            count += 1;
+        }
+        state.write_usize(count);
+    }
+}
+
 impl<'a, T> fmt::Debug for Set<'a, T>
 where
     T: PartialOrd + fmt::Debug,
@@ -370,3 +2150,322 @@ where
         f.debug_list().entries(self.iter()).finish()
     }
 }
+
+/// Sets are displayed as `{a, b, c}`, with items that are not shadowed in ascending order
+///
+/// # Example
+/// ```
+/// use nolloc::Set;
+///
+/// Set::collect([3, 1, 2, 1], |set| assert_eq!(set.to_string(), "{1, 2, 3}"));
+/// ```
+impl<'a, T> fmt::Display for Set<'a, T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, item) in self.sorted_items().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// An iterator over the items of a [`Set`] that start with a prefix
+///
+/// This is returned by [`Set::iter_prefix`].
+pub struct IterPrefix<'a, 'p, T> {
+    nodes: InOrder<'a, SetNode<'a, T>>,
+    set: Set<'a, T>,
+    prefix: &'p str,
+}
+
+impl<'a, 'p, T> Iterator for IterPrefix<'a, 'p, T>
+where
+    T: Borrow<str>,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.nodes.next()?;
+            if !node.item.borrow().starts_with(self.prefix) {
+                // Every later item is past the prefix too
+                self.nodes = InOrder::new(None);
+                return None;
+            }
+            if let Some(node) = self.set.visible(node) {
+                return Some(&node.item);
+            }
+        }
+    }
+}
+
+impl<'a, 'p, T> FusedIterator for IterPrefix<'a, 'p, T> where T: Borrow<str> {}
+
+/// An iterator over the items of a [`Set`] that were inserted more than once
+///
+/// This is returned by [`Set::iter_duplicates`].
+pub struct IterDuplicates<'a, T> {
+    nodes: InOrder<'a, SetNode<'a, T>>,
+    set: Set<'a, T>,
+}
+
+impl<'a, T> Iterator for IterDuplicates<'a, T> {
+    type Item = (&'a T, usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        let node = self
+            .nodes
+            .find_map(|node| set.visible(node).filter(|node| node.count > 1))?;
+        Some((&node.item, node.count))
+    }
+}
+
+/// An iterator over the distinct items of a [`Set`] and their counts
+///
+/// This is returned by [`Set::iter_counts`].
+pub struct IterCounts<'a, T> {
+    nodes: InOrder<'a, SetNode<'a, T>>,
+    set: Set<'a, T>,
+}
+
+impl<'a, T> Iterator for IterCounts<'a, T> {
+    type Item = (&'a T, usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        let node = self.nodes.find_map(|node| set.visible(node))?;
+        Some((&node.item, node.count))
+    }
+}
+
+/// An iterator over the items of a [`Set`] that are not shadowed, in ascending order
+struct SortedItems<'a, T> {
+    nodes: InOrder<'a, SetNode<'a, T>>,
+    set: Set<'a, T>,
+    /// The number of items left to yield
+    len: usize,
+}
+
+impl<'a, T> Iterator for SortedItems<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let set = self.set;
+        let node = self.nodes.find_map(|node| set.visible(node))?;
+        self.len -= 1;
+        Some(&node.item)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for SortedItems<'a, T> {}
+
+#[test]
+fn set_rank() {
+    Set::collect([0, 2, 4, 2], |set| {
+        assert!(set.contains(&0));
+        assert_eq!(set.rank(&4), 2);
+        assert_eq!(set.nth(1), Some(&2));
+    });
+    Set::collect(0..200, |set| {
+        set.debug_validate();
+        for i in 0..200 {
+            assert_eq!(set.rank(&i), i);
+            assert_eq!(set.nth(i), Some(&i));
+        }
+    });
+}
+
+#[test]
+fn set_remove() {
+    Set::collect(0..100, |set| {
+        fn remove_evens(set: &Set<i32>, i: i32) {
+            if i == 100 {
+                set.debug_validate();
+                assert_eq!(set.len(), 50);
+                assert!(set.iter().all(|i| i % 2 == 1));
+                assert_eq!(set.nth(10), Some(&21));
+            } else {
+                set.remove(i, |set| remove_evens(set, i + 2))
+            }
+        }
+        remove_evens(set, 0);
+    });
+}
+
+#[test]
+fn set_iter_len() {
+    Set::collect([1, 2, 3, 2, 4], |set| {
+        let mut iter = set.iter();
+        assert_eq!(iter.len(), 5);
+        iter.next();
+        assert_eq!(iter.len(), 4);
+        set.remove(2, |set| {
+            assert_eq!(set.iter().len(), 3);
+            set.replace(4, |set, _| {
+                assert_eq!(set.iter().len(), 3);
+                assert_eq!(set.iter().count(), 3);
+            });
+        });
+    });
+}
+
+#[test]
+fn set_pop() {
+    fn drain(set: &Set<u32>, expected: u32) {
+        set.pop_max(|rest, max| match max {
+            Some(&max) => {
+                assert_eq!(max, expected);
+                rest.debug_validate();
+                drain(rest, expected - 1)
+            }
+            None => assert_eq!(expected, 0),
+        })
+    }
+    Set::collect([5, 3, 1, 4, 2, 5], |set| drain(set, 5));
+}
+
+#[test]
+fn set_from_array() {
+    let mut items = [0; 100];
+    for (i, item) in items.iter_mut().enumerate() {
+        *item = (i * 37) % 50;
+    }
+    Set::from_array(items, |set| {
+        set.debug_validate();
+        assert_eq!(set.len(), 100);
+        assert_eq!(tree::size(set.root), 50);
+        set.remove(7, |set| {
+            assert_eq!(set.len(), 98);
+            assert_eq!(set.iter().filter(|&&i| i == 8).count(), 2);
+        });
+    });
+    // Rest removes one item at a time, even from a run of equal items
+    Set::from_array([3, 1, 3, 2, 3], |set| {
+        let rest = set.rest();
+        assert_eq!((rest.len(), rest.distinct_len(), rest.count(&3)), (4, 3, 2));
+        assert!(rest.iter().eq(&[3, 3, 2, 1]));
+        let rest = rest.rest().rest();
+        assert_eq!(
+            (rest.len(), rest.distinct_len(), rest.max()),
+            (2, 2, Some(&2))
+        );
+        assert!(!rest.contains(&3) && rest.iter_counts().eq([(&1, 1), (&2, 1)]));
+        set.rest().insert(3, |set| {
+            assert_eq!(set.count(&3), 3);
+            set.remove(3, |set| assert!(set.iter().eq(&[2, 1])));
+        });
+        rest.insert(3, |set| {
+            set.debug_validate();
+            assert_eq!((set.count(&3), set.distinct_len()), (1, 3));
+            assert!(set.iter().eq(&[3, 2, 1]));
+        });
+    });
+}
+
+#[test]
+fn set_ord_hash() {
+    // FNV-1a, kept local so the test does not depend on other modules
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        struct Fnv(u64);
+        impl Hasher for Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+            fn write(&mut self, bytes: &[u8]) {
+                for &byte in bytes {
+                    self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3);
+                }
+            }
+        }
+        let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    Set::collect([3, 1, 2, 1], |a| {
+        Set::collect([1, 2, 3], |b| {
+            assert_eq!(a.cmp_items(b), Ordering::Equal);
+            assert_eq!(hash(a), hash(b));
+            b.insert(0, |c| assert!(*c < *b));
+            b.remove(1, |c| assert!(*c > *b));
+        });
+    });
+}
+
+#[test]
+fn set_collect_sorted() {
+    for len in 0..40 {
+        Set::collect_sorted(0..len, |set| {
+            set.debug_validate();
+            assert_eq!(set.len(), len);
+            assert_eq!(set.distinct_len(), len);
+            assert!(tree::height(set.root) as u32 <= usize::BITS - len.leading_zeros());
+            assert!(set.iter().copied().eq((0..len).rev()));
+        });
+    }
+    // Rest removes one item at a time, greatest first
+    Set::collect_sorted(0..10, |set| {
+        let rest = set.rest().rest();
+        assert_eq!((rest.len(), rest.distinct_len()), (8, 8));
+        assert!(!rest.contains(&8) && rest.contains(&7));
+        assert_eq!(
+            (rest.max(), rest.nth(7), rest.nth(8)),
+            (Some(&7), Some(&7), None)
+        );
+        assert_eq!(
+            (rest.rank(&20), rest.get_le(&20), rest.get_ge(&8)),
+            (8, Some(&7), None)
+        );
+        assert!(rest.iter().copied().eq((0..8).rev()));
+        assert!(rest.iter_counts().map(|(&i, _)| i).eq(0..8));
+        rest.insert(9, |set| {
+            set.debug_validate();
+            assert_eq!((set.len(), set.distinct_len()), (9, 9));
+            assert!(set.contains(&9) && !set.contains(&8));
+            assert_eq!(
+                (set.nth(8), set.get_lt(&9), set.rank(&9)),
+                (Some(&9), Some(&7), 8)
+            );
+            assert_eq!(set.rest(), rest);
+            set.pop_max(|set, max| {
+                assert_eq!(max, Some(&9));
+                assert_eq!(set.max(), Some(&7));
+            });
+        });
+        rest.insert(8, |set| {
+            assert_eq!((set.distinct_len(), set.count(&8)), (9, 1));
+            assert_eq!((set.max(), set.get_gt(&7)), (Some(&8), Some(&8)));
+        });
+        assert_eq!(set.rest().rest().rest().rest().len(), 6);
+    });
+}
+
+#[test]
+fn set_extend() {
+    Set::collect((0..2000).map(|i| i % 300), |set| {
+        set.debug_validate();
+        assert_eq!(set.len(), 2000);
+        assert_eq!(set.distinct_len(), 300);
+        assert_eq!(set.iter_duplicates().map(|(_, n)| n).sum::<usize>(), 2000);
+        assert_eq!(set.rest().len(), 1999);
+        set.extend(0..10, |set| assert_eq!(set.iter().len(), 2010));
+    });
+}
+
+#[test]
+fn set_sorted_insert() {
+    Set::collect(0..300, |set| {
+        set.debug_validate();
+        assert!(tree::height(set.root) <= 12);
+    });
+    Set::collect((0..300).rev(), |set| {
+        set.debug_validate();
+        assert!(tree::height(set.root) <= 12);
+    });
+}
@@ -0,0 +1,250 @@
+//! A pool of slots with a fixed capacity that is stored inline
+//!
+//! Free slots are kept in a list threaded through the slots themselves, so
+//! inserting and removing are **O(1)**. Each slot counts how many times it has
+//! been freed, and a [`SlabKey`] remembers that count, so a key to a removed
+//! item does not find an item inserted into the same slot later.
+
+use core::{
+    fmt,
+    iter::FusedIterator,
+    ops::{Index, IndexMut},
+};
+
+/// A pool of up to `N` items with stable keys that is stored inline
+///
+/// # Example
+/// ```
+/// use nolloc::Slab;
+///
+/// let mut requests = Slab::<&str, 4>::new();
+///
+/// let ping = requests.insert("ping").unwrap();
+/// let read = requests.insert("read").unwrap();
+/// assert_eq!(requests.remove(ping), Some("ping"));
+///
+/// // The freed slot is reused, but the old key does not find the new item
+/// let write = requests.insert("write").unwrap();
+/// assert_eq!(requests.get(ping), None);
+/// assert_eq!(requests[write], "write");
+/// assert_eq!(requests[read], "read");
+/// ```
+#[derive(Clone, Copy)]
+pub struct Slab<T, const N: usize> {
+    slots: [Slot<T>; N],
+    /// The first free slot, or `N` if there are none
+    free: usize,
+    len: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Slot<T> {
+    item: Option<T>,
+    /// The number of times the slot has been freed
+    generation: u32,
+    /// The next free slot, if this one is free
+    next_free: usize,
+}
+
+/// A key to an item in a [`Slab`]
+///
+/// A key only finds the item it was returned for. Once that item is removed,
+/// the key finds nothing, even if its slot is reused.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SlabKey {
+    index: usize,
+    generation: u32,
+}
+
+impl SlabKey {
+    /// Get the index of the key's slot
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<T, const N: usize> Slab<T, N> {
+    /// Create a new empty slab
+    pub fn new() -> Self {
+        Slab {
+            slots: core::array::from_fn(|i| Slot {
+                item: None,
+                generation: 0,
+                next_free: i + 1,
+            }),
+            free: 0,
+            len: 0,
+        }
+    }
+    /// Check if the slab is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the slab is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the number of items in the slab
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the slab's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the slot a key refers to, if its item has not been removed
+    fn slot(&self, key: SlabKey) -> Option<&Slot<T>> {
+        self.slots
+            .get(key.index)
+            .filter(|slot| slot.generation == key.generation && slot.item.is_some())
+    }
+    /// Check if a key's item is in the slab
+    pub fn contains(&self, key: SlabKey) -> bool {
+        self.slot(key).is_some()
+    }
+    /// Get the item for a key
+    pub fn get(&self, key: SlabKey) -> Option<&T> {
+        self.slot(key)?.item.as_ref()
+    }
+    /// Get a mutable reference to the item for a key
+    pub fn get_mut(&mut self, key: SlabKey) -> Option<&mut T> {
+        self.slot(key)?;
+        self.slots[key.index].item.as_mut()
+    }
+    /// Insert an item into a free slot and get its key
+    ///
+    /// If the slab is full, the item is returned as an error.
+    pub fn insert(&mut self, item: T) -> Result<SlabKey, T> {
+        let index = self.free;
+        let slot = match self.slots.get_mut(index) {
+            Some(slot) => slot,
+            None => return Err(item),
+        };
+        slot.item = Some(item);
+        self.free = slot.next_free;
+        self.len += 1;
+        Ok(SlabKey {
+            index,
+            generation: slot.generation,
+        })
+    }
+    /// Remove the item for a key and return it
+    ///
+    /// The key and any copies of it no longer find an item.
+    pub fn remove(&mut self, key: SlabKey) -> Option<T> {
+        self.slot(key)?;
+        self.release(key.index)
+    }
+    /// Free a slot and take its item
+    fn release(&mut self, index: usize) -> Option<T> {
+        let slot = &mut self.slots[index];
+        let item = slot.item.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.next_free = self.free;
+        self.free = index;
+        self.len -= 1;
+        Some(item)
+    }
+    /// Remove all items from the slab
+    ///
+    /// Keys to the removed items no longer find an item.
+    pub fn clear(&mut self) {
+        for index in 0..N {
+            self.release(index);
+        }
+    }
+    /// Get an iterator over the keys and items of the slab in slot order
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.slots.iter().enumerate(),
+            len: self.len,
+        }
+    }
+}
+
+/// An iterator over the keys and items of a [`Slab`]
+///
+/// This is returned by [`Slab::iter`].
+pub struct Iter<'a, T> {
+    slots: core::iter::Enumerate<core::slice::Iter<'a, Slot<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (SlabKey, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, slot, item) = self
+            .slots
+            .find_map(|(index, slot)| Some((index, slot, slot.item.as_ref()?)))?;
+        self.len -= 1;
+        let key = SlabKey {
+            index,
+            generation: slot.generation,
+        };
+        Some((key, item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T, const N: usize> IntoIterator for &'a Slab<T, N> {
+    type Item = (SlabKey, &'a T);
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Index<SlabKey> for Slab<T, N> {
+    type Output = T;
+    fn index(&self, key: SlabKey) -> &T {
+        self.get(key).expect("slab key has no item")
+    }
+}
+
+impl<T, const N: usize> IndexMut<SlabKey> for Slab<T, N> {
+    fn index_mut(&mut self, key: SlabKey) -> &mut T {
+        self.get_mut(key).expect("slab key has no item")
+    }
+}
+
+impl<T, const N: usize> Default for Slab<T, N> {
+    fn default() -> Self {
+        Slab::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Slab<T, N>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, item)| (key.index, item)))
+            .finish()
+    }
+}
+
+#[test]
+fn slab_reuses_slots() {
+    let mut slab = Slab::<u32, 3>::new();
+    let keys = [0, 1, 2].map(|i| slab.insert(i * 10).unwrap());
+    assert!(slab.is_full());
+    assert_eq!(slab.insert(30), Err(30));
+    assert_eq!(slab.remove(keys[1]), Some(10));
+    assert_eq!(slab.remove(keys[1]), None);
+    let reused = slab.insert(40).unwrap();
+    assert_eq!(reused.index(), 1);
+    assert_ne!(reused, keys[1]);
+    slab[reused] += 1;
+    assert!(slab.iter().map(|(_, &item)| item).eq([0, 41, 20]));
+    slab.clear();
+    assert!(slab.is_empty());
+    assert!(!slab.contains(keys[0]) && !slab.contains(reused));
+    assert_eq!(Slab::<u8, 0>::new().insert(1), Err(1));
+}
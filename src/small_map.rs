@@ -0,0 +1,438 @@
+//! A mutable map with a fixed capacity that is stored inline
+//!
+//! A [`SmallMap`] keeps its entries in an array sorted by key, so lookups are
+//! binary searches and inserting or removing shifts the later entries.
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    iter::{FromIterator, FusedIterator},
+    ops::{Index, IndexMut},
+    slice,
+};
+
+use crate::tree;
+
+/// A mutable key-value map with a fixed capacity of `N` entries that is
+/// stored inline
+///
+/// Unlike [`Map`](crate::Map), a `SmallMap` owns its entries, so it can be
+/// mutated in place, entries can be removed, and it can be returned out of a
+/// function by value. It is best suited to small maps, since inserting and
+/// removing are **O(N)**.
+///
+/// # Example
+/// ```
+/// use nolloc::SmallMap;
+///
+/// fn tally(words: &str) -> SmallMap<&str, u32, 8> {
+///     let mut counts = SmallMap::new();
+///     for word in words.split_whitespace() {
+///         match counts.get_mut(word) {
+///             Some(count) => *count += 1,
+///             None => {
+///                 counts.insert(word, 1).unwrap();
+///             }
+///         }
+///     }
+///     counts
+/// }
+///
+/// let mut counts = tally("to be or not to be");
+/// assert_eq!(counts["to"], 2);
+/// assert_eq!(counts.remove("or"), Some(1));
+/// assert!(counts.keys().eq(&["be", "not", "to"]));
+/// ```
+#[derive(Clone, Copy)]
+pub struct SmallMap<K, V, const N: usize> {
+    /// The entries in ascending key order, of which the first `len` are `Some`
+    entries: [Option<(K, V)>; N],
+    len: usize,
+}
+
+impl<K, V, const N: usize> SmallMap<K, V, N> {
+    /// Create a new empty map
+    pub fn new() -> Self {
+        SmallMap {
+            entries: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Check if the map is full
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+    /// Get the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the map's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the entry with the minimum key in the map
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+    /// Get the entry with the maximum key in the map
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+    /// Get an iterator over the entries of the map in ascending key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.entries[..self.len].iter(),
+        }
+    }
+    /// Get an iterator over the entries of the map in ascending key order,
+    /// with mutable references to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            entries: self.entries[..self.len].iter_mut(),
+        }
+    }
+    /// Get an iterator over the keys of the map in ascending order
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.iter().map(|(key, _)| key)
+    }
+    /// Get an iterator over the values of the map in ascending key order
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.iter().map(|(_, value)| value)
+    }
+    /// Get an iterator over mutable references to the values of the map in
+    /// ascending key order
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.iter_mut().map(|(_, value)| value)
+    }
+    /// Remove the entry at an index and return it
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let entry = self.entries[index].take().expect("entry is in the map");
+        self.entries[index..self.len].rotate_left(1);
+        self.len -= 1;
+        entry
+    }
+    /// Keep only the entries for which a predicate returns true
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let keep = match &mut self.entries[i] {
+                Some((key, value)) => f(key, value),
+                None => false,
+            };
+            if keep {
+                self.entries.swap(kept, i);
+                kept += 1;
+            } else {
+                self.entries[i] = None;
+            }
+        }
+        self.len = kept;
+    }
+    /// Remove all entries from the map
+    pub fn clear(&mut self) {
+        for entry in &mut self.entries[..self.len] {
+            *entry = None;
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, V, const N: usize> SmallMap<K, V, N>
+where
+    K: PartialOrd,
+{
+    /// Find the index of the entry for a key, or the index it would be inserted at
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.entries[..self.len].binary_search_by(|entry| match entry {
+            Some((k, _)) => tree::compare(k.borrow(), key),
+            None => Ordering::Greater,
+        })
+    }
+    /// Check if the map contains a key
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+    /// Get the value corresponding to the key
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(self.get_key_value(key)?.1)
+    }
+    /// Get the map's own key equal to the key and its value
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let (key, value) = self.entries[self.search(key).ok()?].as_ref()?;
+        Some((key, value))
+    }
+    /// Get a mutable reference to the value corresponding to the key
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        Some(&mut self.entries[index].as_mut()?.1)
+    }
+    /// Insert a key-value pair into the map
+    ///
+    /// If the key is already in the map, its value is replaced and the old
+    /// value is returned. If the map is full and does not contain the key,
+    /// the pair is returned as an error. This is an **O(N)** operation.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, (K, V)> {
+        match self.search(&key) {
+            Ok(index) => {
+                let entry = self.entries[index].as_mut().expect("entry is in the map");
+                Ok(Some(core::mem::replace(&mut entry.1, value)))
+            }
+            Err(_) if self.is_full() => Err((key, value)),
+            Err(index) => {
+                self.entries[self.len] = Some((key, value));
+                self.entries[index..=self.len].rotate_right(1);
+                self.len += 1;
+                Ok(None)
+            }
+        }
+    }
+    /// Insert the pairs of an iterator into the map
+    ///
+    /// If the map fills up, the first pair with a new key that did not fit is
+    /// returned as an error.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), (K, V)>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        iter.into_iter()
+            .try_for_each(|(key, value)| self.insert(key, value).map(drop))
+    }
+    /// Remove the entry for a key and return its value
+    ///
+    /// This is an **O(N)** operation.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(self.remove_entry(key)?.1)
+    }
+    /// Remove the entry for a key and return it
+    ///
+    /// This is an **O(N)** operation.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        Some(self.remove_at(index))
+    }
+}
+
+/// An iterator over the entries of a [`SmallMap`] in ascending key order
+///
+/// This is returned by [`SmallMap::iter`].
+pub struct Iter<'a, K, V> {
+    entries: slice::Iter<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next()?.as_ref()?;
+        Some((key, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next_back()?.as_ref()?;
+        Some((key, value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+/// An iterator over the entries of a [`SmallMap`] in ascending key order,
+/// with mutable references to the values
+///
+/// This is returned by [`SmallMap::iter_mut`].
+pub struct IterMut<'a, K, V> {
+    entries: slice::IterMut<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next()?.as_mut()?;
+        Some((key, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next_back()?.as_mut()?;
+        Some((key, value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for IterMut<'a, K, V> {}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a SmallMap<K, V, N> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a mut SmallMap<K, V, N> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, const N: usize> FromIterator<(K, V)> for SmallMap<K, V, N>
+where
+    K: PartialOrd,
+{
+    /// Collect the pairs of an iterator into a map
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than `N` distinct keys.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = SmallMap::new();
+        if map.try_extend(iter).is_err() {
+            panic!("iterator has more than {} distinct keys", N);
+        }
+        map
+    }
+}
+
+impl<K, V, Q, const N: usize> Index<&Q> for SmallMap<K, V, N>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    type Output = V;
+    #[track_caller]
+    fn index(&self, index: &Q) -> &Self::Output {
+        self.get(index).expect("no entry found for key")
+    }
+}
+
+impl<K, V, Q, const N: usize> IndexMut<&Q> for SmallMap<K, V, N>
+where
+    K: PartialOrd + Borrow<Q>,
+    Q: PartialOrd + ?Sized,
+{
+    #[track_caller]
+    fn index_mut(&mut self, index: &Q) -> &mut Self::Output {
+        self.get_mut(index).expect("no entry found for key")
+    }
+}
+
+impl<K, V, const N: usize> Default for SmallMap<K, V, N> {
+    fn default() -> Self {
+        SmallMap::new()
+    }
+}
+
+impl<K, V, const N: usize, const M: usize> PartialEq<SmallMap<K, V, M>> for SmallMap<K, V, N>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &SmallMap<K, V, M>) -> bool {
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<K, V, const N: usize> Eq for SmallMap<K, V, N>
+where
+    K: Eq,
+    V: Eq,
+{
+}
+
+impl<K, V, const N: usize> fmt::Debug for SmallMap<K, V, N>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn small_map_edits() {
+    let mut map: SmallMap<u32, char, 6> = [(5, 'e'), (1, 'a'), (3, 'c')].iter().copied().collect();
+    assert_eq!(map.insert(3, 'C'), Ok(Some('c')));
+    assert_eq!(map.insert(2, 'b'), Ok(None));
+    assert!(map.keys().copied().eq([1, 2, 3, 5]));
+    for (_, value) in &mut map {
+        *value = value.to_ascii_uppercase();
+    }
+    assert_eq!(map.remove(&1), Some('A'));
+    assert_eq!(map.remove(&1), None);
+    assert_eq!(map.last(), Some((&5, &'E')));
+    map.try_extend([(0, 'z'), (4, 'd'), (6, 'f')]).unwrap();
+    assert!(map.is_full());
+    assert_eq!(map.insert(7, 'g'), Err((7, 'g')));
+    assert_eq!(map.insert(6, 'F'), Ok(Some('f')));
+    map.retain(|key, _| key % 2 == 0);
+    assert!(map
+        .iter()
+        .eq([(&0, &'z'), (&2, &'B'), (&4, &'d'), (&6, &'F')]));
+    map.clear();
+    assert!(map.is_empty() && map.get(&2).is_none());
+}
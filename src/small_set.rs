@@ -0,0 +1,342 @@
+//! A mutable set with a fixed capacity that is stored inline
+//!
+//! A [`SmallSet`] is a [`SmallMap`] with no values, so its items are kept in
+//! ascending order.
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    iter::{FromIterator, FusedIterator},
+};
+
+use crate::{small_map, Set, SmallMap};
+
+/// A mutable set with a fixed capacity of `N` items that is stored inline
+///
+/// Unlike [`Set`], a `SmallSet` owns its items, so it can be mutated in place,
+/// items can be removed, and it can be returned out of a function by value.
+/// It is best suited to small sets, since inserting and removing are **O(N)**.
+///
+/// # Example
+/// ```
+/// use nolloc::SmallSet;
+///
+/// fn vowels(word: &str) -> SmallSet<char, 5> {
+///     word.chars().filter(|c| "aeiou".contains(*c)).collect()
+/// }
+///
+/// let a = vowels("education");
+/// let b = vowels("mountain");
+/// assert_eq!(a.len(), 5);
+/// assert!(b.is_subset(&a));
+/// assert!(a.difference(&b).iter().eq(&['e']));
+/// ```
+#[derive(Clone, Copy)]
+pub struct SmallSet<T, const N: usize> {
+    map: SmallMap<T, (), N>,
+}
+
+impl<T, const N: usize> SmallSet<T, N> {
+    /// Create a new empty set
+    pub fn new() -> Self {
+        SmallSet {
+            map: SmallMap::new(),
+        }
+    }
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    /// Check if the set is full
+    pub fn is_full(&self) -> bool {
+        self.map.is_full()
+    }
+    /// Get the number of items in the set
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    /// Get the set's capacity
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Get the least item in the set
+    pub fn first(&self) -> Option<&T> {
+        self.iter().next()
+    }
+    /// Get the greatest item in the set
+    pub fn last(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+    /// Get an iterator over the items of the set in ascending order
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: self.map.iter(),
+        }
+    }
+    /// Keep only the items for which a predicate returns true
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.map.retain(|item, _| f(item))
+    }
+    /// Remove all items from the set
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<T, const N: usize> SmallSet<T, N>
+where
+    T: PartialOrd,
+{
+    /// Check if the set contains an item
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.map.contains_key(item)
+    }
+    /// Get the set's own item equal to an item
+    ///
+    /// This is an **O(logN)** operation.
+    pub fn get<Q>(&self, item: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(self.map.get_key_value(item)?.0)
+    }
+    /// Insert an item into the set
+    ///
+    /// Returns whether the item was newly inserted. If the set is full and
+    /// does not contain the item, the item is returned as an error.
+    /// This is an **O(N)** operation.
+    pub fn insert(&mut self, item: T) -> Result<bool, T> {
+        match self.map.insert(item, ()) {
+            Ok(old) => Ok(old.is_none()),
+            Err((item, ())) => Err(item),
+        }
+    }
+    /// Insert the items of an iterator into the set
+    ///
+    /// If the set fills up, the first new item that did not fit is returned
+    /// as an error.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        iter.into_iter()
+            .try_for_each(|item| self.insert(item).map(drop))
+    }
+    /// Remove an item from the set and return whether it was present
+    ///
+    /// This is an **O(N)** operation.
+    pub fn remove<Q>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.map.remove(item).is_some()
+    }
+    /// Remove the set's own item equal to an item and return it
+    ///
+    /// This is an **O(N)** operation.
+    pub fn take<Q>(&mut self, item: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        Some(self.map.remove_entry(item)?.0)
+    }
+    /// Check if every item in this set is also in another
+    ///
+    /// This is an **O(NlogM)** operation.
+    pub fn is_subset<const M: usize>(&self, other: &SmallSet<T, M>) -> bool {
+        self.len() <= other.len() && self.iter().all(|item| other.contains(item))
+    }
+    /// Check if every item in another set is also in this one
+    ///
+    /// This is an **O(MlogN)** operation.
+    pub fn is_superset<const M: usize>(&self, other: &SmallSet<T, M>) -> bool {
+        other.is_subset(self)
+    }
+    /// Check if this set and another have no items in common
+    ///
+    /// This is an **O(NlogM)** operation.
+    pub fn is_disjoint<const M: usize>(&self, other: &SmallSet<T, M>) -> bool {
+        !self.iter().any(|item| other.contains(item))
+    }
+    /// Get a new set with the items in either this set or another
+    ///
+    /// If the items do not all fit in `N`, the first one that did not fit is
+    /// returned as an error.
+    pub fn union<const M: usize>(&self, other: &SmallSet<T, M>) -> Result<Self, T>
+    where
+        T: Clone,
+    {
+        let mut union = self.clone();
+        union.try_extend(other.iter().cloned())?;
+        Ok(union)
+    }
+    /// Get a new set with the items in both this set and another
+    pub fn intersection<const M: usize>(&self, other: &SmallSet<T, M>) -> Self
+    where
+        T: Clone,
+    {
+        let mut intersection = self.clone();
+        intersection.retain(|item| other.contains(item));
+        intersection
+    }
+    /// Get a new set with the items in this set that are not in another
+    pub fn difference<const M: usize>(&self, other: &SmallSet<T, M>) -> Self
+    where
+        T: Clone,
+    {
+        let mut difference = self.clone();
+        difference.retain(|item| !other.contains(item));
+        difference
+    }
+    /// Check if every item in this set is in a persistent [`Set`]
+    ///
+    /// This is an **O(Nlogm)** operation.
+    pub fn is_subset_of_set(&self, set: &Set<T>) -> bool {
+        self.iter().all(|item| set.contains(item))
+    }
+    /// Insert the items of a persistent [`Set`] into this one
+    ///
+    /// If the set fills up, the first new item that did not fit is returned
+    /// as an error.
+    pub fn extend_from_set(&mut self, set: &Set<T>) -> Result<(), T>
+    where
+        T: Clone,
+    {
+        self.try_extend(set.iter_unique().cloned())
+    }
+    /// Remove the items that are not in a persistent [`Set`]
+    ///
+    /// This is an **O(Nlogm)** operation.
+    pub fn intersect_with_set(&mut self, set: &Set<T>) {
+        self.retain(|item| set.contains(item))
+    }
+    /// Remove the items that are in a persistent [`Set`]
+    ///
+    /// This is an **O(Nlogm)** operation.
+    pub fn subtract_set(&mut self, set: &Set<T>) {
+        self.retain(|item| !set.contains(item))
+    }
+}
+
+/// An iterator over the items of a [`SmallSet`] in ascending order
+///
+/// This is returned by [`SmallSet::iter`].
+pub struct Iter<'a, T> {
+    entries: small_map::Iter<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.entries.next()?.0)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(self.entries.next_back()?.0)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallSet<T, N> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SmallSet<T, N>
+where
+    T: PartialOrd,
+{
+    /// Collect the items of an iterator into a set
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields more than `N` distinct items.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = SmallSet::new();
+        if set.try_extend(iter).is_err() {
+            panic!("iterator has more than {} distinct items", N);
+        }
+        set
+    }
+}
+
+impl<T, const N: usize> Default for SmallSet<T, N> {
+    fn default() -> Self {
+        SmallSet::new()
+    }
+}
+
+impl<T, const N: usize, const M: usize> PartialEq<SmallSet<T, M>> for SmallSet<T, N>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &SmallSet<T, M>) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T, const N: usize> Eq for SmallSet<T, N> where T: Eq {}
+
+impl<T, const N: usize> fmt::Debug for SmallSet<T, N>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn small_set_algebra() {
+    let mut a: SmallSet<u8, 8> = [5, 1, 3, 5, 7].iter().copied().collect();
+    let b: SmallSet<u8, 4> = [3, 4, 5].iter().copied().collect();
+    assert_eq!(a.len(), 4);
+    assert_eq!(a.insert(3), Ok(false));
+    assert!(a.intersection(&b).iter().eq(&[3, 5]));
+    assert!(a.difference(&b).iter().eq(&[1, 7]));
+    assert!(a.union(&b).unwrap().iter().eq(&[1, 3, 4, 5, 7]));
+    assert_eq!(b.union(&a), Err(7));
+    assert!(a.is_disjoint(&SmallSet::<u8, 2>::new()));
+    assert!(a.remove(&7) && !a.remove(&7));
+    assert!(!a.is_subset(&b) && a.is_superset(&a.intersection(&b)));
+    Set::collect([1, 3, 9, 9], |set| {
+        let mut c = a;
+        c.intersect_with_set(set);
+        assert!(c.is_subset_of_set(set) && c.iter().eq(&[1, 3]));
+        a.subtract_set(set);
+        assert!(a.iter().eq(&[5]));
+        assert_eq!(a.extend_from_set(set), Ok(()));
+        assert!(a.iter().eq(&[1, 3, 5, 9]));
+    });
+}
@@ -0,0 +1,311 @@
+//! A growable sorted sequence where all items exist on the stack
+//!
+//! A [`SortedList`] keeps its items in a balanced tree in which every subtree
+//! knows its size, so items can be found both by value and by index.
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    iter::FusedIterator,
+    ops::{Bound, Index, RangeBounds},
+};
+
+use crate::tree::{self, InOrder, Node};
+
+/// A growable sequence that keeps its items in ascending order where all
+/// items exist on the stack
+///
+/// Unlike a [`Set`](crate::Set), a `SortedList` keeps every item inserted into
+/// it, including equal ones, and does not remember the order of insertion.
+///
+/// As with the other collections, the list with an inserted item cannot be
+/// accessed from the same scope. Instead, a continuation function is called on
+/// the new list, and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::SortedList;
+///
+/// SortedList::collect([31, 12, 47, 12, 25], |times| {
+///     assert!(times.iter().eq(&[12, 12, 25, 31, 47]));
+///     assert_eq!(times.median(), Some(&25));
+///     assert_eq!(times.binary_search(&31), Ok(3));
+///     assert_eq!(times.binary_search(&30), Err(3));
+///     assert!(times.range(12..30).eq(&[12, 12, 25]));
+/// });
+/// ```
+pub struct SortedList<'a, T> {
+    root: Option<&'a Node<'a, T>>,
+}
+
+impl<'a, T> SortedList<'a, T> {
+    /// Create a new list
+    pub fn new() -> Self {
+        SortedList::default()
+    }
+    /// Check if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+    /// Get the list's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        tree::size(self.root)
+    }
+    /// Get the item at an index in ascending order
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        tree::nth(self.root, index)
+    }
+    /// Get the least item in the list
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn first(&self) -> Option<&'a T> {
+        self.get(0)
+    }
+    /// Get the greatest item in the list
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn last(&self) -> Option<&'a T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+    /// Get the median item of the list
+    ///
+    /// If the list has an even length, this is the lesser of the two middle items.
+    /// This is an **O(logn)** operation.
+    pub fn median(&self) -> Option<&'a T> {
+        self.get(self.len().checked_sub(1)? / 2)
+    }
+    /// Get an iterator over the items of the list in ascending order
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            nodes: InOrder::new(self.root),
+            len: self.len(),
+        }
+    }
+}
+
+impl<'a, T> SortedList<'a, T>
+where
+    T: PartialOrd,
+{
+    /// Get the number of items less than a value
+    fn rank_below<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        tree::rank(self.root, |item: &T| item.borrow() < value)
+    }
+    /// Get the number of items less than or equal to a value
+    fn rank_through<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        tree::rank(self.root, |item: &T| item.borrow() <= value)
+    }
+    /// Search the list for a value
+    ///
+    /// If the value is found, returns the index of the first item equal to it.
+    /// Otherwise, returns the index the value could be inserted at to keep the
+    /// list sorted. This is an **O(logn)** operation.
+    pub fn binary_search<Q>(&self, value: &Q) -> Result<usize, usize>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        let index = self.rank_below(value);
+        match self.get(index) {
+            Some(item) if item.borrow() == value => Ok(index),
+            _ => Err(index),
+        }
+    }
+    /// Check if the list contains a value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.binary_search(value).is_ok()
+    }
+    /// Get the number of items equal to a value
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+    {
+        self.rank_through(value)
+            .saturating_sub(self.rank_below(value))
+    }
+    /// Get an iterator over the items of the list within a range of values, in
+    /// ascending order
+    ///
+    /// Finding the start of the range is an **O(logn)** operation.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'a, T>
+    where
+        T: Borrow<Q>,
+        Q: PartialOrd + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let (start, nodes) = match range.start_bound() {
+            Bound::Included(start) => (
+                self.rank_below(start),
+                InOrder::lower_bound(self.root, |item: &T| item.borrow() < start),
+            ),
+            Bound::Excluded(start) => (
+                self.rank_through(start),
+                InOrder::lower_bound(self.root, |item: &T| item.borrow() <= start),
+            ),
+            Bound::Unbounded => (0, InOrder::new(self.root)),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.rank_through(end),
+            Bound::Excluded(end) => self.rank_below(end),
+            Bound::Unbounded => self.len(),
+        };
+        Iter {
+            nodes,
+            len: end.saturating_sub(start),
+        }
+    }
+    /// Insert an item into the list, call a continuation on the new list, and
+    /// return its result
+    ///
+    /// The item is placed after any items equal to it.
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&SortedList<T>) -> R,
+    {
+        let cmp = |a: &T, b: &T| match tree::compare(a, b) {
+            Ordering::Equal => Ordering::Greater,
+            ordering => ordering,
+        };
+        tree::insert(self.root, &item, &cmp, |root| {
+            then(&SortedList { root: Some(root) })
+        })
+    }
+    /// Collect an iterator into a list and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&SortedList<T>) -> R,
+    {
+        SortedList::default().extend(iter, then)
+    }
+    /// Extend the list with an iterator and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&SortedList<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.insert(item, |list| list.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the items of a [`SortedList`] in ascending order
+///
+/// This is returned by [`SortedList::iter`] and [`SortedList::range`].
+pub struct Iter<'a, T> {
+    nodes: InOrder<'a, T>,
+    /// The number of items left to yield
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        self.nodes.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &SortedList<'a, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Index<usize> for SortedList<'a, T> {
+    type Output = T;
+    #[track_caller]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> Default for SortedList<'a, T> {
+    fn default() -> Self {
+        SortedList { root: None }
+    }
+}
+
+impl<'a, T> Clone for SortedList<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for SortedList<'a, T> {}
+
+impl<'a, 'b, T, U> PartialEq<SortedList<'b, U>> for SortedList<'a, T>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &SortedList<'b, U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<'a, T> Eq for SortedList<'a, T> where T: Eq {}
+
+impl<'a, T> fmt::Debug for SortedList<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn sorted_list_queries() {
+    let items = (0..60).map(|i| (i * 17) % 30);
+    SortedList::collect(items, |list| {
+        assert_eq!(list.len(), 60);
+        assert!(list.iter().copied().eq((0..30).flat_map(|i| [i, i])));
+        assert!(tree::is_balanced(list.root));
+        assert_eq!(list.binary_search(&7), Ok(14));
+        assert_eq!(list.binary_search(&30), Err(60));
+        assert_eq!(list.count(&29), 2);
+        assert_eq!(list.median(), Some(&14));
+        assert!(list.range(5..=6).copied().eq([5, 5, 6, 6]));
+        assert_eq!(list.range((Bound::Excluded(28), Bound::Unbounded)).len(), 2);
+        assert_eq!(
+            list.range((Bound::Included(9), Bound::Excluded(3))).len(),
+            0
+        );
+    });
+}
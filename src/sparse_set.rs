@@ -0,0 +1,198 @@
+//! A set of small integers stored inline as a dense array of members and a
+//! sparse array of their positions
+//!
+//! Inserting, removing and looking up an integer are **O(1)**, and the
+//! members can be iterated as a contiguous slice.
+
+use core::{
+    fmt,
+    iter::{Copied, FromIterator},
+    slice,
+};
+
+/// A set of the integers below `N`, stored inline as two arrays of `N` indices
+///
+/// Unlike a [`BitSet`](crate::BitSet), iterating a `SparseSet` only visits its
+/// members, and clearing it is **O(1)**. Members are kept in the order they
+/// were inserted, except that removing a member moves the last member into
+/// its place.
+///
+/// # Example
+/// ```
+/// use nolloc::SparseSet;
+///
+/// let mut moving = SparseSet::<1024>::new();
+/// moving.insert(700);
+/// moving.insert(3);
+/// moving.insert(42);
+///
+/// assert!(moving.remove(700));
+/// assert!(moving.contains(42));
+/// assert_eq!(moving.as_slice(), &[42, 3]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SparseSet<const N: usize> {
+    /// The members, of which only the first `len` are live
+    dense: [usize; N],
+    /// The position in `dense` of each integer, which is only meaningful for members
+    sparse: [usize; N],
+    len: usize,
+}
+
+impl<const N: usize> SparseSet<N> {
+    /// Create a new empty set
+    pub fn new() -> Self {
+        SparseSet {
+            dense: [0; N],
+            sparse: [0; N],
+            len: 0,
+        }
+    }
+    /// Get the number of integers the set can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of integers in the set
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Check if an integer is in the set
+    ///
+    /// Integers not less than the set's capacity are never in it.
+    /// This is an **O(1)** operation.
+    pub fn contains(&self, value: usize) -> bool {
+        self.sparse
+            .get(value)
+            .is_some_and(|&i| i < self.len && self.dense[i] == value)
+    }
+    /// Add an integer to the set
+    ///
+    /// Returns whether the integer was not already in the set.
+    /// This is an **O(1)** operation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer is not less than the set's capacity.
+    pub fn insert(&mut self, value: usize) -> bool {
+        assert!(
+            value < N,
+            "{} is out of bounds of a set of capacity {}",
+            value,
+            N
+        );
+        if self.contains(value) {
+            return false;
+        }
+        self.dense[self.len] = value;
+        self.sparse[value] = self.len;
+        self.len += 1;
+        true
+    }
+    /// Remove an integer from the set
+    ///
+    /// Returns whether the integer was in the set. The last member is moved
+    /// into the removed integer's place. This is an **O(1)** operation.
+    pub fn remove(&mut self, value: usize) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        let i = self.sparse[value];
+        self.len -= 1;
+        let last = self.dense[self.len];
+        self.dense[i] = last;
+        self.sparse[last] = i;
+        true
+    }
+    /// Remove all integers from the set
+    ///
+    /// This is an **O(1)** operation.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+    /// Get the members of the set as a slice
+    pub fn as_slice(&self) -> &[usize] {
+        &self.dense[..self.len]
+    }
+    /// Get an iterator over the members of the set in the order of
+    /// [`SparseSet::as_slice`]
+    pub fn iter(&self) -> Copied<slice::Iter<'_, usize>> {
+        self.as_slice().iter().copied()
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a SparseSet<N> {
+    type Item = usize;
+    type IntoIter = Copied<slice::Iter<'a, usize>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<const N: usize> Extend<usize> for SparseSet<N> {
+    /// Add the integers of an iterator to the set
+    ///
+    /// # Panics
+    ///
+    /// Panics if any integer is not less than the set's capacity.
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<const N: usize> FromIterator<usize> for SparseSet<N> {
+    /// Collect the integers of an iterator into a set
+    ///
+    /// # Panics
+    ///
+    /// Panics if any integer is not less than the set's capacity.
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = SparseSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const N: usize> Default for SparseSet<N> {
+    fn default() -> Self {
+        SparseSet::new()
+    }
+}
+
+/// Sets are equal if they have the same members, in any order
+impl<const N: usize, const M: usize> PartialEq<SparseSet<M>> for SparseSet<N> {
+    fn eq(&self, other: &SparseSet<M>) -> bool {
+        self.len == other.len && self.iter().all(|value| other.contains(value))
+    }
+}
+
+impl<const N: usize> Eq for SparseSet<N> {}
+
+impl<const N: usize> fmt::Debug for SparseSet<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn sparse_set_ops() {
+    let mut set: SparseSet<100> = [5, 99, 0, 5, 42].iter().copied().collect();
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.as_slice(), &[5, 99, 0, 42]);
+    assert!(set.remove(5));
+    assert!(!set.remove(5));
+    assert!(!set.contains(100));
+    assert_eq!(set.as_slice(), &[42, 99, 0]);
+    assert_eq!(set, [0, 42, 99].iter().copied().collect::<SparseSet<128>>());
+    set.clear();
+    assert!(set.is_empty() && !set.contains(42));
+    assert!(set.insert(42));
+    assert_eq!(set.as_slice(), &[42]);
+}
@@ -0,0 +1,146 @@
+//! A mutable handle to a value placed in a stack frame
+//!
+//! A [`StackBox`] is to a continuation what a `Box` is to the heap: the value
+//! lives in the frame of [`StackBox::emplace`], which stays alive for as long
+//! as the continuation runs.
+
+use core::{
+    borrow::{Borrow, BorrowMut},
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// A mutable handle to a value placed in a lower stack frame
+///
+/// # Example
+/// ```
+/// use nolloc::StackBox;
+///
+/// fn bump(counter: &mut u32) {
+///     *counter += 1;
+/// }
+///
+/// let total = StackBox::emplace(0u32, |mut counter| {
+///     for _ in 0..3 {
+///         bump(&mut counter);
+///     }
+///     *counter
+/// });
+///
+/// assert_eq!(total, 3);
+/// ```
+pub struct StackBox<'a, T: ?Sized> {
+    value: &'a mut T,
+}
+
+impl<'a, T> StackBox<'a, T> {
+    /// Place a value in this stack frame, call a continuation with a handle to
+    /// it, and return the continuation's result
+    ///
+    /// The value is dropped when the continuation returns.
+    pub fn emplace<F, R>(value: T, then: F) -> R
+    where
+        F: FnOnce(StackBox<T>) -> R,
+    {
+        let mut value = value;
+        then(StackBox { value: &mut value })
+    }
+}
+
+impl<'a, T: ?Sized> StackBox<'a, T> {
+    /// Wrap a mutable reference
+    pub fn new(value: &'a mut T) -> Self {
+        StackBox { value }
+    }
+    /// Get the underlying reference, which lives as long as the value's frame
+    pub fn into_mut(self) -> &'a mut T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> Deref for StackBox<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for StackBox<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> AsRef<T> for StackBox<'a, T> {
+    fn as_ref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> AsMut<T> for StackBox<'a, T> {
+    fn as_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> Borrow<T> for StackBox<'a, T> {
+    fn borrow(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> BorrowMut<T> for StackBox<'a, T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<'a, T: ?Sized> fmt::Debug for StackBox<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<'a, T: ?Sized> fmt::Display for StackBox<'a, T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+#[test]
+fn stack_box_drop_and_deref() {
+    use core::cell::Cell;
+
+    struct Tracked<'c>(&'c Cell<u32>, [u8; 4]);
+    impl Drop for Tracked<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let drops = Cell::new(0);
+    let first = StackBox::emplace(Tracked(&drops, [1, 2, 3, 4]), |mut tracked| {
+        tracked.1[0] = 9;
+        assert_eq!(tracked.1.len(), 4);
+        assert_eq!(drops.get(), 0);
+        // A handle does not own its value, so it drops nothing
+        {
+            let mut inner = StackBox::new(&mut *tracked);
+            inner.1[1] = 8;
+        }
+        assert_eq!((drops.get(), tracked.1), (0, [9, 8, 3, 4]));
+        tracked.into_mut().1[0]
+    });
+    assert_eq!(first, 9);
+    assert_eq!(drops.get(), 1);
+    let mut text = [b'a'; 3];
+    let mut slice = StackBox::new(&mut text[..]);
+    slice.make_ascii_uppercase();
+    assert_eq!(&*slice, b"AAA");
+}
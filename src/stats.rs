@@ -0,0 +1,231 @@
+//! Statistics over iterators of numbers
+//!
+//! [`summarize`] computes summary statistics in one pass without storing any
+//! items. [`median`] and [`percentile`] need every item, so they collect the
+//! items into a [`Set`] on the stack.
+
+use crate::Set;
+
+/// Summary statistics of a sequence of numbers
+///
+/// Items are added one at a time, and the variance is kept up to date with
+/// Welford's algorithm, which avoids the cancellation error of summing squares.
+///
+/// # Example
+/// ```
+/// use nolloc::stats::Summary;
+///
+/// let mut latency = Summary::new();
+/// for ms in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0] {
+///     latency.add(ms);
+/// }
+/// latency.add(9.0);
+///
+/// assert_eq!(latency.count(), 8);
+/// assert_eq!(latency.mean(), Some(5.0));
+/// assert_eq!(latency.variance(), Some(4.0));
+/// assert_eq!(latency.max(), Some(9.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Summary {
+    count: usize,
+    mean: f64,
+    /// The sum of squared differences from the mean
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Summary {
+    /// Create a summary of no items
+    pub fn new() -> Self {
+        Summary::default()
+    }
+    /// Add an item to the summary
+    ///
+    /// This is an **O(1)** operation.
+    pub fn add(&mut self, x: f64) {
+        self.count += 1;
+        if self.count == 1 {
+            self.min = x;
+            self.max = x;
+        } else {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+    /// Get the number of items
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    /// Get the mean of the items, or `None` if there are none
+    pub fn mean(&self) -> Option<f64> {
+        Some(self.mean).filter(|_| self.count > 0)
+    }
+    /// Get the population variance of the items, or `None` if there are none
+    pub fn variance(&self) -> Option<f64> {
+        Some(self.m2 / self.count as f64).filter(|_| self.count > 0)
+    }
+    /// Get the sample variance of the items, or `None` if there are fewer than two
+    pub fn sample_variance(&self) -> Option<f64> {
+        Some(self.m2 / (self.count as f64 - 1.0)).filter(|_| self.count > 1)
+    }
+    /// Get the least item, or `None` if there are none
+    ///
+    /// NaN items are ignored unless every item is NaN.
+    pub fn min(&self) -> Option<f64> {
+        Some(self.min).filter(|_| self.count > 0)
+    }
+    /// Get the greatest item, or `None` if there are none
+    ///
+    /// NaN items are ignored unless every item is NaN.
+    pub fn max(&self) -> Option<f64> {
+        Some(self.max).filter(|_| self.count > 0)
+    }
+}
+
+impl<T> Extend<T> for Summary
+where
+    T: Into<f64>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.add(x.into());
+        }
+    }
+}
+
+/// Compute summary statistics of an iterator of numbers in one pass
+///
+/// # Example
+/// ```
+/// use nolloc::stats::summarize;
+///
+/// let readings = [21u8, 23, 22, 26];
+/// let summary = summarize(readings.iter().copied());
+/// assert_eq!(summary.mean(), Some(23.0));
+/// assert_eq!(summary.min(), Some(21.0));
+/// assert_eq!(summary.sample_variance(), Some(14.0 / 3.0));
+/// ```
+pub fn summarize<I>(iter: I) -> Summary
+where
+    I: IntoIterator,
+    I::Item: Into<f64>,
+{
+    let mut summary = Summary::new();
+    summary.extend(iter);
+    summary
+}
+
+/// Find the median of an iterator, call a continuation function on it, and
+/// return its result
+///
+/// For an even number of items, this is the lower of the two middle items.
+/// The continuation is passed `None` if there are no items.
+///
+/// Items are collected into a [`Set`], so this takes **O(nlogn)** time and
+/// places each item in its own stack frame. They must all be comparable, so
+/// for floats there must be no NaN.
+///
+/// # Example
+/// ```
+/// use nolloc::stats::median;
+///
+/// let name = median(["pear", "fig", "apple", "fig"], |name| name.copied());
+/// assert_eq!(name, Some("fig"));
+/// median(1..1, |none| assert!(none.is_none()));
+/// ```
+pub fn median<I, F, R>(iter: I, then: F) -> R
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+    F: FnOnce(Option<&I::Item>) -> R,
+{
+    nth_ranked(iter, |len| len.saturating_sub(1) / 2, then)
+}
+
+/// Find a percentile of an iterator, call a continuation function on it, and
+/// return its result
+///
+/// `p` is a percentage from 0 to 100. This uses the nearest-rank method, so
+/// the result is the least item that at least `p`% of the items are less than
+/// or equal to. The continuation is passed `None` if there are no items.
+///
+/// Items are collected as by [`median`].
+///
+/// # Panics
+///
+/// Panics if `p` is not in the range `0.0..=100.0`.
+///
+/// # Example
+/// ```
+/// use nolloc::stats::percentile;
+///
+/// let latencies = [15, 20, 35, 40, 50];
+/// assert_eq!(percentile(latencies, 30.0, |p| p.copied()), Some(20));
+/// assert_eq!(percentile(latencies, 100.0, |p| p.copied()), Some(50));
+/// ```
+pub fn percentile<I, F, R>(iter: I, p: f64, then: F) -> R
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+    F: FnOnce(Option<&I::Item>) -> R,
+{
+    assert!(
+        (0.0..=100.0).contains(&p),
+        "percentile {} is not between 0 and 100",
+        p
+    );
+    nth_ranked(
+        iter,
+        |len| {
+            let rank = p / 100.0 * len as f64;
+            let ceil = rank as usize + usize::from((rank as usize as f64) < rank);
+            ceil.saturating_sub(1)
+        },
+        then,
+    )
+}
+
+/// Collect an iterator into a set and pass the item at an index in ascending
+/// order, computed from the number of items, to a continuation
+fn nth_ranked<I, N, F, R>(iter: I, index: N, then: F) -> R
+where
+    I: IntoIterator,
+    I::Item: PartialOrd,
+    N: FnOnce(usize) -> usize,
+    F: FnOnce(Option<&I::Item>) -> R,
+{
+    // Each item is paired with its position so that equal items are distinct
+    let items = iter.into_iter().enumerate().map(|(i, item)| (item, i));
+    Set::collect(items, |set| {
+        let item = set.nth(index(set.len())).map(|(item, _)| item);
+        then(item)
+    })
+}
+
+#[test]
+fn stats_summaries() {
+    let empty = summarize(None::<f64>);
+    assert_eq!(empty.mean(), None);
+    assert_eq!(empty.variance(), None);
+    assert_eq!(empty.max(), None);
+    let one = summarize(Some(-3i32));
+    assert_eq!((one.min(), one.max()), (Some(-3.0), Some(-3.0)));
+    assert_eq!((one.variance(), one.sample_variance()), (Some(0.0), None));
+    // Large offsets cancel out of the variance
+    let offset = summarize([1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0]);
+    assert_eq!(offset.sample_variance(), Some(30.0));
+    let nan = summarize([1.0, f64::NAN, 2.0]);
+    assert_eq!((nan.min(), nan.max()), (Some(1.0), Some(2.0)));
+    let repeated = [3, 1, 3, 3, 2, 1];
+    assert_eq!(median(repeated, |m| m.copied()), Some(2));
+    assert_eq!(median([5], |m| m.copied()), Some(5));
+    assert_eq!(percentile(repeated, 0.0, |p| p.copied()), Some(1));
+    assert_eq!(percentile(repeated, 50.0, |p| p.copied()), Some(2));
+    assert_eq!(percentile(repeated, 51.0, |p| p.copied()), Some(3));
+    assert_eq!(percentile(None::<i32>, 50.0, |p| p.copied()), None);
+}
@@ -0,0 +1,67 @@
+//! Splitting [`str`](prim@str)s into [`List`]s of pieces
+//!
+//! Each function collects the pieces in order, so iterating the list yields
+//! them from first to last. The pieces borrow from the original string.
+
+use crate::List;
+
+/// Split a string at a separator, collect the pieces into a list, and call a
+/// continuation function on it
+///
+/// # Example
+/// ```
+/// use nolloc::str_ext::split_collect;
+///
+/// split_collect("set,gain,12", ',', |args| {
+///     assert_eq!(args.len(), 3);
+///     assert!(args.iter().eq(&["set", "gain", "12"]));
+/// });
+/// ```
+pub fn split_collect<'s, F, R>(s: &'s str, separator: char, then: F) -> R
+where
+    F: FnOnce(&List<&'s str>) -> R,
+{
+    List::collect_in_order(s.split(separator), then)
+}
+
+/// Split a string into lines, collect them into a list, and call a
+/// continuation function on it
+///
+/// Lines are split as by [`str::lines`], so they do not include their
+/// `\n` or `\r\n` endings.
+pub fn lines_collect<'s, F, R>(s: &'s str, then: F) -> R
+where
+    F: FnOnce(&List<&'s str>) -> R,
+{
+    List::collect_in_order(s.lines(), then)
+}
+
+/// Split a string at whitespace, collect the words into a list, and call a
+/// continuation function on it
+///
+/// # Example
+/// ```
+/// use nolloc::str_ext::split_whitespace_collect;
+///
+/// split_whitespace_collect("  led  on\t2 ", |words| {
+///     assert_eq!(words.head(), Some(&"led"));
+///     assert_eq!(words.tail(), Some(&"2"));
+/// });
+/// ```
+pub fn split_whitespace_collect<'s, F, R>(s: &'s str, then: F) -> R
+where
+    F: FnOnce(&List<&'s str>) -> R,
+{
+    List::collect_in_order(s.split_whitespace(), then)
+}
+
+#[test]
+fn str_ext_order() {
+    split_collect("a::b", ':', |pieces| {
+        assert!(pieces.iter().eq(&["a", "", "b"]));
+    });
+    lines_collect("one\r\ntwo\n\nthree", |lines| {
+        assert!(lines.iter().eq(&["one", "two", "", "three"]));
+    });
+    split_whitespace_collect(" \t\n", |words| assert!(words.is_empty()));
+}
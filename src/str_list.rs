@@ -0,0 +1,244 @@
+//! A growable string made of [`str`](prim@str) fragments that exist on the stack
+
+use core::{fmt, str};
+
+use crate::{bytes_list, list, BytesList};
+
+/// A growable string made of [`str`](prim@str) fragments that exist on the stack
+///
+/// Fragments are appended with [`StrList::push_str`]. As with [`List`](crate::List), the
+/// string with an appended fragment cannot be accessed from the same scope.
+/// Instead, a continuation function is called on the new string, and its
+/// result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::StrList;
+///
+/// let level = "WARN";
+/// StrList::collect(["[", level, "] ", "disk almost full"], |line| {
+///     assert_eq!(line, "[WARN] disk almost full");
+///     assert_eq!(line.len_utf8(), 23);
+///     assert_eq!(line.to_string(), "[WARN] disk almost full");
+/// });
+/// ```
+#[derive(Default)]
+pub struct StrList<'a> {
+    /// The fragments as byte segments, each of which is valid UTF-8
+    bytes: BytesList<'a>,
+}
+
+impl<'a> StrList<'a> {
+    /// Create a new empty string
+    pub fn new() -> Self {
+        StrList::default()
+    }
+    /// Check if the string is empty
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    /// Get the length of the string in bytes
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len_utf8(&self) -> usize {
+        self.bytes.total_len()
+    }
+    /// Get the number of fragments in the string
+    ///
+    /// This is an **O(1)** operation.
+    pub fn fragment_count(&self) -> usize {
+        self.bytes.segment_count()
+    }
+    /// Get the string as a [`BytesList`] of its fragments
+    pub fn as_bytes(&self) -> BytesList<'a> {
+        self.bytes
+    }
+    /// Append a fragment to the string and call a continuation function
+    pub fn push_str<F, R>(&self, s: &'a str, then: F) -> R
+    where
+        F: FnOnce(&StrList) -> R,
+    {
+        self.bytes
+            .push(s.as_bytes(), |bytes| then(&StrList { bytes: *bytes }))
+    }
+    /// Get an iterator over the fragments of the string, last first
+    pub fn fragments_rev(&self) -> FragmentsRev<'a> {
+        FragmentsRev {
+            segments: self.bytes.segments_rev(),
+        }
+    }
+    /// Call a continuation function on an iterator over the fragments of the
+    /// string in order
+    ///
+    /// The fragments are reversed on the stack, so this is an **O(m)**
+    /// operation for m fragments that uses stack space for each of them.
+    pub fn fragments<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(Fragments) -> R,
+    {
+        self.bytes.segments(|segments| then(Fragments { segments }))
+    }
+    /// Call a continuation function on an iterator over the [`char`]s of the
+    /// string
+    ///
+    /// The fragments are reversed on the stack, so this is an **O(n + m)**
+    /// operation for n bytes in m fragments.
+    pub fn chars<F, R>(&self, then: F) -> R
+    where
+        F: FnOnce(Chars) -> R,
+    {
+        self.fragments(|fragments| {
+            then(Chars {
+                fragments,
+                chars: "".chars(),
+            })
+        })
+    }
+    /// Write the string to a writer
+    pub fn write_to<W>(&self, w: &mut W) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        self.fragments(|mut fragments| fragments.try_for_each(|s| w.write_str(s)))
+    }
+    /// Collect an iterator of fragments into a string and call a continuation
+    /// function on it
+    pub fn collect<'s, I, F, R>(iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: IntoIterator<Item = &'s str>,
+        F: FnOnce(&StrList) -> R,
+    {
+        StrList::default().extend(iter, then)
+    }
+    /// Append the fragments of an iterator to the string and call a continuation
+    /// function on it
+    pub fn extend<'s, I, F, R>(&self, iter: I, then: F) -> R
+    where
+        's: 'a,
+        I: IntoIterator<Item = &'s str>,
+        F: FnOnce(&StrList) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(s) = iter.next() {
+            self.push_str(s, |string| string.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// View a segment pushed by [`StrList::push_str`] as a [`str`](prim@str)
+fn fragment(bytes: &[u8]) -> &str {
+    str::from_utf8(bytes).expect("fragments are pushed as str")
+}
+
+/// An iterator over the fragments of a [`StrList`], last first
+///
+/// This is returned by [`StrList::fragments_rev`].
+pub struct FragmentsRev<'a> {
+    segments: list::Iter<'a, &'a [u8]>,
+}
+
+impl<'a> Iterator for FragmentsRev<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.segments.next().map(|bytes| fragment(bytes))
+    }
+}
+
+/// An iterator over the fragments of a [`StrList`] in order
+///
+/// This is passed to the continuation of [`StrList::fragments`].
+pub struct Fragments<'a> {
+    segments: bytes_list::Segments<'a>,
+}
+
+impl<'a> Iterator for Fragments<'a> {
+    type Item = &'a str;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.segments.next().map(fragment)
+    }
+}
+
+/// An iterator over the [`char`]s of a [`StrList`]
+///
+/// This is passed to the continuation of [`StrList::chars`].
+pub struct Chars<'a> {
+    fragments: Fragments<'a>,
+    chars: str::Chars<'a>,
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = char;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(c) = self.chars.next() {
+                return Some(c);
+            }
+            self.chars = self.fragments.next()?.chars();
+        }
+    }
+}
+
+impl<'a> Clone for StrList<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for StrList<'a> {}
+
+impl<'a> PartialEq<str> for StrList<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes == *other.as_bytes()
+    }
+}
+
+impl<'a> PartialEq<&str> for StrList<'a> {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a> PartialEq<StrList<'a>> for str {
+    fn eq(&self, other: &StrList<'a>) -> bool {
+        *other == *self
+    }
+}
+
+impl<'a> PartialEq<StrList<'a>> for &str {
+    fn eq(&self, other: &StrList<'a>) -> bool {
+        *other == **self
+    }
+}
+
+impl<'a> fmt::Debug for StrList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        self.chars(|mut chars| chars.try_for_each(|c| fmt::Display::fmt(&c.escape_debug(), f)))?;
+        f.write_str("\"")
+    }
+}
+
+impl<'a> fmt::Display for StrList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+#[test]
+fn str_list_eq() {
+    StrList::collect(["ab", "", "cé", "d"], |s| {
+        assert_eq!(s.len_utf8(), 6);
+        assert_eq!(*s, "abcéd");
+        assert_ne!(*s, "abcéx");
+        assert_ne!(*s, "abcé");
+        assert!(s.chars(|chars| chars.eq("abcéd".chars())));
+        assert!(s.fragments(|fragments| fragments.eq(["ab", "", "cé", "d"])));
+        assert!(s.fragments_rev().eq(["d", "cé", "", "ab"]));
+        assert_eq!(s.as_bytes(), "abcéd".as_bytes());
+        assert_eq!("abcéd", *s);
+    });
+    assert_eq!(StrList::new(), "");
+}
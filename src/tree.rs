@@ -0,0 +1,655 @@
+//! A persistent AVL tree used to index the entries of the ordered collections
+//!
+//! Nodes only hold references to entries, so inserting copies the nodes along
+//! the search path rather than the entries themselves. The copied nodes live in
+//! a buffer in a single stack frame, and the new root is passed to a continuation.
+
+use core::{cmp::Ordering, fmt};
+
+/// The maximum height of a tree
+///
+/// A balanced tree this tall would have over 10 billion nodes.
+pub(crate) const MAX_HEIGHT: usize = 48;
+
+pub(crate) struct Node<'a, E, A = ()> {
+    pub(crate) entry: &'a E,
+    pub(crate) left: Option<&'a Node<'a, E, A>>,
+    pub(crate) right: Option<&'a Node<'a, E, A>>,
+    pub(crate) height: u8,
+    /// The number of nodes in this subtree
+    pub(crate) size: usize,
+    /// The summary of this subtree
+    pub(crate) augment: A,
+}
+
+/// A summary of a subtree that is kept up to date as the tree changes
+///
+/// Most trees have no summary, which is `()`.
+pub(crate) trait Augment<E>: Copy {
+    /// Summarize a subtree from its root entry and the summaries of its children
+    fn new(entry: &E, left: Option<&Self>, right: Option<&Self>) -> Self;
+}
+
+impl<E> Augment<E> for () {
+    fn new(_: &E, _: Option<&Self>, _: Option<&Self>) -> Self {}
+}
+
+impl<'a, E, A> Clone for Node<'a, E, A>
+where
+    A: Copy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, E, A> Copy for Node<'a, E, A> where A: Copy {}
+
+impl<'a, E, A> Node<'a, E, A>
+where
+    A: Augment<E>,
+{
+    pub(crate) fn new(entry: &'a E, left: Option<&'a Self>, right: Option<&'a Self>) -> Self {
+        Node {
+            entry,
+            left,
+            right,
+            height: height(left).max(height(right)) + 1,
+            size: size(left) + size(right) + 1,
+            augment: A::new(
+                entry,
+                left.map(|left| &left.augment),
+                right.map(|right| &right.augment),
+            ),
+        }
+    }
+}
+
+pub(crate) fn height<E, A>(node: Option<&Node<E, A>>) -> u8 {
+    node.map_or(0, |node| node.height)
+}
+
+pub(crate) fn size<E, A>(node: Option<&Node<E, A>>) -> usize {
+    node.map_or(0, |node| node.size)
+}
+
+/// Compare two values the same way lookups do
+///
+/// Values that are neither equal nor less are treated as greater.
+pub(crate) fn compare<Q>(a: &Q, b: &Q) -> Ordering
+where
+    Q: PartialOrd + ?Sized,
+{
+    if a == b {
+        Ordering::Equal
+    } else if a < b {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Check that the heights of every node's subtrees differ by at most one
+pub(crate) fn is_balanced<E, A>(node: Option<&Node<E, A>>) -> bool {
+    match node {
+        Some(node) => {
+            height(node.left).abs_diff(height(node.right)) <= 1
+                && is_balanced(node.left)
+                && is_balanced(node.right)
+        }
+        None => true,
+    }
+}
+
+/// Check the invariants of a tree, panicking if any are violated
+#[cfg(any(test, feature = "validate"))]
+pub(crate) fn validate<E, A, C>(root: Option<&Node<E, A>>, cmp: C)
+where
+    C: Fn(&E, &E) -> Ordering,
+{
+    fn check_heights<E, A>(node: Option<&Node<E, A>>) -> u8 {
+        node.map_or(0, |node| {
+            let height = check_heights(node.left).max(check_heights(node.right)) + 1;
+            assert_eq!(node.height, height, "tree node has the wrong height");
+            height
+        })
+    }
+    fn check_sizes<E, A>(node: Option<&Node<E, A>>) -> usize {
+        node.map_or(0, |node| {
+            let size = check_sizes(node.left) + check_sizes(node.right) + 1;
+            assert_eq!(node.size, size, "tree node has the wrong size");
+            size
+        })
+    }
+    check_heights(root);
+    check_sizes(root);
+    assert!(is_balanced(root), "tree is not balanced");
+    let mut entries = InOrder::new(root);
+    if let Some(mut prev) = entries.next() {
+        for entry in entries {
+            assert_eq!(cmp(prev, entry), Ordering::Less, "tree is out of order");
+            prev = entry;
+        }
+    }
+}
+
+/// Find the node for which `probe` returns [`Ordering::Equal`]
+///
+/// `probe` returns the ordering of the searched-for value relative to an entry.
+pub(crate) fn find<'a, E, A, P>(
+    mut node: Option<&'a Node<'a, E, A>>,
+    mut probe: P,
+) -> Option<&'a Node<'a, E, A>>
+where
+    P: FnMut(&E) -> Ordering,
+{
+    loop {
+        let curr = node?;
+        node = match probe(curr.entry) {
+            Ordering::Equal => return Some(curr),
+            Ordering::Less => curr.left,
+            Ordering::Greater => curr.right,
+        };
+    }
+}
+
+/// Find the first entry for which `before` returns false
+///
+/// `before` must return true for some prefix of the entries in order and false
+/// for the rest.
+pub(crate) fn lower_bound<'a, E, A, P>(
+    mut node: Option<&'a Node<'a, E, A>>,
+    before: P,
+) -> Option<&'a E>
+where
+    P: Fn(&E) -> bool,
+{
+    let mut found = None;
+    while let Some(curr) = node {
+        if before(curr.entry) {
+            node = curr.right;
+        } else {
+            found = Some(curr.entry);
+            node = curr.left;
+        }
+    }
+    found
+}
+
+/// Find the last entry for which `before` returns true
+///
+/// `before` must return true for some prefix of the entries in order and false
+/// for the rest.
+pub(crate) fn last_before<'a, E, A, P>(
+    mut node: Option<&'a Node<'a, E, A>>,
+    before: P,
+) -> Option<&'a E>
+where
+    P: Fn(&E) -> bool,
+{
+    let mut found = None;
+    while let Some(curr) = node {
+        if before(curr.entry) {
+            found = Some(curr.entry);
+            node = curr.right;
+        } else {
+            node = curr.left;
+        }
+    }
+    found
+}
+
+/// Count the entries for which `before` returns true
+///
+/// `before` must return true for some prefix of the entries in order and false
+/// for the rest.
+pub(crate) fn rank<E, A, P>(mut node: Option<&Node<E, A>>, before: P) -> usize
+where
+    P: Fn(&E) -> bool,
+{
+    let mut rank = 0;
+    while let Some(curr) = node {
+        if before(curr.entry) {
+            rank += size(curr.left) + 1;
+            node = curr.right;
+        } else {
+            node = curr.left;
+        }
+    }
+    rank
+}
+
+/// Get the entry at an index in order
+pub(crate) fn nth<'a, E, A>(mut node: Option<&'a Node<'a, E, A>>, mut n: usize) -> Option<&'a E> {
+    loop {
+        let curr = node?;
+        let left = size(curr.left);
+        node = match n.cmp(&left) {
+            Ordering::Less => curr.left,
+            Ordering::Equal => return Some(curr.entry),
+            Ordering::Greater => {
+                n -= left + 1;
+                curr.right
+            }
+        };
+    }
+}
+
+/// Find the leftmost entry that matches a predicate
+pub(crate) fn first<'a, E, A, P>(node: Option<&'a Node<'a, E, A>>, pred: &P) -> Option<&'a E>
+where
+    P: Fn(&E) -> bool,
+{
+    let node = node?;
+    first(node.left, pred)
+        .or_else(|| Some(node.entry).filter(|entry| pred(entry)))
+        .or_else(|| first(node.right, pred))
+}
+
+/// Find the rightmost entry that matches a predicate
+pub(crate) fn last<'a, E, A, P>(node: Option<&'a Node<'a, E, A>>, pred: &P) -> Option<&'a E>
+where
+    P: Fn(&E) -> bool,
+{
+    let node = node?;
+    last(node.right, pred)
+        .or_else(|| Some(node.entry).filter(|entry| pred(entry)))
+        .or_else(|| last(node.left, pred))
+}
+
+/// Write a tree in the DOT graph format
+///
+/// `attrs` writes the attribute list of an entry's node. Nodes are numbered in
+/// pre-order, and edges are labeled by the side of the child.
+pub(crate) fn write_dot<E, W, A>(root: Option<&Node<E>>, w: &mut W, attrs: &mut A) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+    A: FnMut(&mut W, &E) -> fmt::Result,
+{
+    fn write_node<E, W, A>(
+        node: &Node<E>,
+        id: usize,
+        w: &mut W,
+        attrs: &mut A,
+    ) -> Result<usize, fmt::Error>
+    where
+        W: fmt::Write + ?Sized,
+        A: FnMut(&mut W, &E) -> fmt::Result,
+    {
+        write!(w, "    n{} [", id)?;
+        attrs(w, node.entry)?;
+        writeln!(w, "];")?;
+        let mut next = id + 1;
+        for (side, child) in [("left", node.left), ("right", node.right)] {
+            if let Some(child) = child {
+                writeln!(w, "    n{} -> n{} [label=\"{}\"];", id, next, side)?;
+                next = write_node(child, next, w, attrs)?;
+            }
+        }
+        Ok(next)
+    }
+    writeln!(w, "digraph {{")?;
+    if let Some(root) = root {
+        write_node(root, 0, w, attrs)?;
+    }
+    writeln!(w, "}}")
+}
+
+/// Escapes quotes and backslashes so that text can be written in a DOT string
+pub(crate) struct DotEscape<'w, W: ?Sized>(pub(crate) &'w mut W);
+
+impl<'w, W> fmt::Write for DotEscape<'w, W>
+where
+    W: fmt::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c == '"' || c == '\\' {
+                self.0.write_char('\\')?;
+            }
+            self.0.write_char(c)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the entries of a tree in order, or in reverse order
+pub(crate) struct InOrder<'a, E, A = ()> {
+    stack: [Option<&'a Node<'a, E, A>>; MAX_HEIGHT],
+    len: usize,
+    rev: bool,
+}
+
+impl<'a, E, A> InOrder<'a, E, A> {
+    pub(crate) fn new(root: Option<&'a Node<'a, E, A>>) -> Self {
+        InOrder::with_direction(root, false)
+    }
+    pub(crate) fn rev(root: Option<&'a Node<'a, E, A>>) -> Self {
+        InOrder::with_direction(root, true)
+    }
+    /// Start at the first entry for which `before` returns false
+    ///
+    /// `before` must return true for some prefix of the entries in order and
+    /// false for the rest.
+    pub(crate) fn lower_bound<P>(mut node: Option<&'a Node<'a, E, A>>, before: P) -> Self
+    where
+        P: Fn(&E) -> bool,
+    {
+        let mut iter = InOrder::with_direction(None, false);
+        while let Some(curr) = node {
+            if before(curr.entry) {
+                node = curr.right;
+            } else {
+                iter.stack[iter.len] = Some(curr);
+                iter.len += 1;
+                node = curr.left;
+            }
+        }
+        iter
+    }
+    fn with_direction(root: Option<&'a Node<'a, E, A>>, rev: bool) -> Self {
+        let mut iter = InOrder {
+            stack: [None; MAX_HEIGHT],
+            len: 0,
+            rev,
+        };
+        iter.push_near(root);
+        iter
+    }
+    /// Push a node and its descendants on the side that comes first
+    fn push_near(&mut self, mut node: Option<&'a Node<'a, E, A>>) {
+        while let Some(curr) = node {
+            self.stack[self.len] = Some(curr);
+            self.len += 1;
+            node = if self.rev { curr.right } else { curr.left };
+        }
+    }
+}
+
+impl<'a, E, A> Iterator for InOrder<'a, E, A> {
+    type Item = &'a E;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        let node = self.stack[self.len].take()?;
+        self.push_near(if self.rev { node.left } else { node.right });
+        Some(node.entry)
+    }
+}
+
+/// Insert an entry into the tree and call a continuation on the new root
+///
+/// If an equal entry already exists, its node is replaced.
+pub(crate) fn insert<'a, E, A, C, F, R>(
+    root: Option<&'a Node<'a, E, A>>,
+    entry: &'a E,
+    cmp: &C,
+    then: F,
+) -> R
+where
+    A: Augment<E>,
+    C: Fn(&E, &E) -> Ordering,
+    F: FnOnce(&Node<E, A>) -> R,
+{
+    // The buffer is sized by height to cover `insert_room`
+    match height(root) {
+        0..=5 => insert_in::<_, _, _, _, _, 8>(root, entry, cmp, then),
+        6..=9 => insert_in::<_, _, _, _, _, 12>(root, entry, cmp, then),
+        10..=13 => insert_in::<_, _, _, _, _, 16>(root, entry, cmp, then),
+        14..=21 => insert_in::<_, _, _, _, _, 24>(root, entry, cmp, then),
+        _ => insert_in::<_, _, _, _, _, { MAX_HEIGHT + 3 }>(root, entry, cmp, then),
+    }
+}
+
+// Kept out of line so that only the chosen buffer size takes up stack space
+#[inline(never)]
+fn insert_in<'a, E, A, C, F, R, const N: usize>(
+    root: Option<&'a Node<'a, E, A>>,
+    entry: &'a E,
+    cmp: &C,
+    then: F,
+) -> R
+where
+    A: Augment<E>,
+    C: Fn(&E, &E) -> Ordering,
+    F: FnOnce(&Node<E, A>) -> R,
+{
+    let mut buffer = [None; N];
+    let mut free: &mut [Option<Node<E, A>>] = &mut buffer;
+    then(insert_into(root, entry, cmp, &mut free))
+}
+
+/// The unused part of a buffer that copied nodes are placed in
+pub(crate) type Free<'b, 'f, E, A = ()> = &'f mut &'b mut [Option<Node<'b, E, A>>];
+
+/// The most nodes that inserting into a tree can copy
+///
+/// An insertion copies at most one node per level, plus the new leaf and the
+/// two extra nodes of a rotation.
+pub(crate) fn insert_room<E, A>(root: Option<&Node<E, A>>) -> usize {
+    usize::from(height(root)) + 3
+}
+
+/// Insert an entry into the tree, placing the copied nodes in a buffer,
+/// and get the new root
+///
+/// The buffer must have room for [`insert_room`] nodes. If an equal entry
+/// already exists, its node is replaced.
+// Kept out of line so that the search path does not outlive the insertion
+#[inline(never)]
+pub(crate) fn insert_into<'b, E, A, C>(
+    root: Option<&'b Node<'b, E, A>>,
+    entry: &'b E,
+    cmp: &C,
+    free: Free<'b, '_, E, A>,
+) -> &'b Node<'b, E, A>
+where
+    A: Augment<E>,
+    C: Fn(&E, &E) -> Ordering,
+{
+    // Record the search path, with a set bit for each step to the right
+    let mut path = [None; MAX_HEIGHT];
+    let mut rights = 0u64;
+    let mut len = 0;
+    let mut node = root;
+    let mut replaced = None;
+    while let Some(curr) = node {
+        match cmp(entry, curr.entry) {
+            Ordering::Equal => {
+                replaced = Some(curr);
+                break;
+            }
+            Ordering::Less => node = curr.left,
+            Ordering::Greater => {
+                rights |= 1 << len;
+                node = curr.right;
+            }
+        }
+        path[len] = Some(curr);
+        len += 1;
+    }
+    // Copy the path from the bottom up
+    let new = replaced.map_or_else(
+        || Node::new(entry, None, None),
+        |node| Node::new(entry, node.left, node.right),
+    );
+    let mut sub = alloc(free, new);
+    for (i, node) in path[..len].iter().enumerate().rev() {
+        let node = node.expect("recorded path node");
+        sub = if rights & (1 << i) == 0 {
+            balance(node.entry, Some(sub), node.right, free)
+        } else {
+            balance(node.entry, node.left, Some(sub), free)
+        };
+    }
+    sub
+}
+
+/// Remove the entry for which `probe` returns [`Ordering::Equal`] and call a
+/// continuation on the new root
+///
+/// `probe` returns the ordering of the removed value relative to an entry.
+/// If there is no such entry, the continuation gets the tree unchanged.
+pub(crate) fn remove<'a, E, A, P, F, R>(root: Option<&'a Node<'a, E, A>>, probe: P, then: F) -> R
+where
+    A: Augment<E>,
+    P: FnMut(&E) -> Ordering,
+    F: FnOnce(Option<&Node<E, A>>) -> R,
+{
+    // A removal copies at most one node per level, and each of them may be
+    // rotated into three, so the buffer is sized by height
+    match height(root) {
+        0..=5 => remove_in::<_, _, _, _, _, 16>(root, probe, then),
+        6..=9 => remove_in::<_, _, _, _, _, 28>(root, probe, then),
+        10..=13 => remove_in::<_, _, _, _, _, 40>(root, probe, then),
+        _ => remove_in::<_, _, _, _, _, { 3 * MAX_HEIGHT + 1 }>(root, probe, then),
+    }
+}
+
+// Kept out of line so that only the chosen buffer size takes up stack space
+#[inline(never)]
+fn remove_in<'a, E, A, P, F, R, const N: usize>(
+    root: Option<&'a Node<'a, E, A>>,
+    probe: P,
+    then: F,
+) -> R
+where
+    A: Augment<E>,
+    P: FnMut(&E) -> Ordering,
+    F: FnOnce(Option<&Node<E, A>>) -> R,
+{
+    let mut buffer = [None; N];
+    let mut free: &mut [Option<Node<E, A>>] = &mut buffer;
+    then(remove_into(root, probe, &mut free))
+}
+
+/// Remove the entry for which `probe` returns [`Ordering::Equal`], placing
+/// the copied nodes in a buffer, and get the new root
+///
+/// The buffer must have room for three nodes per level of the tree.
+// Kept out of line so that the search path does not outlive the removal
+#[inline(never)]
+fn remove_into<'b, E, A, P>(
+    root: Option<&'b Node<'b, E, A>>,
+    mut probe: P,
+    free: Free<'b, '_, E, A>,
+) -> Option<&'b Node<'b, E, A>>
+where
+    A: Augment<E>,
+    P: FnMut(&E) -> Ordering,
+{
+    // Record the search path, with a set bit for each step to the right
+    let mut path = [None; MAX_HEIGHT];
+    let mut rights = 0u64;
+    let mut len = 0;
+    let mut node = root;
+    let removed = loop {
+        let curr = node?;
+        match probe(curr.entry) {
+            Ordering::Equal => break curr,
+            Ordering::Less => node = curr.left,
+            Ordering::Greater => {
+                rights |= 1 << len;
+                node = curr.right;
+            }
+        }
+        path[len] = Some(curr);
+        len += 1;
+    };
+    // Join the removed node's subtrees, moving up its successor if it has both
+    let mut sub = match (removed.left, removed.right) {
+        (left, None) => left,
+        (None, right) => right,
+        (Some(left), Some(right)) => {
+            let mut spine = [None; MAX_HEIGHT];
+            let mut spine_len = 0;
+            let mut successor = right;
+            while let Some(next) = successor.left {
+                spine[spine_len] = Some(successor);
+                spine_len += 1;
+                successor = next;
+            }
+            let mut rest = successor.right;
+            for node in spine[..spine_len].iter().rev() {
+                let node = node.expect("recorded spine node");
+                rest = Some(balance(node.entry, rest, node.right, free));
+            }
+            Some(balance(successor.entry, Some(left), rest, free))
+        }
+    };
+    // Copy the path from the bottom up
+    for (i, node) in path[..len].iter().enumerate().rev() {
+        let node = node.expect("recorded path node");
+        sub = Some(if rights & (1 << i) == 0 {
+            balance(node.entry, sub, node.right, free)
+        } else {
+            balance(node.entry, node.left, sub, free)
+        });
+    }
+    sub
+}
+
+/// Build a balanced tree from entries in order, placing the nodes in a buffer
+///
+/// The entries must all be `Some`, and the buffer must have room for a node per entry.
+pub(crate) fn build<'b, E, A>(
+    entries: &[Option<&'b E>],
+    free: Free<'b, '_, E, A>,
+) -> Option<&'b Node<'b, E, A>>
+where
+    A: Augment<E>,
+{
+    if entries.is_empty() {
+        return None;
+    }
+    let mid = entries.len() / 2;
+    let left = build(&entries[..mid], free);
+    let right = build(&entries[mid + 1..], free);
+    let entry = entries[mid].expect("entry to build from");
+    Some(alloc(free, Node::new(entry, left, right)))
+}
+
+/// Move a node into the buffer
+fn alloc<'b, E, A>(free: Free<'b, '_, E, A>, node: Node<'b, E, A>) -> &'b Node<'b, E, A> {
+    let (slot, rest) = core::mem::take(free)
+        .split_first_mut()
+        .expect("tree insertion buffer is full");
+    *free = rest;
+    Option::insert(slot, node)
+}
+
+/// Join two subtrees under an entry, rotating if their heights differ by more than one
+fn balance<'b, E, A>(
+    entry: &'b E,
+    left: Option<&'b Node<'b, E, A>>,
+    right: Option<&'b Node<'b, E, A>>,
+    free: Free<'b, '_, E, A>,
+) -> &'b Node<'b, E, A>
+where
+    A: Augment<E>,
+{
+    match (left, right) {
+        (Some(l), _) if l.height > height(right) + 1 => {
+            if height(l.left) >= height(l.right) {
+                let new_right = alloc(free, Node::new(entry, l.right, right));
+                alloc(free, Node::new(l.entry, l.left, Some(new_right)))
+            } else {
+                let lr = l.right.expect("left-right child of a left-heavy node");
+                let new_left = alloc(free, Node::new(l.entry, l.left, lr.left));
+                let new_right = alloc(free, Node::new(entry, lr.right, right));
+                alloc(free, Node::new(lr.entry, Some(new_left), Some(new_right)))
+            }
+        }
+        (_, Some(r)) if r.height > height(left) + 1 => {
+            if height(r.right) >= height(r.left) {
+                let new_left = alloc(free, Node::new(entry, left, r.left));
+                alloc(free, Node::new(r.entry, Some(new_left), r.right))
+            } else {
+                let rl = r.left.expect("right-left child of a right-heavy node");
+                let new_left = alloc(free, Node::new(entry, left, rl.left));
+                let new_right = alloc(free, Node::new(r.entry, rl.right, r.right));
+                alloc(free, Node::new(rl.entry, Some(new_left), Some(new_right)))
+            }
+        }
+        _ => alloc(free, Node::new(entry, left, right)),
+    }
+}
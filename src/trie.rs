@@ -0,0 +1,322 @@
+//! A growable map with byte string keys where all items exist on the stack
+//!
+//! A [`Trie`] node holds the value for the key spelled by the path to it,
+//! if there is one, and its children in a persistent balanced search tree
+//! ordered by byte. Inserting a key copies one node per byte of the key.
+
+use core::{fmt, iter::FusedIterator};
+
+use crate::tree::{self, Node};
+
+/// A growable map with byte string keys where all items exist on the stack
+///
+/// Keys can be any `AsRef<[u8]>` type, such as [`str`](prim@str) or `[u8]`,
+/// and are borrowed for the lifetime of the trie. As with [`Map`](crate::Map),
+/// the trie with an inserted key cannot be accessed from the same scope. Instead,
+/// a continuation function is called on the new trie, and its result is returned
+/// to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Trie;
+///
+/// let routes = [("sensors/", 1), ("sensors/temp", 2), ("sensors/temp/raw", 3)];
+///
+/// Trie::collect(routes, |router| {
+///     assert_eq!(router.get("sensors/temp"), Some(&2));
+///     assert_eq!(
+///         router.longest_prefix("sensors/temp/avg"),
+///         Some((&b"sensors/temp"[..], &2))
+///     );
+///     assert!(router.iter_prefix("sensors/temp").map(|(_, v)| v).eq(&[2, 3]));
+/// });
+/// ```
+pub struct Trie<'a, V> {
+    root: Option<&'a TrieNode<'a, V>>,
+    len: usize,
+}
+
+struct TrieNode<'a, V> {
+    /// The last byte of the path to this node
+    byte: u8,
+    /// The key spelled by the path to this node and its value, if it has one
+    entry: Option<(&'a [u8], &'a V)>,
+    children: Option<&'a TrieTree<'a, V>>,
+}
+
+type TrieTree<'a, V> = Node<'a, TrieNode<'a, V>>;
+
+type InsertThen<'t, V, R> = dyn FnMut(&TrieNode<V>) -> R + 't;
+
+impl<'a, V> Trie<'a, V> {
+    /// Create a new trie
+    pub fn new() -> Self {
+        Trie::default()
+    }
+    /// Check if the trie is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of keys in the trie
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the node for a key, whether or not it has a value
+    ///
+    /// This is an **O(m)** operation for a key of length m.
+    fn node(&self, key: &[u8]) -> Option<&'a TrieNode<'a, V>> {
+        key.iter()
+            .try_fold(self.root?, |node, &byte| child(node, byte))
+    }
+    /// Check if the trie contains a key
+    ///
+    /// This is an **O(m)** operation for a key of length m.
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+    /// Get the value corresponding to a key
+    ///
+    /// This is an **O(m)** operation for a key of length m.
+    pub fn get<K>(&self, key: &K) -> Option<&'a V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        Some(self.node(key.as_ref())?.entry?.1)
+    }
+    /// Get the longest key in the trie that is a prefix of a key, and its value
+    ///
+    /// This is an **O(m)** operation for a key of length m.
+    pub fn longest_prefix<K>(&self, key: &K) -> Option<(&'a [u8], &'a V)>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let mut node = self.root?;
+        let mut found = node.entry;
+        for &byte in key.as_ref() {
+            node = match child(node, byte) {
+                Some(node) => node,
+                None => break,
+            };
+            found = node.entry.or(found);
+        }
+        found
+    }
+    /// Get an iterator over the keys and values of the trie in lexicographic order
+    ///
+    /// Each step is an **O(m)** operation for keys of length m.
+    pub fn iter(&self) -> IterPrefix<'a, V> {
+        self.iter_prefix(&[])
+    }
+    /// Get an iterator over the keys that start with a prefix and their values
+    /// in lexicographic order
+    ///
+    /// Each step is an **O(m)** operation for keys of length m.
+    pub fn iter_prefix<K>(&self, prefix: &K) -> IterPrefix<'a, V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let prefix = prefix.as_ref();
+        IterPrefix {
+            root: self.root,
+            depth: prefix.len(),
+            next: self.node(prefix).and_then(first_entry),
+        }
+    }
+    /// Insert a key-value pair into the trie, call a continuation on the
+    /// new trie, and return its result
+    ///
+    /// If the key is already in the trie, its value is replaced.
+    /// This is an **O(m)** operation for a key of length m.
+    pub fn insert<K, F, R>(&self, key: &'a K, value: V, then: F) -> R
+    where
+        K: AsRef<[u8]> + ?Sized,
+        F: FnOnce(&Trie<V>) -> R,
+    {
+        let key = key.as_ref();
+        let len = self.len + usize::from(!self.contains_key(key));
+        let mut then = Some(then);
+        insert_at(self.root, 0, key, (key, &value), &mut |root| {
+            let then = then.take().expect("trie insertion continues once");
+            then(&Trie {
+                root: Some(root),
+                len,
+            })
+        })
+    }
+    /// Collect an iterator into a trie and call a continuation function on it
+    pub fn collect<'k, K, I, F, R>(iter: I, then: F) -> R
+    where
+        'k: 'a,
+        K: AsRef<[u8]> + ?Sized + 'k,
+        I: IntoIterator<Item = (&'k K, V)>,
+        F: FnOnce(&Trie<V>) -> R,
+    {
+        Trie::default().extend(iter, then)
+    }
+    /// Extend the trie with an iterator and call a continuation function on it
+    pub fn extend<'k, K, I, F, R>(&self, iter: I, then: F) -> R
+    where
+        'k: 'a,
+        K: AsRef<[u8]> + ?Sized + 'k,
+        I: IntoIterator<Item = (&'k K, V)>,
+        F: FnOnce(&Trie<V>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some((key, value)) = iter.next() {
+            self.insert(key, value, |trie| trie.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// Get the child of a node for a byte
+fn child<'a, V>(node: &TrieNode<'a, V>, byte: u8) -> Option<&'a TrieNode<'a, V>> {
+    Some(tree::find(node.children, |child| byte.cmp(&child.byte))?.entry)
+}
+
+/// Get the child of a node with the least byte greater than a byte
+fn next_child<'a, V>(node: &TrieNode<'a, V>, byte: u8) -> Option<&'a TrieNode<'a, V>> {
+    tree::lower_bound(node.children, |child| child.byte <= byte)
+}
+
+/// Get the least entry in the subtrie of a node
+fn first_entry<'a, V>(mut node: &'a TrieNode<'a, V>) -> Option<(&'a [u8], &'a V)> {
+    loop {
+        if let Some(entry) = node.entry {
+            return Some(entry);
+        }
+        node = tree::lower_bound(node.children, |_| false)?;
+    }
+}
+
+/// Copy a node with a key inserted below it and call a continuation on the copy
+///
+/// `rest` is the part of the key below the node.
+fn insert_at<V, R>(
+    old: Option<&TrieNode<V>>,
+    byte: u8,
+    rest: &[u8],
+    entry: (&[u8], &V),
+    then: &mut InsertThen<V, R>,
+) -> R {
+    let children = old.and_then(|old| old.children);
+    let (&next, rest) = match rest.split_first() {
+        Some(split) => split,
+        None => {
+            return then(&TrieNode {
+                byte,
+                entry: Some(entry),
+                children,
+            })
+        }
+    };
+    let by_byte = |a: &TrieNode<V>, b: &TrieNode<V>| a.byte.cmp(&b.byte);
+    let old_child = old.and_then(|old| child(old, next));
+    insert_at(old_child, next, rest, entry, &mut |new_child| {
+        tree::insert(children, new_child, &by_byte, |children| {
+            then(&TrieNode {
+                byte,
+                entry: old.and_then(|old| old.entry),
+                children: Some(children),
+            })
+        })
+    })
+}
+
+/// An iterator over the keys and values of a [`Trie`] that start with a prefix
+///
+/// This is returned by [`Trie::iter_prefix`] and [`Trie::iter`].
+pub struct IterPrefix<'a, V> {
+    root: Option<&'a TrieNode<'a, V>>,
+    /// The length of the prefix
+    depth: usize,
+    next: Option<(&'a [u8], &'a V)>,
+}
+
+impl<'a, V> IterPrefix<'a, V> {
+    /// Find the least entry with a key greater than a key that starts with the prefix
+    fn successor(&self, key: &[u8]) -> Option<(&'a [u8], &'a V)> {
+        // Walk down the key, keeping the deepest branch to a greater key
+        let mut node = self.root?;
+        let mut branch = None;
+        for (i, &byte) in key.iter().enumerate() {
+            if i >= self.depth {
+                branch = next_child(node, byte).or(branch);
+            }
+            node = child(node, byte).expect("yielded key is in the trie");
+        }
+        // Keys below the node come before keys on later branches
+        let below = tree::lower_bound(node.children, |_| false);
+        first_entry(below.or(branch)?)
+    }
+}
+
+impl<'a, V> Iterator for IterPrefix<'a, V> {
+    type Item = (&'a [u8], &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.next?;
+        self.next = self.successor(entry.0);
+        Some(entry)
+    }
+}
+
+impl<'a, V> FusedIterator for IterPrefix<'a, V> {}
+
+impl<'a, V> Default for Trie<'a, V> {
+    fn default() -> Self {
+        Trie { root: None, len: 0 }
+    }
+}
+
+impl<'a, V> Clone for Trie<'a, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, V> Copy for Trie<'a, V> {}
+
+impl<'a, V> fmt::Debug for Trie<'a, V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn trie_prefixes() {
+    let words = ["tea", "ten", "to", "inn", "in", "i", "tea", ""];
+    Trie::collect(words.iter().copied().zip(0..), |trie| {
+        assert_eq!(trie.len(), 7);
+        assert_eq!(trie.get("tea"), Some(&6));
+        assert_eq!(trie.get("te"), None);
+        assert_eq!(trie.get(""), Some(&7));
+        assert!(trie.iter().map(|(k, _)| k).eq([
+            &b""[..],
+            b"i",
+            b"in",
+            b"inn",
+            b"tea",
+            b"ten",
+            b"to"
+        ]));
+        assert!(trie.iter_prefix("te").map(|(_, &v)| v).eq([6, 1]));
+        assert!(trie.iter_prefix("inn").map(|(_, &v)| v).eq([3]));
+        assert_eq!(trie.iter_prefix("x").next(), None);
+        assert_eq!(trie.longest_prefix("innate"), Some((&b"inn"[..], &3)));
+        assert_eq!(trie.longest_prefix("it"), Some((&b"i"[..], &5)));
+        assert_eq!(trie.longest_prefix("xyz"), Some((&b""[..], &7)));
+        trie.insert(&[0xff, 0], 8, |trie| {
+            assert_eq!(trie.iter().last(), Some((&[0xff, 0][..], &8)));
+        });
+    });
+    assert_eq!(Trie::<u8>::new().longest_prefix("a"), None);
+}
@@ -0,0 +1,321 @@
+//! A growable collection of disjoint sets where all items exist on the stack
+//!
+//! A [`UnionFind`] is a [`Map`] from each item to either its parent item or,
+//! for the representative of a set, the size of the set. Joining two sets
+//! links the representative of the smaller set to that of the larger one,
+//! so every item is at most **O(logn)** links from its representative.
+
+use core::fmt;
+
+use crate::{map, Map};
+
+/// A growable collection of disjoint sets where all items exist on the stack
+///
+/// As with [`Map`], the collection with an added item or joined sets cannot be
+/// accessed from the same scope. Instead, a continuation function is called on
+/// the new collection, and its result is returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::UnionFind;
+///
+/// let links = [("a", "b"), ("c", "d"), ("b", "e")];
+///
+/// UnionFind::collect(["a", "b", "c", "d", "e", "f"], |sensors| {
+///     sensors.union_all(links, |sensors| {
+///         assert_eq!(sensors.set_count(), 3);
+///         assert!(sensors.same_set(&"a", &"e"));
+///         assert!(!sensors.same_set(&"a", &"d"));
+///         assert_eq!(sensors.set_size(&"e"), 3);
+///     })
+/// });
+/// ```
+pub struct UnionFind<'a, T> {
+    links: Map<'a, T, Link<'a, T>>,
+    len: usize,
+    set_count: usize,
+}
+
+enum Link<'a, T> {
+    /// The item represents a set of this size
+    Root(usize),
+    /// The item is in the same set as this item
+    Parent(&'a T),
+}
+
+impl<'a, T> UnionFind<'a, T> {
+    /// Create a new collection
+    pub fn new() -> Self {
+        UnionFind::default()
+    }
+    /// Check if the collection is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Get the number of items in the collection
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Get the number of disjoint sets in the collection
+    ///
+    /// This is an **O(1)** operation.
+    pub fn set_count(&self) -> usize {
+        self.set_count
+    }
+}
+
+impl<'a, T> UnionFind<'a, T>
+where
+    T: PartialOrd,
+{
+    /// Check if the collection contains an item
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn contains(&self, item: &T) -> bool {
+        self.links.contains_key(item)
+    }
+    /// Get the representative of an item's set and the size of the set
+    fn root(&self, item: &T) -> Option<(&'a T, usize)> {
+        let mut entry = self.links.get_key_value(item)?;
+        loop {
+            match entry {
+                (root, Link::Root(size)) => return Some((root, *size)),
+                (_, Link::Parent(parent)) => entry = self.links.get_key_value(*parent)?,
+            }
+        }
+    }
+    /// Get the representative of the set an item is in
+    ///
+    /// Two items are in the same set if they have the same representative.
+    /// This is an **O(log²n)** operation.
+    pub fn find(&self, item: &T) -> Option<&'a T> {
+        Some(self.root(item)?.0)
+    }
+    /// Check if two items are in the same set
+    ///
+    /// This is an **O(log²n)** operation.
+    pub fn same_set(&self, a: &T, b: &T) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+    /// Get the number of items in the set an item is in, or 0 if it is not
+    /// in the collection
+    ///
+    /// This is an **O(log²n)** operation.
+    pub fn set_size(&self, item: &T) -> usize {
+        self.root(item).map_or(0, |(_, size)| size)
+    }
+    /// Get an iterator over the items of the collection in ascending order
+    ///
+    /// Each step is an **O(logn)** operation.
+    pub fn items(&self) -> Items<'a, T> {
+        Items {
+            cursor: self.links.cursor(),
+        }
+    }
+    /// Add an item to the collection in a set of its own if it is not already
+    /// in it, call a continuation on the new (or old) collection, and return
+    /// its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn insert<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        if self.contains(&item) {
+            return then(self);
+        }
+        self.links.insert(item, Link::Root(1), |links| {
+            then(&UnionFind {
+                links: *links,
+                len: self.len + 1,
+                set_count: self.set_count + 1,
+            })
+        })
+    }
+    /// Join the sets of two items, adding the items if they are not already in
+    /// the collection, call a continuation on the new collection, and return
+    /// its result
+    ///
+    /// This is an **O(log²n)** operation.
+    pub fn union<F, R>(&self, a: T, b: T, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        self.insert(a.clone(), |sets| {
+            sets.insert(b.clone(), |sets| sets.link(&a, &b, then))
+        })
+    }
+    /// Link the representative of the smaller of two items' sets to that of
+    /// the larger one
+    fn link<F, R>(&self, a: &T, b: &T, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        let (a, a_size) = self.root(a).expect("linked item is in the collection");
+        let (b, b_size) = self.root(b).expect("linked item is in the collection");
+        if a == b {
+            return then(self);
+        }
+        let (large, small) = if a_size >= b_size { (a, b) } else { (b, a) };
+        self.links
+            .insert(small.clone(), Link::Parent(large), |links| {
+                links.insert(large.clone(), Link::Root(a_size + b_size), |links| {
+                    then(&UnionFind {
+                        links: *links,
+                        len: self.len,
+                        set_count: self.set_count - 1,
+                    })
+                })
+            })
+    }
+    /// Link every item between an item and its representative directly to the
+    /// representative, call a continuation on the new collection, and return
+    /// its result
+    ///
+    /// This makes later lookups of those items **O(logn)** operations.
+    /// It uses stack space for each relinked item.
+    pub fn compress<F, R>(&self, item: &T, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        match self.find(item) {
+            Some(root) => self.compress_to(item, root, then),
+            None => then(self),
+        }
+    }
+    fn compress_to<F, R>(&self, item: &T, root: &'a T, then: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        match self.links.get_key_value(item) {
+            Some((item, &Link::Parent(parent))) if parent != root => {
+                self.links
+                    .insert(item.clone(), Link::Parent(root), |links| {
+                        UnionFind {
+                            links: *links,
+                            ..*self
+                        }
+                        .compress_to(parent, root, then)
+                    })
+            }
+            _ => then(self),
+        }
+    }
+    /// Join the sets of the pairs of items of an iterator, call a continuation
+    /// on the new collection, and return its result
+    pub fn union_all<I, F, R>(&self, pairs: I, then: F) -> R
+    where
+        T: Clone,
+        I: IntoIterator<Item = (T, T)>,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        let mut pairs = pairs.into_iter();
+        if let Some((a, b)) = pairs.next() {
+            self.union(a, b, |sets| sets.union_all(pairs, then))
+        } else {
+            then(self)
+        }
+    }
+    /// Collect an iterator into a collection of sets of one item each and
+    /// call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        UnionFind::default().extend(iter, then)
+    }
+    /// Add the items of an iterator to the collection in sets of their own
+    /// and call a continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&UnionFind<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.insert(item, |sets| sets.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the items of a [`UnionFind`] in ascending order
+///
+/// This is returned by [`UnionFind::items`].
+pub struct Items<'a, T> {
+    cursor: map::MapCursor<'a, T, Link<'a, T>>,
+}
+
+impl<'a, T> Iterator for Items<'a, T>
+where
+    T: PartialOrd,
+{
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (item, _) = self.cursor.peek()?;
+        self.cursor.next_key();
+        Some(item)
+    }
+}
+
+impl<'a, T> Default for UnionFind<'a, T> {
+    fn default() -> Self {
+        UnionFind {
+            links: Map::default(),
+            len: 0,
+            set_count: 0,
+        }
+    }
+}
+
+impl<'a, T> Clone for UnionFind<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for UnionFind<'a, T> {}
+
+impl<'a, T> fmt::Debug for UnionFind<'a, T>
+where
+    T: PartialOrd + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.items().map(|item| (item, self.find(item))))
+            .finish()
+    }
+}
+
+#[test]
+fn union_find_components() {
+    let edges = (0..32)
+        .map(|i| (i, (i * 7 + 3) % 32))
+        .filter(|(i, _)| i % 4 != 0);
+    UnionFind::new().union_all(edges.clone(), |sets| {
+        assert_eq!(sets.len(), 32);
+        assert!(edges.clone().all(|(a, b)| sets.same_set(&a, &b)));
+        let sizes: usize = sets
+            .items()
+            .filter(|item| sets.find(item) == Some(*item))
+            .map(|root| sets.set_size(root))
+            .sum();
+        assert_eq!(sizes, 32);
+        sets.compress(&31, |compressed| {
+            assert_eq!(compressed.set_count(), sets.set_count());
+            assert!((0..32).all(|i| compressed.find(&i) == sets.find(&i)));
+        });
+        assert!(!sets.same_set(&0, &32));
+    });
+}
@@ -0,0 +1,214 @@
+//! A growable, indexable sequence where all items exist on the stack
+//!
+//! A [`Vector`] keeps its items in a balanced tree ordered by position, in
+//! which every subtree knows its size, so items can be found by index.
+
+use core::{cmp::Ordering, fmt, iter::FusedIterator, ops::Index};
+
+use crate::tree::{self, InOrder, Node};
+
+/// A growable sequence with **O(logn)** indexing where all items exist on the stack
+///
+/// When using [`Vector::push_back`] or [`Vector::push_front`], the new vector
+/// with the pushed item cannot be accessed from the same scope. Instead,
+/// a continuation function is called on the new vector, and its result is
+/// returned to the calling scope.
+///
+/// # Example
+/// ```
+/// use nolloc::Vector;
+///
+/// Vector::collect("stack".chars(), |letters| {
+///     assert_eq!(letters.get(2), Some(&'a'));
+///     letters.push_front('a', |letters| {
+///         assert!(letters.iter().eq(&['a', 's', 't', 'a', 'c', 'k']));
+///         assert_eq!(letters[5], 'k');
+///     });
+/// });
+/// ```
+pub struct Vector<'a, T> {
+    root: Option<&'a Node<'a, T>>,
+}
+
+impl<'a, T> Vector<'a, T> {
+    /// Create a new vector
+    pub fn new() -> Self {
+        Vector::default()
+    }
+    /// Check if the vector is empty
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+    /// Get the vector's length
+    ///
+    /// This is an **O(1)** operation.
+    pub fn len(&self) -> usize {
+        tree::size(self.root)
+    }
+    /// Get the item at an index
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        tree::nth(self.root, index)
+    }
+    /// Get the first item in the vector
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn first(&self) -> Option<&'a T> {
+        self.get(0)
+    }
+    /// Get the last item in the vector
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn last(&self) -> Option<&'a T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+    /// Get an iterator over the items of the vector in order
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            nodes: InOrder::new(self.root),
+            len: self.len(),
+        }
+    }
+    /// Push an item onto the back of the vector, call a continuation on the
+    /// new vector, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn push_back<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Vector<T>) -> R,
+    {
+        self.push(item, Ordering::Greater, then)
+    }
+    /// Push an item onto the front of the vector, call a continuation on the
+    /// new vector, and return its result
+    ///
+    /// This is an **O(logn)** operation.
+    pub fn push_front<F, R>(&self, item: T, then: F) -> R
+    where
+        F: FnOnce(&Vector<T>) -> R,
+    {
+        self.push(item, Ordering::Less, then)
+    }
+    /// Insert an item that compares as `side` to every existing item
+    fn push<F, R>(&self, item: T, side: Ordering, then: F) -> R
+    where
+        F: FnOnce(&Vector<T>) -> R,
+    {
+        tree::insert(self.root, &item, &|_, _| side, |root| {
+            then(&Vector { root: Some(root) })
+        })
+    }
+    /// Collect an iterator into a vector and call a continuation function on it
+    pub fn collect<I, F, R>(iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Vector<T>) -> R,
+    {
+        Vector::default().extend(iter, then)
+    }
+    /// Push the items of an iterator onto the back of the vector and call a
+    /// continuation function on it
+    pub fn extend<I, F, R>(&self, iter: I, then: F) -> R
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&Vector<T>) -> R,
+    {
+        let mut iter = iter.into_iter();
+        if let Some(item) = iter.next() {
+            self.push_back(item, |vector| vector.extend(iter, then))
+        } else {
+            then(self)
+        }
+    }
+}
+
+/// An iterator over the items of a [`Vector`] in order
+///
+/// This is returned by [`Vector::iter`].
+pub struct Iter<'a, T> {
+    nodes: InOrder<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.nodes.next()?;
+        self.len -= 1;
+        Some(item)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> IntoIterator for &Vector<'a, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Index<usize> for Vector<'a, T> {
+    type Output = T;
+    #[track_caller]
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> Default for Vector<'a, T> {
+    fn default() -> Self {
+        Vector { root: None }
+    }
+}
+
+impl<'a, T> Clone for Vector<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Vector<'a, T> {}
+
+impl<'a, 'b, T, U> PartialEq<Vector<'b, U>> for Vector<'a, T>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &Vector<'b, U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a == b)
+    }
+}
+
+impl<'a, T> Eq for Vector<'a, T> where T: Eq {}
+
+impl<'a, T> fmt::Debug for Vector<'a, T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn vector_indexing() {
+    Vector::collect(0..100, |numbers| {
+        assert_eq!(numbers.len(), 100);
+        assert!((0..100).all(|i| numbers[i] == i));
+        assert!(tree::is_balanced(numbers.root));
+        assert_eq!(numbers.get(100), None);
+        numbers.push_front(usize::MAX, |pushed| {
+            assert_eq!(pushed.first(), Some(&usize::MAX));
+            assert_eq!(pushed.last(), Some(&99));
+            assert!(pushed.iter().skip(1).eq(numbers));
+            assert_ne!(*pushed, *numbers);
+        });
+    });
+}
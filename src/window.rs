@@ -0,0 +1,166 @@
+//! Minimum and maximum over a sliding window of an iterator
+//!
+//! The extremes are found with a monotonic deque: a [`RingBuffer`] of the
+//! items that could still be the extreme of some window, kept in order of
+//! both position and value. Each item is pushed and popped at most once, so
+//! each window's extreme is found in **O(1)** amortized time.
+//!
+//! The buffer's capacity is a const parameter, and the window size can be any
+//! length up to it. The persistent [`Deque`](crate::Deque) is not used,
+//! because each of its pushes needs a new stack frame, which an iterator
+//! cannot keep between items.
+
+use core::iter::FusedIterator;
+
+use crate::RingBuffer;
+
+/// Get an iterator over the minimum of each window of `window` items
+///
+/// Returns `None` if `window` is 0 or greater than `N`. See [`window_max`].
+pub fn window_min<I, const N: usize>(
+    iter: I,
+    window: usize,
+) -> Option<WindowExtremes<I::IntoIter, N>>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd + Clone,
+{
+    WindowExtremes::new(iter.into_iter(), window, false)
+}
+
+/// Get an iterator over the maximum of each window of `window` items
+///
+/// An extreme is yielded for every item, of the window ending at that item.
+/// The first `window - 1` windows are shorter, since they start at the first
+/// item. `N` is the capacity of the buffer of candidate items.
+///
+/// Returns `None` if `window` is 0 or greater than `N`.
+///
+/// # Example
+/// ```
+/// use nolloc::window::window_max;
+///
+/// let samples = [0.1, 0.8, -0.9, 0.3, 0.2, -0.1, 0.0];
+/// let magnitudes = samples.iter().map(|s: &f32| s.abs());
+///
+/// let envelope = window_max::<_, 4>(magnitudes, 3).unwrap();
+/// assert!(envelope.eq([0.1, 0.8, 0.9, 0.9, 0.9, 0.3, 0.2]));
+///
+/// assert!(window_max::<_, 4>(samples, 5).is_none());
+/// ```
+pub fn window_max<I, const N: usize>(
+    iter: I,
+    window: usize,
+) -> Option<WindowExtremes<I::IntoIter, N>>
+where
+    I: IntoIterator,
+    I::Item: PartialOrd + Clone,
+{
+    WindowExtremes::new(iter.into_iter(), window, true)
+}
+
+/// An iterator over the minimum or maximum of each window of an iterator
+///
+/// This is returned by [`window_min`] and [`window_max`].
+pub struct WindowExtremes<I, const N: usize>
+where
+    I: Iterator,
+{
+    iter: I,
+    window: usize,
+    /// The positions and values of items that may be the extreme of this or a
+    /// later window, with the current extreme at the front
+    candidates: RingBuffer<(usize, I::Item), N>,
+    position: usize,
+    max: bool,
+}
+
+impl<I, const N: usize> WindowExtremes<I, N>
+where
+    I: Iterator,
+    I::Item: PartialOrd + Clone,
+{
+    fn new(iter: I, window: usize, max: bool) -> Option<Self> {
+        if window == 0 || window > N {
+            return None;
+        }
+        Some(WindowExtremes {
+            iter,
+            window,
+            candidates: RingBuffer::new(),
+            position: 0,
+            max,
+        })
+    }
+}
+
+impl<I, const N: usize> Iterator for WindowExtremes<I, N>
+where
+    I: Iterator,
+    I::Item: PartialOrd + Clone,
+{
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        // Earlier items that are no more extreme than this one can never be
+        // the extreme again
+        while let Some((_, back)) = self.candidates.back() {
+            let dominated = if self.max {
+                item >= *back
+            } else {
+                item <= *back
+            };
+            if !dominated {
+                break;
+            }
+            self.candidates.pop_back();
+        }
+        if let Some(&(start, _)) = self.candidates.front() {
+            if start + self.window <= self.position {
+                self.candidates.pop_front();
+            }
+        }
+        self.candidates
+            .try_push((self.position, item))
+            .unwrap_or_else(|_| unreachable!("candidates are all in the window"));
+        self.position += 1;
+        let (_, extreme) = self.candidates.front().expect("item was just pushed");
+        Some(extreme.clone())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, const N: usize> FusedIterator for WindowExtremes<I, N>
+where
+    I: FusedIterator,
+    I::Item: PartialOrd + Clone,
+{
+}
+
+#[test]
+fn window_extremes() {
+    let items = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3];
+    // Check against a brute-force scan of every window
+    for window in 1..=4 {
+        let brute = |i: usize, max: bool| {
+            let start = (i + 1).saturating_sub(window);
+            let items = items[start..=i].iter().copied();
+            if max {
+                items.max()
+            } else {
+                items.min()
+            }
+        };
+        let mins = window_min::<_, 4>(items.iter().copied(), window).unwrap();
+        assert!(mins.eq((0..items.len()).map(|i| brute(i, false).unwrap())));
+        let maxes = window_max::<_, 4>(items.iter().copied(), window).unwrap();
+        assert!(maxes.eq((0..items.len()).map(|i| brute(i, true).unwrap())));
+    }
+    let mins = window_min::<_, 1>(items.iter().copied(), 1).unwrap();
+    assert!(mins.eq(items.iter().copied()));
+    assert_eq!(window_max::<_, 2>(None::<i32>, 2).unwrap().next(), None);
+    assert!(window_min::<_, 4>(items, 0).is_none());
+    assert!(window_max::<_, 4>(items, 5).is_none());
+}